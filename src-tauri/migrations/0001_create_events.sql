@@ -0,0 +1,8 @@
+CREATE TABLE IF NOT EXISTS events (
+  id INTEGER PRIMARY KEY AUTOINCREMENT,
+  timestamp TEXT NOT NULL,
+  event_type TEXT NOT NULL,
+  text_hash TEXT NOT NULL,
+  source TEXT,
+  span_length INTEGER
+);
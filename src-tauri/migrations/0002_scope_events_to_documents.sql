@@ -0,0 +1,10 @@
+CREATE TABLE IF NOT EXISTS documents (
+  id INTEGER PRIMARY KEY AUTOINCREMENT,
+  title TEXT NOT NULL,
+  created_at TEXT NOT NULL
+);
+
+-- Rows logged before documents existed keep a NULL document_id
+ALTER TABLE events ADD COLUMN document_id INTEGER REFERENCES documents(id);
+
+CREATE INDEX IF NOT EXISTS idx_events_document ON events (document_id, timestamp);
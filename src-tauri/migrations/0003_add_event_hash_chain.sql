@@ -0,0 +1,2 @@
+ALTER TABLE events ADD COLUMN prev_hash TEXT;
+ALTER TABLE events ADD COLUMN row_hash TEXT;
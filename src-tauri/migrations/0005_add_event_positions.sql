@@ -0,0 +1,3 @@
+ALTER TABLE events ADD COLUMN operation TEXT NOT NULL DEFAULT 'insert';
+ALTER TABLE events ADD COLUMN position_from INTEGER;
+ALTER TABLE events ADD COLUMN position_to INTEGER;
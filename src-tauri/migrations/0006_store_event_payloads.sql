@@ -0,0 +1,5 @@
+-- Payload text makes the log replayable; rows logged earlier keep NULL and cannot be replayed
+ALTER TABLE events ADD COLUMN text TEXT;
+
+-- SHA-256 of the document's plain text, recorded when a manifest is generated
+ALTER TABLE documents ADD COLUMN text_hash TEXT;
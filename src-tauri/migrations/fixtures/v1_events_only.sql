@@ -0,0 +1,25 @@
+-- Schema shipped in 0.1.0: one global events table, created by tauri-plugin-sql (version 1)
+-- or by the inline CREATE TABLE in Database::new.
+CREATE TABLE _sqlx_migrations (
+  version BIGINT PRIMARY KEY,
+  description TEXT NOT NULL,
+  installed_on TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  success BOOLEAN NOT NULL,
+  checksum BLOB NOT NULL,
+  execution_time BIGINT NOT NULL
+);
+INSERT INTO _sqlx_migrations (version, description, success, checksum, execution_time)
+VALUES (1, 'create events table', 1, X'00', 0);
+
+CREATE TABLE events (
+  id INTEGER PRIMARY KEY AUTOINCREMENT,
+  timestamp TEXT NOT NULL,
+  event_type TEXT NOT NULL,
+  text_hash TEXT NOT NULL,
+  source TEXT,
+  span_length INTEGER
+);
+INSERT INTO events (timestamp, event_type, text_hash, source, span_length) VALUES
+  ('2025-01-01T09:00:00Z', 'human', 'a591a6d40bf420404a011733cfb7b190d62c65bf0bcda32b57b277d9ad9f146e', 'user', 11),
+  ('2025-01-01T09:01:00Z', 'ai', '2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824', 'gpt-4', 5),
+  ('2025-01-01T09:02:00Z', 'cited', '486ea46224d1bb4fb680f34f7c9ad96a8f24ec88be73ea8e5a6c65260e9cb8a7', NULL, 5);
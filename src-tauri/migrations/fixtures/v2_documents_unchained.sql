@@ -0,0 +1,22 @@
+-- Documents exist but events are not hash-chained yet
+CREATE TABLE documents (
+  id INTEGER PRIMARY KEY AUTOINCREMENT,
+  title TEXT NOT NULL,
+  created_at TEXT NOT NULL
+);
+INSERT INTO documents (title, created_at) VALUES ('Draft', '2025-02-01T08:00:00Z');
+
+CREATE TABLE events (
+  id INTEGER PRIMARY KEY AUTOINCREMENT,
+  document_id INTEGER REFERENCES documents(id),
+  timestamp TEXT NOT NULL,
+  event_type TEXT NOT NULL,
+  text_hash TEXT NOT NULL,
+  source TEXT,
+  span_length INTEGER
+);
+CREATE INDEX idx_events_document ON events (document_id, timestamp);
+INSERT INTO events (document_id, timestamp, event_type, text_hash, source, span_length) VALUES
+  (NULL, '2025-01-31T10:00:00Z', 'human', 'a591a6d40bf420404a011733cfb7b190d62c65bf0bcda32b57b277d9ad9f146e', 'user', 11),
+  (1, '2025-02-01T08:05:00Z', 'human', '2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824', 'user', 5),
+  (1, '2025-02-01T08:06:00Z', 'ai', '486ea46224d1bb4fb680f34f7c9ad96a8f24ec88be73ea8e5a6c65260e9cb8a7', 'gpt-4', 5);
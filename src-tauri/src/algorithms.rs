@@ -0,0 +1,220 @@
+// AIDEV-NOTE: Signature algorithms for the primary manifest signature. A manifest names its algorithm in
+// signature_algorithm (absent means ed25519, as in every manifest written before the field existed), and
+// the verifier picks the implementation from there. Keys and signatures stay base64 in both cases:
+//   ed25519:    32-byte public key, 64-byte signature (RFC 8032)
+//   ecdsa-p256: 33-byte compressed SEC1 public key, 64-byte r||s signature over SHA-256 (FIPS 186-4)
+// Keystore keys can be either; rotations, revocations and co-signatures remain ed25519-only.
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine as _;
+use serde_json::Value;
+
+use crate::crypto_utils::parse_public_key;
+use crate::error::SonnunError;
+use crate::key_formats::{did_key, did_key_p256};
+use crate::keystore::Keystore;
+use crate::SignatureAlgorithm;
+
+pub const SIGNATURE_ALGORITHM_FIELD: &str = "signature_algorithm";
+
+pub trait ManifestSigner {
+    fn algorithm(&self) -> SignatureAlgorithm;
+    fn public_key(&self) -> Result<String, SonnunError>; // base64, as written to the manifest
+    fn sign(&self, message: &[u8]) -> Result<String, SonnunError>; // base64 signature
+}
+
+pub trait SignatureVerifier {
+    fn algorithm(&self) -> SignatureAlgorithm;
+    fn did_key(&self) -> String;
+    // Ok(false) for a well-formed signature that does not match; malformed ones are errors
+    fn verify(&self, message: &[u8], signature_b64: &str) -> Result<bool, SonnunError>;
+}
+
+impl ManifestSigner for ed25519_dalek::SigningKey {
+    fn algorithm(&self) -> SignatureAlgorithm {
+        SignatureAlgorithm::Ed25519
+    }
+
+    fn public_key(&self) -> Result<String, SonnunError> {
+        Ok(BASE64.encode(self.verifying_key().to_bytes()))
+    }
+
+    fn sign(&self, message: &[u8]) -> Result<String, SonnunError> {
+        Ok(BASE64.encode(ed25519_dalek::Signer::sign(self, message).to_bytes()))
+    }
+}
+
+impl ManifestSigner for p256::ecdsa::SigningKey {
+    fn algorithm(&self) -> SignatureAlgorithm {
+        SignatureAlgorithm::EcdsaP256
+    }
+
+    fn public_key(&self) -> Result<String, SonnunError> {
+        Ok(encode_p256_public_key(self.verifying_key()))
+    }
+
+    fn sign(&self, message: &[u8]) -> Result<String, SonnunError> {
+        let signature: p256::ecdsa::Signature = p256::ecdsa::signature::Signer::sign(self, message);
+        Ok(BASE64.encode(signature.to_bytes()))
+    }
+}
+
+impl SignatureVerifier for ed25519_dalek::VerifyingKey {
+    fn algorithm(&self) -> SignatureAlgorithm {
+        SignatureAlgorithm::Ed25519
+    }
+
+    fn did_key(&self) -> String {
+        did_key(self)
+    }
+
+    fn verify(&self, message: &[u8], signature_b64: &str) -> Result<bool, SonnunError> {
+        let bytes: [u8; 64] = decode_signature(signature_b64)?
+            .try_into()
+            .map_err(|_| SonnunError::InvalidSignature("Invalid signature length".to_string()))?;
+        let signature = ed25519_dalek::Signature::from_bytes(&bytes);
+        Ok(ed25519_dalek::Verifier::verify(self, message, &signature).is_ok())
+    }
+}
+
+impl SignatureVerifier for p256::ecdsa::VerifyingKey {
+    fn algorithm(&self) -> SignatureAlgorithm {
+        SignatureAlgorithm::EcdsaP256
+    }
+
+    fn did_key(&self) -> String {
+        did_key_p256(self)
+    }
+
+    fn verify(&self, message: &[u8], signature_b64: &str) -> Result<bool, SonnunError> {
+        let signature = p256::ecdsa::Signature::from_slice(&decode_signature(signature_b64)?)
+            .map_err(|e| SonnunError::InvalidSignature(format!("Invalid signature: {}", e)))?;
+        Ok(p256::ecdsa::signature::Verifier::verify(self, message, &signature).is_ok())
+    }
+}
+
+// An unlocked keystore key, signing with the algorithm its key file records
+pub struct KeystoreSigner<'a> {
+    keystore: &'a Keystore,
+    key_id: &'a str,
+    algorithm: SignatureAlgorithm,
+}
+
+impl<'a> KeystoreSigner<'a> {
+    pub fn new(keystore: &'a Keystore, key_id: &'a str) -> Result<Self, SonnunError> {
+        let algorithm = keystore.algorithm(key_id)?;
+        Ok(KeystoreSigner { keystore, key_id, algorithm })
+    }
+}
+
+impl ManifestSigner for KeystoreSigner<'_> {
+    fn algorithm(&self) -> SignatureAlgorithm {
+        self.algorithm
+    }
+
+    fn public_key(&self) -> Result<String, SonnunError> {
+        self.keystore.public_key(self.key_id)
+    }
+
+    fn sign(&self, message: &[u8]) -> Result<String, SonnunError> {
+        self.keystore.sign(self.key_id, message)
+    }
+}
+
+// Raw private key bytes: the 32-byte ed25519 seed or the 32-byte P-256 scalar
+pub fn signer_from_bytes(
+    algorithm: SignatureAlgorithm,
+    private_key_bytes: &[u8],
+) -> Result<Box<dyn ManifestSigner>, SonnunError> {
+    let length_error = || SonnunError::InvalidKey("Invalid private key length".to_string());
+    match algorithm {
+        SignatureAlgorithm::Ed25519 => Ok(Box::new(ed25519_dalek::SigningKey::from_bytes(
+            &private_key_bytes.try_into().map_err(|_| length_error())?,
+        ))),
+        SignatureAlgorithm::EcdsaP256 => {
+            if private_key_bytes.len() != 32 {
+                return Err(length_error());
+            }
+            let key = p256::ecdsa::SigningKey::from_slice(private_key_bytes)
+                .map_err(|e| SonnunError::InvalidKey(format!("Invalid private key: {}", e)))?;
+            Ok(Box::new(key))
+        }
+    }
+}
+
+pub fn signature_verifier(
+    algorithm: SignatureAlgorithm,
+    public_key_b64: &str,
+) -> Result<Box<dyn SignatureVerifier>, SonnunError> {
+    match algorithm {
+        SignatureAlgorithm::Ed25519 => Ok(Box::new(parse_public_key(public_key_b64)?)),
+        SignatureAlgorithm::EcdsaP256 => Ok(Box::new(parse_p256_public_key(public_key_b64)?)),
+    }
+}
+
+// The did:key a manifest's author_did must carry for this signer
+pub fn signer_did(signer: &dyn ManifestSigner) -> Result<String, SonnunError> {
+    Ok(signature_verifier(signer.algorithm(), &signer.public_key()?)?.did_key())
+}
+
+// The algorithm a manifest was signed with; it is part of the signed bytes, so it cannot be swapped
+pub fn manifest_algorithm(manifest: &Value) -> Result<SignatureAlgorithm, SonnunError> {
+    match manifest.get(SIGNATURE_ALGORITHM_FIELD) {
+        None => Ok(SignatureAlgorithm::Ed25519),
+        Some(Value::String(name)) => name
+            .parse()
+            .map_err(|e: SonnunError| SonnunError::ManifestSchema(e.message())),
+        Some(_) => Err(SonnunError::ManifestSchema(format!("{} must be a string", SIGNATURE_ALGORITHM_FIELD))),
+    }
+}
+
+pub fn encode_p256_public_key(key: &p256::ecdsa::VerifyingKey) -> String {
+    BASE64.encode(key.to_encoded_point(true).as_bytes())
+}
+
+// Compressed or uncompressed SEC1 points are read; manifests always carry the compressed form
+pub fn parse_p256_public_key(public_key_b64: &str) -> Result<p256::ecdsa::VerifyingKey, SonnunError> {
+    let bytes = BASE64
+        .decode(public_key_b64.trim())
+        .map_err(|e| SonnunError::InvalidKey(format!("Invalid public key encoding: {}", e)))?;
+    p256::ecdsa::VerifyingKey::from_sec1_bytes(&bytes)
+        .map_err(|_| SonnunError::InvalidKey("Invalid P-256 public key".to_string()))
+}
+
+// The raw signature bytes, which is what RFC 3161 timestamps cover for either algorithm
+pub(crate) fn decode_signature(signature_b64: &str) -> Result<Vec<u8>, SonnunError> {
+    BASE64
+        .decode(signature_b64)
+        .map_err(|e| SonnunError::InvalidSignature(format!("Invalid signature encoding: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::OsRng;
+
+    #[test]
+    fn test_each_algorithm_verifies_only_its_own_signatures() {
+        let ed25519 = ed25519_dalek::SigningKey::generate(&mut OsRng);
+        let p256 = p256::ecdsa::SigningKey::random(&mut OsRng);
+        let signers: [&dyn ManifestSigner; 2] = [&ed25519, &p256];
+        for signer in signers {
+            let public_key = signer.public_key().unwrap();
+            let signature = signer.sign(b"manifest").unwrap();
+            let verifier = signature_verifier(signer.algorithm(), &public_key).unwrap();
+            assert!(verifier.verify(b"manifest", &signature).unwrap());
+            assert!(!verifier.verify(b"manifest!", &signature).unwrap());
+        }
+
+        // A P-256 key is not an ed25519 key, whatever the manifest claims
+        let p256_key = ManifestSigner::public_key(&p256).unwrap();
+        assert_eq!(BASE64.decode(&p256_key).unwrap().len(), 33);
+        assert_eq!(signature_verifier(SignatureAlgorithm::Ed25519, &p256_key).err().unwrap().code(), "INVALID_KEY");
+        assert!(did_key_p256(p256.verifying_key()).starts_with("did:key:zDn"));
+
+        assert_eq!(manifest_algorithm(&serde_json::json!({})).unwrap(), SignatureAlgorithm::Ed25519);
+        let manifest = serde_json::json!({ "signature_algorithm": "ecdsa-p256" });
+        assert_eq!(manifest_algorithm(&manifest).unwrap(), SignatureAlgorithm::EcdsaP256);
+        let manifest = serde_json::json!({ "signature_algorithm": "rsa" });
+        assert_eq!(manifest_algorithm(&manifest).unwrap_err().code(), "MANIFEST_SCHEMA");
+    }
+}
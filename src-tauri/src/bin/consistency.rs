@@ -0,0 +1,76 @@
+use clap::{Arg, ArgMatches, Command};
+use std::fs;
+use std::path::Path;
+use sonnun_lib::{
+    load_consistency_proof, normalize_public_key, read_key_argument, read_tree_head, verify_tree_heads, SignedTreeHead,
+    SonnunError,
+};
+
+// AIDEV-NOTE: Checks that the transparency log behind two Sonnun documents (or two saved tree heads)
+// only grew between them. Exit codes follow sonnun-verify: SonnunError::exit_code.
+
+fn main() {
+    let matches = Command::new("sonnun-consistency")
+        .version("1.0")
+        .about("Check that a Sonnun transparency log only grew between two tree heads")
+        .arg(
+            Arg::new("old")
+                .help("Earlier document or signed tree head JSON")
+                .required(true)
+                .index(1),
+        )
+        .arg(
+            Arg::new("new")
+                .help("Later document or signed tree head JSON")
+                .required(true)
+                .index(2),
+        )
+        .arg(
+            Arg::new("proof")
+                .short('p')
+                .long("proof")
+                .value_name("FILE")
+                .required(true)
+                .help("Consistency proof JSON published by the log"),
+        )
+        .arg(
+            Arg::new("log-key")
+                .short('k')
+                .long("key")
+                .value_name("KEY")
+                .help("Public key the log is expected to sign with (any sonnun-verify --key form, or a file holding it)"),
+        )
+        .get_matches();
+
+    match check_consistency(&matches) {
+        Ok((old, new)) => {
+            println!("✅ CONSISTENT log");
+            println!("Log key: {}", new.tree_head.log_key);
+            println!("Tree size {} -> {}", old.tree_head.tree_size, new.tree_head.tree_size);
+            println!("Root {} -> {}", old.tree_head.root_hash, new.tree_head.root_hash);
+        }
+        Err(e) => {
+            eprintln!("Error [{}]: {}", e.code(), e.message());
+            std::process::exit(e.exit_code());
+        }
+    }
+}
+
+fn check_consistency(matches: &ArgMatches) -> Result<(SignedTreeHead, SignedTreeHead), SonnunError> {
+    let old = read_tree_head(&read_file(matches.get_one::<String>("old").unwrap())?)?;
+    let new = read_tree_head(&read_file(matches.get_one::<String>("new").unwrap())?)?;
+    let proof = load_consistency_proof(Path::new(matches.get_one::<String>("proof").unwrap()))?;
+
+    if let Some(log_key) = matches.get_one::<String>("log-key") {
+        let log_key = normalize_public_key(&read_key_argument(log_key)?)?;
+        if old.tree_head.log_key != log_key {
+            return Err(SonnunError::InvalidKey("Tree heads were not signed by the expected log key".to_string()));
+        }
+    }
+    verify_tree_heads(&old, &new, &proof)?;
+    Ok((old, new))
+}
+
+fn read_file(path: &str) -> Result<String, SonnunError> {
+    fs::read_to_string(path).map_err(|e| SonnunError::Io(format!("Failed to read {}: {}", path, e)))
+}
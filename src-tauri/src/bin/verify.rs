@@ -1,11 +1,14 @@
-use clap::{Arg, Command};
+use clap::{Arg, ArgMatches, Command};
 use std::fs;
-use serde_json::Value;
-use ed25519_dalek::{VerifyingKey, Signature, Verifier};
-use base64::{engine::general_purpose, Engine as _};
-use regex::Regex;
+use std::path::Path;
+use sonnun_lib::{
+    load_c2pa_manifest, load_revocations, load_rotations, load_tsa_certificates, read_key_argument, verify_cose,
+    verify_credential, verify_html, verify_jws, ContentBinding, RequiredSigner, SonnunError, VerificationResult,
+    VerifyOptions,
+};
 
-// AIDEV-NOTE: CLI verifier for Sonnun signed documents - validates ed25519 signatures
+// AIDEV-NOTE: CLI verifier for Sonnun signed documents - validates ed25519 and ECDSA P-256 signatures.
+// Argument handling only; the checks themselves live in sonnun_lib::verifier.
 
 fn main() {
     let matches = Command::new("sonnun-verify")
@@ -13,7 +16,7 @@ fn main() {
         .about("Verify Sonnun signed documents")
         .arg(
             Arg::new("file")
-                .help("HTML file to verify")
+                .help("Signed HTML file, provenance Verifiable Credential (JSON), compact JWS or COSE_Sign1 envelope to verify")
                 .required(true)
                 .index(1),
         )
@@ -22,139 +25,221 @@ fn main() {
                 .short('k')
                 .long("key")
                 .value_name("KEY")
-                .help("Public key to verify against (base64)"),
+                .help("Trusted public key: base64, prov-pk string, did:key, or SPKI PEM (file or text); P-256 keys as base64 SEC1 or did:key; newer keys are accepted through --rotations"),
+        )
+        .arg(
+            Arg::new("rotations")
+                .short('r')
+                .long("rotations")
+                .value_name("FILE")
+                .help("JSON file of signed key rotation statements"),
+        )
+        .arg(
+            Arg::new("revocations")
+                .long("revocations")
+                .value_name("FILE|DIR")
+                .help("Signed revocation list, or a directory of them; revoked keys exit with code 11"),
+        )
+        .arg(
+            Arg::new("allow-unbound")
+                .long("allow-unbound")
+                .action(clap::ArgAction::SetTrue)
+                .help("Accept manifests without a content_hash (exports that predate content binding), and credentials, JWS and COSE envelopes without --content"),
+        )
+        .arg(
+            Arg::new("require")
+                .long("require")
+                .value_name("ROLE[=KEY]")
+                .value_delimiter(',')
+                .action(clap::ArgAction::Append)
+                .help("Roles that must have signed, e.g. author,editor; a bare ROLE is met only by --key or a --signer-key, ROLE=KEY pins the signer; unmet policies exit with code 13"),
+        )
+        .arg(
+            Arg::new("signer-key")
+                .long("signer-key")
+                .value_name("KEY")
+                .action(clap::ArgAction::Append)
+                .help("Key trusted to sign in the role it claims (any --key form); may be repeated"),
+        )
+        .arg(
+            Arg::new("tsa-cert")
+                .long("tsa-cert")
+                .value_name("FILE")
+                .action(clap::ArgAction::Append)
+                .help("Trusted RFC 3161 TSA certificate (PEM bundle or DER); may be repeated"),
+        )
+        .arg(
+            Arg::new("require-timestamp")
+                .long("require-timestamp")
+                .action(clap::ArgAction::SetTrue)
+                .help("Fail with exit code 14 unless the signature carries a timestamp from a trusted TSA"),
+        )
+        .arg(
+            Arg::new("log-key")
+                .long("log-key")
+                .value_name("KEY")
+                .help("Require an inclusion proof from the transparency log signing with KEY (any --key form); exit 15 otherwise"),
+        )
+        .arg(
+            Arg::new("c2pa")
+                .long("c2pa")
+                .value_name("FILE")
+                .help("C2PA manifest store (.c2pa) to check against the document; exit 12 if it binds another page"),
+        )
+        .arg(
+            Arg::new("format")
+                .long("format")
+                .value_parser(["auto", "html", "vc", "jws", "cose"])
+                .default_value("auto")
+                .help("Document format; auto tells HTML, credentials and compact JWS apart, COSE envelopes need --format cose"),
+        )
+        .arg(
+            Arg::new("content")
+                .long("content")
+                .value_name("FILE")
+                .help("Published page a credential, JWS or COSE envelope describes; its content must match (exit 12); required unless --allow-unbound"),
         )
         .get_matches();
 
-    let filename = matches.get_one::<String>("file").unwrap();
-    let provided_key = matches.get_one::<String>("public-key");
-
-    match verify_document(filename, provided_key) {
+    // AIDEV-NOTE: Exit status comes from SonnunError::exit_code so scripts can tell failures apart
+    match verify_document(&matches) {
         Ok(result) => {
-            if result.valid {
-                println!("✅ VALID signature");
-                println!("Public key: {}", result.public_key);
-                println!("Manifest: {}", serde_json::to_string_pretty(&result.manifest).unwrap());
-            } else {
-                println!("❌ INVALID signature");
-                std::process::exit(1);
+            println!("✅ VALID signature");
+            println!("Public key: {}", result.public_key);
+            println!("Author DID: {}", result.author_did);
+            println!("Algorithm: {}", result.algorithm.as_str());
+            match &result.content {
+                ContentBinding::Matched(hash) => println!("Content hash: {} (matches page)", hash),
+                ContentBinding::Unchecked(hash) => {
+                    println!("⚠️  Content hash: {} (not checked against a page; pass it with --content)", hash)
+                }
+                ContentBinding::Unbound => println!("⚠️  Content is not bound to the signature"),
             }
+            for signer in &result.signers {
+                if signer.trusted {
+                    println!("Signed as {}: {}", signer.role.as_str(), signer.public_key);
+                } else {
+                    println!("Signed as {} (unauthenticated, key not trusted): {}", signer.role.as_str(), signer.public_key);
+                }
+            }
+            match &result.timestamp {
+                Some(timestamp) if timestamp.trusted => {
+                    println!("Timestamp: {} by {}", timestamp.gen_time.to_rfc3339(), timestamp.tsa)
+                }
+                Some(timestamp) => println!(
+                    "⚠️  Timestamp {} by {} is not from a trusted TSA (see --tsa-cert)",
+                    timestamp.gen_time.to_rfc3339(),
+                    timestamp.tsa
+                ),
+                None => {}
+            }
+            if let Some(proof) = &result.transparency {
+                let tree_head = &proof.tree_head.tree_head;
+                println!(
+                    "Transparency log: entry {} of {} (tree head {}, signed {})",
+                    proof.leaf_index, tree_head.tree_size, tree_head.root_hash, tree_head.timestamp
+                );
+            }
+            if let Some(c2pa) = &result.c2pa {
+                println!("C2PA manifest: {} by {}, certificate {}", c2pa.label, c2pa.claim_generator, c2pa.signer);
+                for action in c2pa.actions() {
+                    let source_type = action["digitalSourceType"].as_str().and_then(|uri| uri.rsplit('/').next());
+                    println!(
+                        "  {} {}% {}",
+                        action["action"].as_str().unwrap_or("?"),
+                        action["parameters"]["sonnun.percentage"],
+                        source_type.unwrap_or("")
+                    );
+                }
+                for ingredient in c2pa.ingredients() {
+                    println!("  ingredient: {}", ingredient);
+                }
+            }
+            for rotation in &result.rotation_path {
+                println!(
+                    "Rotated: {} -> {} (effective {})",
+                    rotation.statement.previous_key, rotation.statement.new_key, rotation.statement.effective_date
+                );
+            }
+            println!("Manifest: {}", serde_json::to_string_pretty(&result.manifest).unwrap());
         }
-        Err(e) => {
-            eprintln!("Error: {}", e);
-            std::process::exit(1);
+        Err(e @ SonnunError::SignatureMismatch(_)) => {
+            println!("❌ INVALID signature");
+            eprintln!("Error [{}]: {}", e.code(), e.message());
+            std::process::exit(e.exit_code());
         }
-    }
-}
-
-struct VerificationResult {
-    valid: bool,
-    public_key: String,
-    manifest: Value,
-}
-
-fn verify_document(filename: &str, provided_key: Option<&String>) -> Result<VerificationResult, String> {
-    let content = fs::read_to_string(filename).map_err(|e| format!("Failed to read file: {}", e))?;
-
-    // AIDEV-NOTE: Regex-based HTML parsing for manifest extraction - more robust than string matching
-    let manifest_regex = Regex::new(
-        r#"<script\s+type\s*=\s*["']application/json["']\s+id\s*=\s*["']sonnun-manifest["']\s*>(.*?)</script>"#
-    ).map_err(|e| format!("Failed to compile regex: {}", e))?;
-    
-    let captures = manifest_regex.captures(&content)
-        .ok_or("No Sonnun manifest found in document")?;
-    
-    let manifest_json = captures.get(1)
-        .ok_or("Failed to extract manifest content")?
-        .as_str();
-    let signed_manifest: Value = serde_json::from_str(manifest_json.trim())
-        .map_err(|e| format!("Invalid manifest JSON: {}", e))?;
-
-    // AIDEV-NOTE: Manifest validation - ensure required fields exist before accessing
-    validate_manifest_structure(&signed_manifest)?;
-    
-    let manifest = signed_manifest["manifest"].clone();
-    let signature_b64 = signed_manifest["signature"]
-        .as_str()
-        .ok_or("No signature in manifest")?;
-    let public_key_b64 = signed_manifest["public_key"]
-        .as_str()
-        .ok_or("No public key in manifest")?;
-
-    if let Some(key) = provided_key {
-        if key != public_key_b64 {
-            return Err("Provided public key does not match document key".to_string());
+        Err(e) => {
+            eprintln!("Error [{}]: {}", e.code(), e.message());
+            std::process::exit(e.exit_code());
         }
     }
-
-    // AIDEV-NOTE: Base64 decode - using general_purpose::STANDARD engine per base64 v0.21 API
-    let public_key_bytes = general_purpose::STANDARD
-        .decode(public_key_b64)
-        .map_err(|e| format!("Invalid public key encoding: {}", e))?;
-    let signature_bytes = general_purpose::STANDARD
-        .decode(signature_b64)
-        .map_err(|e| format!("Invalid signature encoding: {}", e))?;
-
-    // AIDEV-NOTE: Ed25519 key construction - requires exactly 32 bytes via array reference
-    let verifying_key = VerifyingKey::from_bytes(
-        &public_key_bytes.try_into()
-            .map_err(|_| "Invalid public key length")?
-    ).map_err(|e| format!("Invalid public key: {}", e))?;
-    
-    // AIDEV-NOTE: Ed25519 signature - requires exactly 64 bytes via array reference
-    let signature = Signature::from_bytes(
-        &signature_bytes.try_into()
-            .map_err(|_| "Invalid signature length")?
-    );
-
-    // AIDEV-NOTE: Canonical JSON serialization ensures consistent signature verification
-    let canonical_manifest = serde_json::to_string(&manifest)
-        .map_err(|e| format!("Failed to serialize manifest: {}", e))?;
-
-    // AIDEV-NOTE: Cryptographic verification - returns Ok() on valid signature, Err on invalid
-    let valid = verifying_key
-        .verify(canonical_manifest.as_bytes(), &signature)
-        .is_ok();
-
-    Ok(VerificationResult {
-        valid,
-        public_key: public_key_b64.to_string(),
-        manifest,
-    })
 }
 
-// AIDEV-NOTE: Validates manifest has required fields: manifest, signature, public_key
-fn validate_manifest_structure(signed_manifest: &Value) -> Result<(), String> {
-    if !signed_manifest.is_object() {
-        return Err("Manifest must be a JSON object".to_string());
-    }
-    
-    if !signed_manifest.get("manifest").is_some() {
-        return Err("Missing 'manifest' field in signed document".to_string());
-    }
-    
-    if !signed_manifest.get("signature").is_some() {
-        return Err("Missing 'signature' field in signed document".to_string());
-    }
-    
-    if !signed_manifest.get("public_key").is_some() {
-        return Err("Missing 'public_key' field in signed document".to_string());
+fn verify_document(matches: &ArgMatches) -> Result<VerificationResult, SonnunError> {
+    let filename = matches.get_one::<String>("file").unwrap();
+    let bytes = fs::read(filename).map_err(|e| SonnunError::Io(format!("Failed to read file: {}", e)))?;
+    let page = matches
+        .get_one::<String>("content")
+        .map(|path| fs::read_to_string(path).map_err(|e| SonnunError::Io(format!("Failed to read page: {}", e))))
+        .transpose()?;
+
+    let mut tsa_certificates = Vec::new();
+    for path in matches.get_many::<String>("tsa-cert").into_iter().flatten() {
+        tsa_certificates.extend(load_tsa_certificates(Path::new(path))?);
     }
-    
-    // AIDEV-NOTE: Validate inner manifest structure for provenance tracking
-    let manifest = &signed_manifest["manifest"];
-    if !manifest.is_object() {
-        return Err("Inner manifest must be a JSON object".to_string());
+    let options = VerifyOptions {
+        trusted_key: matches.get_one::<String>("public-key").map(|key| read_key_argument(key)).transpose()?,
+        rotations: match matches.get_one::<String>("rotations") {
+            Some(path) => load_rotations(Path::new(path))?,
+            None => Vec::new(),
+        },
+        revocations: match matches.get_one::<String>("revocations") {
+            Some(path) => load_revocations(Path::new(path))?,
+            None => Vec::new(),
+        },
+        allow_unbound_content: matches.get_flag("allow-unbound"),
+        required_signers: matches
+            .get_many::<String>("require")
+            .into_iter()
+            .flatten()
+            .map(|clause| clause.parse::<RequiredSigner>())
+            .collect::<Result<_, _>>()?,
+        trusted_signers: matches
+            .get_many::<String>("signer-key")
+            .into_iter()
+            .flatten()
+            .map(|key| read_key_argument(key))
+            .collect::<Result<_, _>>()?,
+        tsa_certificates,
+        require_timestamp: matches.get_flag("require-timestamp"),
+        log_key: matches.get_one::<String>("log-key").map(|key| read_key_argument(key)).transpose()?,
+        c2pa_manifest: matches.get_one::<String>("c2pa").map(|path| load_c2pa_manifest(Path::new(path))).transpose()?,
+    };
+    // AIDEV-NOTE: With --format auto, a document that is a JSON object is taken to be a credential, one
+    // that is three dot-separated base64url segments a compact JWS, and anything else HTML. COSE is
+    // binary and always named explicitly.
+    let format = matches.get_one::<String>("format").unwrap().as_str();
+    if format == "cose" {
+        return verify_cose(&bytes, page.as_deref(), &options);
     }
-    
-    // Check for expected provenance fields
-    let expected_fields = ["total_characters", "human_characters", "ai_characters", "cited_characters"];
-    for field in expected_fields {
-        if !manifest.get(field).is_some() {
-            return Err(format!("Missing '{}' field in manifest", field));
+    let content = String::from_utf8(bytes)
+        .map_err(|_| SonnunError::InvalidInput(format!("{} is not text; use --format cose for COSE envelopes", filename)))?;
+    match format {
+        "vc" => verify_credential(&content, page.as_deref(), &options),
+        "jws" => verify_jws(&content, page.as_deref(), &options),
+        "auto" if content.trim_start().starts_with('{') => verify_credential(&content, page.as_deref(), &options),
+        "auto" if is_compact_jws(&content) => verify_jws(&content, page.as_deref(), &options),
+        _ if page.is_some() => {
+            Err(SonnunError::InvalidInput("--content only applies to credentials, JWS and COSE".to_string()))
         }
+        _ => verify_html(&content, &options),
     }
-    
-    Ok(())
 }
 
+fn is_compact_jws(content: &str) -> bool {
+    let parts: Vec<&str> = content.trim().split('.').collect();
+    parts.len() == 3
+        && parts
+            .iter()
+            .all(|part| part.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_'))
+}
@@ -0,0 +1,772 @@
+// AIDEV-NOTE: C2PA manifest store (document.c2pa), the external-manifest form C2PA readers take next to
+// an asset. Layout per C2PA 1.x: a JUMBF 'c2pa' superbox holding one 'c2ma' manifest, which holds the
+// 'c2as' assertion store, the 'c2cl' claim and the 'c2cs' claim signature. Assertions and the claim are
+// CBOR; the claim lists every assertion by hashed URI (SHA-256 over the assertion superbox's contents,
+// i.e. without its 8-byte header). The signature is a tagged COSE_Sign1 over the claim bytes, payload
+// detached, carrying an x5chain. Sonnun has no CA, so the chain is a leaf certificate for the author
+// key issued by a root that same key self-signs: C2PA tools report it untrusted until the reader adds
+// that root to their trust list, and the verifier below trusts it only when the leaf holds the page's
+// signing key. The c2pa.hash.data hard binding covers the exact page bytes, so the store has to be
+// exported after the page's last change (co-signatures, timestamp, log proof).
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+use std::time::Duration;
+
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine as _;
+use chrono::{DateTime, Utc};
+use coset::cbor::value::Value as CborValue;
+use coset::iana::EnumI64;
+use coset::{iana, CborSerializable, CoseSign1, CoseSign1Builder, HeaderBuilder, Label, TaggedCborSerializable};
+use der::asn1::{BitString, ObjectIdentifier, OctetString, UtcTime};
+use der::oid::AssociatedOid;
+use der::{Decode, Encode};
+use ed25519_dalek::pkcs8::{DecodePublicKey, EncodePublicKey};
+use ed25519_dalek::{Signature, Verifier, VerifyingKey};
+use serde::Serialize;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use spki::{AlgorithmIdentifierOwned, SubjectPublicKeyInfoOwned};
+use x509_cert::certificate::{TbsCertificate, Version};
+use x509_cert::ext::pkix::{
+    AuthorityKeyIdentifier, BasicConstraints, ExtendedKeyUsage, KeyUsage, KeyUsages, SubjectKeyIdentifier,
+};
+use x509_cert::ext::Extension;
+use x509_cert::name::Name;
+use x509_cert::serial_number::SerialNumber;
+use x509_cert::time::{Time, Validity};
+use x509_cert::Certificate;
+
+use crate::cose::{from_cbor, to_cbor, write_cbor};
+use crate::error::SonnunError;
+use crate::keystore::{key_fingerprint, Keystore};
+use crate::{EventType, ManifestData, Operation};
+
+// c2pa.actions.v2: the version whose parameters.ingredients can name several sources
+pub const ACTIONS_LABEL: &str = "c2pa.actions.v2";
+pub const DATA_HASH_LABEL: &str = "c2pa.hash.data";
+const INGREDIENT_LABEL: &str = "c2pa.ingredient";
+const STORE_LABEL: &str = "c2pa";
+const ASSERTIONS_LABEL: &str = "c2pa.assertions";
+const CLAIM_LABEL: &str = "c2pa.claim";
+const SIGNATURE_LABEL: &str = "c2pa.signature";
+const DOCUMENT_FORMAT: &str = "text/html";
+const DIGITAL_SOURCE_TYPE_BASE: &str = "http://cv.iptc.org/newscodes/digitalsourcetype/";
+
+// JUMBF content types (ISO 19566-5) are four ASCII characters followed by this fixed suffix
+const JUMBF_UUID_SUFFIX: [u8; 12] = [0x00, 0x11, 0x00, 0x10, 0x80, 0x00, 0x00, 0xaa, 0x00, 0x38, 0x9b, 0x71];
+const JUMBF_REQUESTABLE_LABELLED: u8 = 0x03;
+
+const ID_ED25519: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.3.101.112");
+const ID_KP_EMAIL_PROTECTION: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.3.6.1.5.5.7.3.4");
+const ID_KP_DOCUMENT_SIGNING: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.3.6.1.5.5.7.3.36");
+
+// The active manifest of a store, once its signature and every assertion hash have been checked
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct C2paManifest {
+    pub label: String,
+    pub claim_generator: String,
+    pub title: String,
+    pub format: String,
+    pub instance_id: String,
+    pub assertions: Vec<C2paAssertion>, // those the claim lists, in claim order
+    pub signer: String,                 // subject of the signing certificate
+    pub public_key: String,             // base64 ed25519 key of the signing certificate
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct C2paAssertion {
+    pub label: String,
+    pub data: Value, // the CBOR assertion as JSON, byte strings base64 encoded
+}
+
+fn c2pa_error(message: impl std::fmt::Display) -> SonnunError {
+    SonnunError::ManifestSchema(format!("Invalid C2PA manifest store: {}", message))
+}
+
+fn asn1_error(e: der::Error) -> SonnunError {
+    SonnunError::ManifestSchema(format!("Invalid C2PA certificate: {}", e))
+}
+
+// AIDEV-NOTE: How each provenance category reads in C2PA's action vocabulary: the action that put the
+// text there and the IPTC digital source type of the result. c2pa.created always carries a source type;
+// the rest only do when the category says something about generative AI or tooling.
+fn claim_action(event_type: EventType) -> (&'static str, Option<&'static str>) {
+    match event_type {
+        EventType::Human => ("c2pa.created", Some("digitalCreation")),
+        EventType::Ai => ("c2pa.created", Some("trainedAlgorithmicMedia")),
+        EventType::AiEdited => ("c2pa.edited", Some("compositeWithTrainedAlgorithmicMedia")),
+        EventType::Translated => ("c2pa.translated", None),
+        EventType::ToolAssisted => ("c2pa.edited", Some("algorithmicallyEnhanced")),
+        EventType::Cited => ("c2pa.placed", None),
+        EventType::Imported => ("c2pa.placed", None),
+    }
+}
+
+fn text(value: impl Into<String>) -> CborValue {
+    CborValue::Text(value.into())
+}
+
+fn map(entries: Vec<(&str, CborValue)>) -> CborValue {
+    CborValue::Map(entries.into_iter().map(|(key, value)| (text(key), value)).collect())
+}
+
+fn generator_info() -> CborValue {
+    map(vec![
+        ("name", text("Sonnun")),
+        ("version", text(env!("CARGO_PKG_VERSION"))),
+    ])
+}
+
+// A box: 32-bit big-endian length including the 8-byte header, then the box type
+fn jumbf_box(box_type: &[u8; 4], payload: &[u8]) -> Vec<u8> {
+    let mut bytes = ((payload.len() + 8) as u32).to_be_bytes().to_vec();
+    bytes.extend_from_slice(box_type);
+    bytes.extend_from_slice(payload);
+    bytes
+}
+
+// A superbox's contents: the description box naming its content type and label, then its children
+fn superbox_contents(content_type: &[u8; 4], label: &str, children: &[Vec<u8>]) -> Vec<u8> {
+    let mut description = content_type.to_vec();
+    description.extend_from_slice(&JUMBF_UUID_SUFFIX);
+    description.push(JUMBF_REQUESTABLE_LABELLED);
+    description.extend_from_slice(label.as_bytes());
+    description.push(0);
+    let mut contents = jumbf_box(b"jumd", &description);
+    children.iter().for_each(|child| contents.extend_from_slice(child));
+    contents
+}
+
+fn superbox(content_type: &[u8; 4], label: &str, children: &[Vec<u8>]) -> Vec<u8> {
+    jumbf_box(b"jumb", &superbox_contents(content_type, label, children))
+}
+
+// AIDEV-NOTE: One cited source per ingredient, deduplicated and sorted so the same manifest always
+// yields the same store. C2PA ingredients are whole assets; a quotation is the closest match.
+fn cited_sources(manifest: &ManifestData) -> BTreeSet<&str> {
+    manifest
+        .events
+        .iter()
+        .filter(|event| event.event_type == EventType::Cited && event.operation != Operation::Delete)
+        .map(|event| event.source.trim())
+        .filter(|source| !source.is_empty())
+        .collect()
+}
+
+// Collects assertion superboxes and the hashed URIs the claim lists them by
+#[derive(Default)]
+struct AssertionStore {
+    boxes: Vec<Vec<u8>>,
+    hashed_uris: Vec<CborValue>,
+}
+
+impl AssertionStore {
+    fn add(&mut self, label: &str, data: &CborValue) -> Result<CborValue, SonnunError> {
+        let contents = superbox_contents(b"cbor", label, &[jumbf_box(b"cbor", &write_cbor(data)?)]);
+        let hashed_uri = map(vec![
+            ("url", text(format!("self#jumbf={}/{}", ASSERTIONS_LABEL, label))),
+            ("hash", CborValue::Bytes(Sha256::digest(&contents).to_vec())),
+        ]);
+        self.boxes.push(jumbf_box(b"jumb", &contents));
+        self.hashed_uris.push(hashed_uri.clone());
+        Ok(hashed_uri)
+    }
+}
+
+// AIDEV-NOTE: One action per category with a non-zero share, in EventType::ALL order. The Sonnun
+// category and share ride along as action parameters; c2pa.placed names the cited ingredients.
+pub fn build_c2pa_manifest(
+    keystore: &Keystore,
+    key_id: &str,
+    manifest: &ManifestData,
+    title: &str,
+    asset: &[u8],
+) -> Result<Vec<u8>, SonnunError> {
+    let public_key = keystore.ed25519_public_key(key_id)?;
+    let mut assertions = AssertionStore::default();
+
+    let ingredients = cited_sources(manifest)
+        .into_iter()
+        .enumerate()
+        .map(|(index, source)| {
+            let label = match index {
+                0 => INGREDIENT_LABEL.to_string(),
+                n => format!("{}__{}", INGREDIENT_LABEL, n),
+            };
+            let ingredient = map(vec![
+                ("dc:title", text(source)),
+                ("dc:format", text("text/plain")),
+                (
+                    "instanceID",
+                    text(format!(
+                        "xmp:iid:{}",
+                        uuid_from_hash(&format!("{:x}", Sha256::digest(source)))
+                    )),
+                ),
+                ("relationship", text("componentOf")),
+            ]);
+            assertions.add(&label, &ingredient)
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let actions = EventType::ALL
+        .into_iter()
+        .filter(|event_type| manifest.percentage(*event_type) > 0.0)
+        .map(|event_type| {
+            let (action, source_type) = claim_action(event_type);
+            let mut parameters = vec![
+                ("sonnun.event_type", text(event_type.as_str())),
+                ("sonnun.percentage", to_cbor(&json!(manifest.percentage(event_type)))?),
+            ];
+            if event_type == EventType::Cited && !ingredients.is_empty() {
+                parameters.push(("ingredients", CborValue::Array(ingredients.clone())));
+            }
+            let mut entry = vec![("action", text(action)), ("softwareAgent", generator_info())];
+            if let Some(source_type) = source_type {
+                entry.push((
+                    "digitalSourceType",
+                    text(format!("{}{}", DIGITAL_SOURCE_TYPE_BASE, source_type)),
+                ));
+            }
+            entry.push(("parameters", map(parameters)));
+            Ok(map(entry))
+        })
+        .collect::<Result<Vec<_>, SonnunError>>()?;
+    assertions.add(ACTIONS_LABEL, &map(vec![("actions", CborValue::Array(actions))]))?;
+
+    let asset_hash = Sha256::digest(asset);
+    assertions.add(
+        DATA_HASH_LABEL,
+        &map(vec![
+            ("alg", text("sha256")),
+            ("hash", CborValue::Bytes(asset_hash.to_vec())),
+            ("pad", CborValue::Bytes(Vec::new())),
+        ]),
+    )?;
+
+    let claim = map(vec![
+        ("claim_generator", text(format!("Sonnun/{}", env!("CARGO_PKG_VERSION")))),
+        ("claim_generator_info", CborValue::Array(vec![generator_info()])),
+        ("signature", text(format!("self#jumbf={}", SIGNATURE_LABEL))),
+        ("assertions", CborValue::Array(assertions.hashed_uris)),
+        ("dc:format", text(DOCUMENT_FORMAT)),
+        ("dc:title", text(title)),
+        (
+            "instanceID",
+            text(format!("xmp:iid:{}", uuid_from_hash(&format!("{:x}", asset_hash)))),
+        ),
+        ("alg", text("sha256")),
+    ]);
+    let claim_bytes = write_cbor(&claim)?;
+
+    // Certificates start at the manifest's own signing time, so re-exporting gives the same chain
+    let signed_at = manifest
+        .generated_at
+        .as_deref()
+        .and_then(|generated_at| DateTime::parse_from_rfc3339(generated_at).ok())
+        .map(|generated_at| generated_at.with_timezone(&Utc))
+        .unwrap_or_else(Utc::now);
+    let chain = certificate_chain(keystore, key_id, &public_key, signed_at)?;
+    let protected = HeaderBuilder::new()
+        .algorithm(iana::Algorithm::EdDSA)
+        .value(
+            iana::HeaderParameter::X5Chain.to_i64(),
+            CborValue::Array(chain.into_iter().map(CborValue::Bytes).collect()),
+        )
+        .build();
+    let signature = CoseSign1Builder::new()
+        .protected(protected)
+        .try_create_detached_signature(&claim_bytes, b"", |tbs| sign_bytes(keystore, key_id, tbs))?
+        .build()
+        .to_tagged_vec()
+        .map_err(cose_error)?;
+
+    // The manifest label is derived from the claim, so regenerating a store gives the same bytes
+    let label = format!(
+        "urn:uuid:{}",
+        uuid_from_hash(&format!("{:x}", Sha256::digest(&claim_bytes)))
+    );
+    let manifest_box = superbox(
+        b"c2ma",
+        &label,
+        &[
+            superbox(b"c2as", ASSERTIONS_LABEL, &assertions.boxes),
+            superbox(b"c2cl", CLAIM_LABEL, &[jumbf_box(b"cbor", &claim_bytes)]),
+            superbox(b"c2cs", SIGNATURE_LABEL, &[jumbf_box(b"cbor", &signature)]),
+        ],
+    );
+    Ok(superbox(b"c2pa", STORE_LABEL, &[manifest_box]))
+}
+
+fn cose_error(e: coset::CoseError) -> SonnunError {
+    c2pa_error(format!("claim signature is not a COSE_Sign1: {}", e))
+}
+
+fn sign_bytes(keystore: &Keystore, key_id: &str, bytes: &[u8]) -> Result<Vec<u8>, SonnunError> {
+    BASE64
+        .decode(keystore.sign(key_id, bytes)?)
+        .map_err(|e| SonnunError::InvalidSignature(format!("Invalid signature encoding: {}", e)))
+}
+
+fn ed25519() -> AlgorithmIdentifierOwned {
+    AlgorithmIdentifierOwned {
+        oid: ID_ED25519,
+        parameters: None,
+    }
+}
+
+fn extension<T: AssociatedOid + Encode>(value: &T, critical: bool) -> Result<Extension, SonnunError> {
+    Ok(Extension {
+        extn_id: T::OID,
+        critical,
+        extn_value: OctetString::new(value.to_der().map_err(asn1_error)?).map_err(asn1_error)?,
+    })
+}
+
+// AIDEV-NOTE: C2PA requires the claim signer's certificate to be an end-entity one with a signing EKU,
+// so the author key gets a leaf (digitalSignature, emailProtection + documentSigning) under a CA
+// certificate it self-signs. Both hold the same key and never expire; revocation stays with Sonnun's
+// own revocation lists.
+fn certificate_chain(
+    keystore: &Keystore,
+    key_id: &str,
+    public_key: &VerifyingKey,
+    not_before: DateTime<Utc>,
+) -> Result<Vec<Vec<u8>>, SonnunError> {
+    let fingerprint = key_fingerprint(public_key.as_bytes());
+    let name = |common_name: String| Name::from_str(&format!("CN={},O=Sonnun", common_name)).map_err(asn1_error);
+    let root_name = name(format!("Sonnun author root {}", fingerprint))?;
+    let leaf_name = name(format!("Sonnun author {}", fingerprint))?;
+    let issue = |issuer: &Name, subject: &Name, ca: bool| {
+        issue_certificate(keystore, key_id, public_key, issuer, subject, ca, not_before)
+    };
+    Ok(vec![
+        issue(&root_name, &leaf_name, false)?,
+        issue(&root_name, &root_name, true)?,
+    ])
+}
+
+fn issue_certificate(
+    keystore: &Keystore,
+    key_id: &str,
+    public_key: &VerifyingKey,
+    issuer: &Name,
+    subject: &Name,
+    ca: bool,
+    not_before: DateTime<Utc>,
+) -> Result<Vec<u8>, SonnunError> {
+    // One key, so the subject and authority key identifiers are the same 160-bit truncated SHA-256
+    let key_identifier = OctetString::new(&Sha256::digest(public_key.as_bytes())[..20]).map_err(asn1_error)?;
+    let mut extensions = vec![
+        extension(
+            &BasicConstraints {
+                ca,
+                path_len_constraint: None,
+            },
+            true,
+        )?,
+        extension(&SubjectKeyIdentifier(key_identifier.clone()), false)?,
+    ];
+    if ca {
+        extensions.push(extension(&KeyUsage(KeyUsages::KeyCertSign | KeyUsages::CRLSign), true)?);
+    } else {
+        extensions.push(extension(&KeyUsage(KeyUsages::DigitalSignature.into()), true)?);
+        extensions.push(extension(
+            &ExtendedKeyUsage(vec![ID_KP_EMAIL_PROTECTION, ID_KP_DOCUMENT_SIGNING]),
+            false,
+        )?);
+        extensions.push(extension(
+            &AuthorityKeyIdentifier {
+                key_identifier: Some(key_identifier),
+                authority_cert_issuer: None,
+                authority_cert_serial_number: None,
+            },
+            false,
+        )?);
+    }
+
+    // A positive 16-byte serial, fixed by the subject and start date
+    let mut serial = Sha256::digest(format!("{}\n{}", subject, not_before.timestamp()))[..16].to_vec();
+    serial[0] = (serial[0] & 0x7f) | 0x40;
+    let not_before = u64::try_from(not_before.timestamp()).map_err(|_| c2pa_error("signing time before 1970"))?;
+    let tbs_certificate = TbsCertificate {
+        version: Version::V3,
+        serial_number: SerialNumber::new(&serial).map_err(asn1_error)?,
+        signature: ed25519(),
+        issuer: issuer.clone(),
+        validity: Validity {
+            not_before: Time::UtcTime(
+                UtcTime::from_unix_duration(Duration::from_secs(not_before)).map_err(asn1_error)?,
+            ),
+            not_after: Time::INFINITY,
+        },
+        subject: subject.clone(),
+        subject_public_key_info: SubjectPublicKeyInfoOwned::from_der(
+            public_key
+                .to_public_key_der()
+                .map_err(|e| SonnunError::InvalidKey(format!("Failed to encode public key: {}", e)))?
+                .as_bytes(),
+        )
+        .map_err(asn1_error)?,
+        issuer_unique_id: None,
+        subject_unique_id: None,
+        extensions: Some(extensions),
+    };
+    let signature = sign_bytes(keystore, key_id, &tbs_certificate.to_der().map_err(asn1_error)?)?;
+    Certificate {
+        tbs_certificate,
+        signature_algorithm: ed25519(),
+        signature: BitString::from_bytes(&signature).map_err(asn1_error)?,
+    }
+    .to_der()
+    .map_err(asn1_error)
+}
+
+pub fn load_c2pa_manifest(path: &Path) -> Result<Vec<u8>, SonnunError> {
+    fs::read(path).map_err(|e| SonnunError::Io(format!("Failed to read {}: {}", path.display(), e)))
+}
+
+struct JumbfBox<'a> {
+    box_type: [u8; 4],
+    payload: &'a [u8],
+}
+
+struct Superbox<'a> {
+    content_type: [u8; 4],
+    label: String,
+    contents: &'a [u8], // what a hashed URI covers
+    children: Vec<JumbfBox<'a>>,
+}
+
+fn read_boxes(mut bytes: &[u8]) -> Result<Vec<JumbfBox<'_>>, SonnunError> {
+    let mut boxes = Vec::new();
+    while !bytes.is_empty() {
+        if bytes.len() < 8 {
+            return Err(c2pa_error("truncated box header"));
+        }
+        let length = u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as usize;
+        if length < 8 || length > bytes.len() {
+            return Err(c2pa_error(format!("unsupported box length {}", length)));
+        }
+        boxes.push(JumbfBox {
+            box_type: [bytes[4], bytes[5], bytes[6], bytes[7]],
+            payload: &bytes[8..length],
+        });
+        bytes = &bytes[length..];
+    }
+    Ok(boxes)
+}
+
+fn read_superbox<'a>(jumbf: &JumbfBox<'a>) -> Result<Superbox<'a>, SonnunError> {
+    if &jumbf.box_type != b"jumb" {
+        return Err(c2pa_error("expected a JUMBF superbox"));
+    }
+    let mut children = read_boxes(jumbf.payload)?;
+    if children.first().map(|child| &child.box_type) != Some(b"jumd") {
+        return Err(c2pa_error("superbox has no description box"));
+    }
+    let description = children.remove(0).payload;
+    if description.len() < 17 || description[4..16] != JUMBF_UUID_SUFFIX {
+        return Err(c2pa_error("description box has an unknown content type"));
+    }
+    let label = match description[16] & 0x02 {
+        0 => String::new(),
+        _ => {
+            let rest = &description[17..];
+            let end = rest
+                .iter()
+                .position(|byte| *byte == 0)
+                .ok_or_else(|| c2pa_error("unterminated label"))?;
+            String::from_utf8(rest[..end].to_vec()).map_err(|_| c2pa_error("label is not UTF-8"))?
+        }
+    };
+    Ok(Superbox {
+        content_type: [description[0], description[1], description[2], description[3]],
+        label,
+        contents: jumbf.payload,
+        children,
+    })
+}
+
+impl<'a> Superbox<'a> {
+    fn superboxes(&self) -> Result<Vec<Superbox<'a>>, SonnunError> {
+        self.children
+            .iter()
+            .filter(|child| &child.box_type == b"jumb")
+            .map(read_superbox)
+            .collect()
+    }
+
+    fn child(&self, label: &str) -> Result<Superbox<'a>, SonnunError> {
+        self.superboxes()?
+            .into_iter()
+            .find(|child| child.label == label)
+            .ok_or_else(|| c2pa_error(format!("{} is missing from {}", label, self.label)))
+    }
+
+    fn content(&self, box_type: &[u8; 4]) -> Result<&'a [u8], SonnunError> {
+        self.children
+            .iter()
+            .find(|child| &child.box_type == box_type)
+            .map(|child| child.payload)
+            .ok_or_else(|| c2pa_error(format!("{} has no content box", self.label)))
+    }
+}
+
+// Byte strings (hashes, certificates) have no JSON form of their own
+fn bytes_as_base64(value: CborValue) -> CborValue {
+    match value {
+        CborValue::Bytes(bytes) => CborValue::Text(BASE64.encode(bytes)),
+        CborValue::Array(items) => CborValue::Array(items.into_iter().map(bytes_as_base64).collect()),
+        CborValue::Map(entries) => CborValue::Map(
+            entries
+                .into_iter()
+                .map(|(key, value)| (key, bytes_as_base64(value)))
+                .collect(),
+        ),
+        other => other,
+    }
+}
+
+fn decode_cbor(bytes: &[u8]) -> Result<Value, SonnunError> {
+    let value: CborValue = coset::cbor::de::from_reader(bytes).map_err(c2pa_error)?;
+    from_cbor(bytes_as_base64(value))
+}
+
+// The first x5chain certificate is the signer's; the rest only matter to readers that build a path
+fn verify_claim_signature(envelope: &[u8], claim: &[u8]) -> Result<(VerifyingKey, Certificate), SonnunError> {
+    let sign1 = CoseSign1::from_tagged_slice(envelope)
+        .or_else(|_| CoseSign1::from_slice(envelope))
+        .map_err(cose_error)?;
+    let header = &sign1.protected.header;
+    if header.alg != Some(coset::Algorithm::Assigned(iana::Algorithm::EdDSA)) {
+        return Err(c2pa_error("claim is not signed with EdDSA"));
+    }
+    let x5chain = Label::Int(iana::HeaderParameter::X5Chain.to_i64());
+    let chain = header
+        .rest
+        .iter()
+        .chain(&sign1.unprotected.rest)
+        .find(|(label, _)| *label == x5chain)
+        .map(|(_, chain)| chain)
+        .ok_or_else(|| c2pa_error("claim signature has no x5chain"))?;
+    let leaf = match chain {
+        CborValue::Bytes(der) => der,
+        CborValue::Array(certificates) => match certificates.first() {
+            Some(CborValue::Bytes(der)) => der,
+            _ => return Err(c2pa_error("x5chain holds no certificate")),
+        },
+        _ => return Err(c2pa_error("x5chain holds no certificate")),
+    };
+    let leaf = Certificate::from_der(leaf).map_err(asn1_error)?;
+    let spki = leaf
+        .tbs_certificate
+        .subject_public_key_info
+        .to_der()
+        .map_err(asn1_error)?;
+    let public_key = VerifyingKey::from_public_key_der(&spki)
+        .map_err(|_| SonnunError::InvalidKey("C2PA signing certificate does not hold an Ed25519 key".to_string()))?;
+    sign1.verify_detached_signature(claim, b"", |signature, tbs| {
+        let signature = Signature::from_slice(signature)
+            .map_err(|e| SonnunError::InvalidSignature(format!("Invalid signature: {}", e)))?;
+        public_key
+            .verify(tbs, &signature)
+            .map_err(|_| SonnunError::SignatureMismatch("C2PA claim signature does not match".to_string()))
+    })?;
+    Ok((public_key, leaf))
+}
+
+// AIDEV-NOTE: Reads the active (last) manifest of a store. Only assertions the signed claim lists, with
+// a matching hash, are returned; anything else in the assertion store is ignored.
+pub fn read_c2pa_manifest(store: &[u8]) -> Result<C2paManifest, SonnunError> {
+    let boxes = read_boxes(store)?;
+    let [root] = boxes.as_slice() else {
+        return Err(c2pa_error("expected a single manifest store box"));
+    };
+    let root = read_superbox(root)?;
+    if &root.content_type != b"c2pa" {
+        return Err(c2pa_error("not a C2PA manifest store"));
+    }
+    let manifest = root
+        .superboxes()?
+        .pop()
+        .filter(|manifest| &manifest.content_type == b"c2ma")
+        .ok_or_else(|| c2pa_error("store holds no standard manifest"))?;
+
+    let claim_bytes = manifest.child(CLAIM_LABEL)?.content(b"cbor")?;
+    let (public_key, leaf) = verify_claim_signature(manifest.child(SIGNATURE_LABEL)?.content(b"cbor")?, claim_bytes)?;
+    let claim = decode_cbor(claim_bytes)?;
+    if claim["alg"].as_str().unwrap_or("sha256") != "sha256" {
+        return Err(c2pa_error(format!("unsupported hash algorithm {}", claim["alg"])));
+    }
+
+    let stored = manifest.child(ASSERTIONS_LABEL)?.superboxes()?;
+    let assertions = claim["assertions"]
+        .as_array()
+        .ok_or_else(|| c2pa_error("claim lists no assertions"))?
+        .iter()
+        .map(|hashed_uri| {
+            let label = hashed_uri["url"]
+                .as_str()
+                .and_then(|url| url.rsplit_once(&format!("{}/", ASSERTIONS_LABEL)))
+                .map(|(_, label)| label)
+                .ok_or_else(|| c2pa_error(format!("bad assertion reference {}", hashed_uri["url"])))?;
+            let assertion = stored
+                .iter()
+                .find(|assertion| assertion.label == label)
+                .ok_or_else(|| c2pa_error(format!("assertion {} is missing", label)))?;
+            if hashed_uri["hash"] != BASE64.encode(Sha256::digest(assertion.contents)) {
+                return Err(SonnunError::SignatureMismatch(format!(
+                    "C2PA assertion {} does not match the claim",
+                    label
+                )));
+            }
+            Ok(C2paAssertion {
+                label: label.to_string(),
+                data: decode_cbor(assertion.content(b"cbor")?)?,
+            })
+        })
+        .collect::<Result<_, SonnunError>>()?;
+
+    let field = |name: &str| claim[name].as_str().unwrap_or_default().to_string();
+    Ok(C2paManifest {
+        label: manifest.label.clone(),
+        claim_generator: field("claim_generator"),
+        title: field("dc:title"),
+        format: field("dc:format"),
+        instance_id: field("instanceID"),
+        assertions,
+        signer: leaf.tbs_certificate.subject.to_string(),
+        public_key: BASE64.encode(public_key.as_bytes()),
+    })
+}
+
+impl C2paManifest {
+    pub fn assertion(&self, label: &str) -> Option<&Value> {
+        self.assertions
+            .iter()
+            .find(|assertion| assertion.label == label)
+            .map(|assertion| &assertion.data)
+    }
+
+    pub fn actions(&self) -> &[Value] {
+        self.assertion(ACTIONS_LABEL)
+            .or_else(|| self.assertion("c2pa.actions"))
+            .and_then(|data| data["actions"].as_array())
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    pub fn ingredients(&self) -> Vec<&str> {
+        self.assertions
+            .iter()
+            .filter(|assertion| assertion.label.starts_with(INGREDIENT_LABEL))
+            .filter_map(|assertion| assertion.data["dc:title"].as_str())
+            .collect()
+    }
+
+    // Only a whole-asset SHA-256 binding can cover a page; exclusions are for embedded manifests
+    pub fn binds(&self, asset: &[u8]) -> bool {
+        self.assertion(DATA_HASH_LABEL).is_some_and(|binding| {
+            binding["alg"] == "sha256"
+                && binding
+                    .get("exclusions")
+                    .map_or(true, |exclusions| exclusions == &json!([]))
+                && binding["hash"] == BASE64.encode(Sha256::digest(asset))
+        })
+    }
+}
+
+// RFC 9562 version 8 (custom) UUID from the first 16 bytes of a hex digest
+fn uuid_from_hash(hex_digest: &str) -> String {
+    let mut hex: Vec<char> = hex_digest.chars().take(32).collect();
+    hex.resize(32, '0');
+    hex[12] = '8';
+    let variant = hex[16].to_digit(16).unwrap_or(0);
+    hex[16] = std::char::from_digit(0x8 | (variant & 0x3), 16).expect("nibble is a hex digit");
+    let hex: String = hex.into_iter().collect();
+    format!(
+        "{}-{}-{}-{}-{}",
+        &hex[..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..]
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keystore::test_keys::{test_keystore, unlocked_key};
+    use crate::test_manifest::manifest_data;
+
+    const PAGE: &[u8] = b"<html><body><p>Post</p></body></html>";
+
+    fn replace(bytes: &[u8], from: &[u8], to: &[u8]) -> Vec<u8> {
+        let at = bytes.windows(from.len()).position(|window| window == from).unwrap();
+        [&bytes[..at], to, &bytes[at + from.len()..]].concat()
+    }
+
+    #[test]
+    fn test_manifest_store_marks_ai_portions_and_cited_ingredients() {
+        let (keystore, dir) = test_keystore("c2pa");
+        let key = unlocked_key(&keystore, "Author");
+
+        let store = build_c2pa_manifest(&keystore, &key.key_id, &manifest_data(), "Post", PAGE).unwrap();
+        assert_eq!(&store[4..8], b"jumb");
+        assert_eq!(&store[16..20], b"c2pa");
+        // Re-exporting the same manifest gives the same bytes
+        assert_eq!(
+            build_c2pa_manifest(&keystore, &key.key_id, &manifest_data(), "Post", PAGE).unwrap(),
+            store
+        );
+
+        let manifest = read_c2pa_manifest(&store).unwrap();
+        assert_eq!(manifest.public_key, key.public_key);
+        assert_eq!(manifest.title, "Post");
+        assert_eq!(manifest.format, "text/html");
+        assert!(manifest.signer.contains(&key.key_id));
+        let actions = manifest.actions();
+        assert_eq!(actions.len(), 3);
+        assert_eq!(actions[1]["action"], "c2pa.created");
+        assert_eq!(
+            actions[1]["digitalSourceType"],
+            "http://cv.iptc.org/newscodes/digitalsourcetype/trainedAlgorithmicMedia"
+        );
+        assert_eq!(actions[1]["parameters"]["sonnun.percentage"], 30);
+        assert_eq!(actions[2]["action"], "c2pa.placed");
+        assert!(actions[2].get("digitalSourceType").is_none());
+        assert_eq!(
+            actions[2]["parameters"]["ingredients"][1]["url"],
+            "self#jumbf=c2pa.assertions/c2pa.ingredient__1"
+        );
+        assert_eq!(
+            manifest.ingredients(),
+            ["https://example.com/a", "https://example.com/b"]
+        );
+
+        assert!(manifest.binds(PAGE));
+        assert!(!manifest.binds(b"<html><body><p>Pest</p></body></html>"));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_edited_store_fails_verification() {
+        let (keystore, dir) = test_keystore("c2pa-edit");
+        let key = unlocked_key(&keystore, "Author");
+        let store = build_c2pa_manifest(&keystore, &key.key_id, &manifest_data(), "Post", PAGE).unwrap();
+
+        // Relabelling the cited share breaks the actions assertion's hash
+        let edited = replace(&store, b"c2pa.placed", b"c2pa.opened");
+        assert_eq!(read_c2pa_manifest(&edited).unwrap_err().code(), "SIGNATURE_MISMATCH");
+        // Retitling breaks the claim signature
+        let edited = replace(&store, b"Post", b"Pest");
+        assert_eq!(read_c2pa_manifest(&edited).unwrap_err().code(), "SIGNATURE_MISMATCH");
+        assert_eq!(read_c2pa_manifest(b"{}").unwrap_err().code(), "MANIFEST_SCHEMA");
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,254 @@
+// AIDEV-NOTE: Binary envelope for API consumers: the manifest as deterministic CBOR (RFC 8949 §4.2.1
+// core deterministic encoding) signed as a tagged COSE_Sign1 (RFC 9052) with EdDSA. The signing key
+// is named by `kid`, its fingerprint as in the keystore; the envelope carries no key of its own.
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine as _;
+use coset::cbor::value::{Integer, Value as CborValue};
+use coset::{iana, CborSerializable, CoseSign1, CoseSign1Builder, HeaderBuilder, TaggedCborSerializable};
+use ed25519_dalek::{Signature, Verifier};
+use serde_json::{Map, Number, Value};
+
+use crate::error::SonnunError;
+use crate::keystore::{key_fingerprint, Keystore};
+use crate::verifier::{
+    reject_html_only_options, resolve_fingerprinted_key, verify_sole_signer_manifest, VerificationResult, VerifyOptions,
+};
+
+pub const COSE_CONTENT_TYPE: &str = "application/vnd.sonnun.manifest+cbor";
+
+// The parts of an envelope readable without verifying it
+#[derive(Debug, Clone, PartialEq)]
+pub struct CoseManifest {
+    pub manifest: Value,
+    pub key_id: String, // kid: keystore fingerprint of the signing key
+}
+
+fn cbor_error(e: impl std::fmt::Display) -> SonnunError {
+    SonnunError::ManifestSchema(format!("Invalid CBOR manifest: {}", e))
+}
+
+fn cose_error(e: coset::CoseError) -> SonnunError {
+    SonnunError::ManifestSchema(format!("Invalid COSE_Sign1 envelope: {}", e))
+}
+
+// AIDEV-NOTE: Integral floats are written as integers (the §4.2.2 numeric reduction), so 70 and 70.0
+// encode alike, as they canonicalize alike under JCS. Map keys sort by their encoded bytes.
+pub(crate) fn to_cbor(value: &Value) -> Result<CborValue, SonnunError> {
+    Ok(match value {
+        Value::Null => CborValue::Null,
+        Value::Bool(b) => CborValue::Bool(*b),
+        Value::Number(n) => match (n.as_u64(), n.as_i64(), n.as_f64()) {
+            (Some(u), _, _) => CborValue::Integer(Integer::from(u)),
+            (None, Some(i), _) => CborValue::Integer(Integer::from(i)),
+            (None, None, Some(f)) if f.fract() == 0.0 && f.abs() < 9_007_199_254_740_992.0 => {
+                CborValue::Integer(Integer::from(f as i64))
+            }
+            (None, None, Some(f)) => CborValue::Float(f),
+            _ => return Err(cbor_error(format!("unrepresentable number {}", n))),
+        },
+        Value::String(s) => CborValue::Text(s.clone()),
+        Value::Array(items) => CborValue::Array(items.iter().map(to_cbor).collect::<Result<_, _>>()?),
+        Value::Object(map) => {
+            let mut entries = map
+                .iter()
+                .map(|(key, value)| Ok((write_cbor(&CborValue::Text(key.clone()))?, key, to_cbor(value)?)))
+                .collect::<Result<Vec<_>, SonnunError>>()?;
+            entries.sort_by(|a, b| a.0.cmp(&b.0));
+            CborValue::Map(
+                entries
+                    .into_iter()
+                    .map(|(_, key, value)| (CborValue::Text(key.clone()), value))
+                    .collect(),
+            )
+        }
+    })
+}
+
+pub(crate) fn from_cbor(value: CborValue) -> Result<Value, SonnunError> {
+    Ok(match value {
+        CborValue::Null => Value::Null,
+        CborValue::Bool(b) => Value::Bool(b),
+        CborValue::Integer(i) => {
+            let i = i128::from(i);
+            match (u64::try_from(i), i64::try_from(i)) {
+                (Ok(u), _) => Value::from(u),
+                (_, Ok(i)) => Value::from(i),
+                _ => return Err(cbor_error(format!("integer {} out of range", i))),
+            }
+        }
+        CborValue::Float(f) => Value::Number(Number::from_f64(f).ok_or_else(|| cbor_error("non-finite float"))?),
+        CborValue::Text(s) => Value::String(s),
+        CborValue::Array(items) => Value::Array(items.into_iter().map(from_cbor).collect::<Result<_, _>>()?),
+        CborValue::Map(entries) => {
+            let mut map = Map::new();
+            for (key, value) in entries {
+                let key = match key {
+                    CborValue::Text(key) => key,
+                    _ => return Err(cbor_error("map keys must be text")),
+                };
+                if map.insert(key.clone(), from_cbor(value)?).is_some() {
+                    return Err(cbor_error(format!("duplicate key '{}'", key)));
+                }
+            }
+            Value::Object(map)
+        }
+        other => return Err(cbor_error(format!("unsupported item {:?}", other))),
+    })
+}
+
+pub(crate) fn write_cbor(value: &CborValue) -> Result<Vec<u8>, SonnunError> {
+    let mut bytes = Vec::new();
+    coset::cbor::ser::into_writer(value, &mut bytes).map_err(cbor_error)?;
+    Ok(bytes)
+}
+
+pub fn encode_manifest_cbor(manifest: &Value) -> Result<Vec<u8>, SonnunError> {
+    if !manifest.is_object() {
+        return Err(SonnunError::ManifestSchema("Manifest must be a JSON object".to_string()));
+    }
+    write_cbor(&to_cbor(manifest)?)
+}
+
+// Only the deterministic encoding is accepted: anything else would re-encode to different bytes
+pub fn decode_manifest_cbor(bytes: &[u8]) -> Result<Value, SonnunError> {
+    let value: CborValue = coset::cbor::de::from_reader(bytes).map_err(cbor_error)?;
+    let manifest = from_cbor(value)?;
+    if encode_manifest_cbor(&manifest)? != bytes {
+        return Err(cbor_error("not in deterministic encoding"));
+    }
+    Ok(manifest)
+}
+
+pub fn sign_manifest_cose(keystore: &Keystore, key_id: &str, manifest: &Value) -> Result<Vec<u8>, SonnunError> {
+    let public_key = keystore.ed25519_public_key(key_id)?;
+    let protected = HeaderBuilder::new()
+        .algorithm(iana::Algorithm::EdDSA)
+        .key_id(key_fingerprint(public_key.as_bytes()).into_bytes())
+        .content_type(COSE_CONTENT_TYPE.to_string())
+        .build();
+    CoseSign1Builder::new()
+        .protected(protected)
+        .payload(encode_manifest_cbor(manifest)?)
+        .try_create_signature(b"", |tbs| {
+            BASE64
+                .decode(keystore.sign(key_id, tbs)?)
+                .map_err(|e| SonnunError::InvalidSignature(format!("Invalid signature encoding: {}", e)))
+        })?
+        .build()
+        .to_tagged_vec()
+        .map_err(cose_error)
+}
+
+fn parse_envelope(envelope: &[u8]) -> Result<(CoseSign1, CoseManifest), SonnunError> {
+    let sign1 = CoseSign1::from_tagged_slice(envelope)
+        .or_else(|_| CoseSign1::from_slice(envelope))
+        .map_err(cose_error)?;
+    let header = &sign1.protected.header;
+    if header.alg != Some(coset::Algorithm::Assigned(iana::Algorithm::EdDSA)) {
+        return Err(SonnunError::ManifestSchema("COSE envelope is not signed with EdDSA".to_string()));
+    }
+    let key_id = String::from_utf8(header.key_id.clone())
+        .map_err(|_| SonnunError::ManifestSchema("COSE kid is not a key fingerprint".to_string()))?;
+    let payload = sign1
+        .payload
+        .as_deref()
+        .ok_or_else(|| SonnunError::ManifestSchema("COSE envelope has no payload".to_string()))?;
+    let manifest = decode_manifest_cbor(payload)?;
+    Ok((sign1, CoseManifest { manifest, key_id }))
+}
+
+pub fn decode_cose_manifest(envelope: &[u8]) -> Result<CoseManifest, SonnunError> {
+    parse_envelope(envelope).map(|(_, manifest)| manifest)
+}
+
+pub fn verify_cose(envelope: &[u8], page: Option<&str>, options: &VerifyOptions) -> Result<VerificationResult, SonnunError> {
+    reject_html_only_options(options, "COSE envelopes")?;
+    let (sign1, cose) = parse_envelope(envelope)?;
+    let verifying_key = resolve_fingerprinted_key(&cose.manifest, &cose.key_id, options)?;
+    sign1.verify_signature(b"", |signature, tbs| {
+        let signature = Signature::from_slice(signature)
+            .map_err(|e| SonnunError::InvalidSignature(format!("Invalid signature: {}", e)))?;
+        verifying_key
+            .verify(tbs, &signature)
+            .map_err(|_| SonnunError::SignatureMismatch("COSE signature does not match manifest".to_string()))
+    })?;
+    verify_sole_signer_manifest(cose.manifest, &verifying_key, page, options)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto_utils::parse_public_key;
+    use crate::export::CONTENT_HASH_FIELD;
+    use crate::key_formats::did_key;
+    use crate::keystore::test_keys::{test_keystore, unlocked_key};
+    use crate::test_manifest::manifest_value;
+    use serde_json::json;
+
+    #[test]
+    fn test_cbor_encoding_is_deterministic() {
+        let a = json!({"total_characters": 100, "ai_percentage": 20.0, "b": [1.5, null, true], "aa": "x"});
+        let b = json!({"aa": "x", "b": [1.5, null, true], "ai_percentage": 20, "total_characters": 100});
+        let encoded = encode_manifest_cbor(&a).unwrap();
+        assert_eq!(encoded, encode_manifest_cbor(&b).unwrap());
+        // Shorter keys first, 20.0 as the one-byte integer 0x14, 1.5 as a half-precision float
+        assert_eq!(&encoded[..4], &[0xa4, 0x61, b'b', 0x83]);
+        assert_eq!(&encoded[4..7], &[0xf9, 0x3e, 0x00]);
+        assert_eq!(decode_manifest_cbor(&encoded).unwrap(), b);
+
+        // The same map with keys out of order is rejected
+        let mut unsorted = Vec::new();
+        let map = CborValue::Map(vec![
+            (CborValue::Text("bb".to_string()), CborValue::Integer(1.into())),
+            (CborValue::Text("a".to_string()), CborValue::Integer(2.into())),
+        ]);
+        coset::cbor::ser::into_writer(&map, &mut unsorted).unwrap();
+        assert_eq!(decode_manifest_cbor(&unsorted).unwrap_err().code(), "MANIFEST_SCHEMA");
+    }
+
+    #[test]
+    fn test_cose_envelope_verifies_with_author_did_or_trusted_key() {
+        let (keystore, dir) = test_keystore("cose");
+        let key = unlocked_key(&keystore, "Author");
+        let did = did_key(&parse_public_key(&key.public_key).unwrap());
+        let manifest = manifest_value();
+
+        let unbound = sign_manifest_cose(&keystore, &key.key_id, &manifest).unwrap();
+        assert_eq!(unbound[0], 0xd2); // tag 18, COSE_Sign1
+        assert_eq!(decode_cose_manifest(&unbound).unwrap().key_id, key.key_id);
+        let options = VerifyOptions {
+            trusted_key: Some(key.public_key.clone()),
+            allow_unbound_content: true,
+            ..Default::default()
+        };
+        assert_eq!(verify_cose(&unbound, None, &options).unwrap().author_did, did);
+        // Without author_did or --key nothing names the signing key
+        let error = verify_cose(&unbound, None, &VerifyOptions { allow_unbound_content: true, ..Default::default() });
+        assert_eq!(error.unwrap_err().code(), "INVALID_KEY");
+
+        let hash = crate::export::content_hash("<p>Hello</p>");
+        let mut bound = manifest.clone();
+        bound["author_did"] = json!(did);
+        bound[CONTENT_HASH_FIELD] = json!(hash);
+        let envelope = sign_manifest_cose(&keystore, &key.key_id, &bound).unwrap();
+        verify_cose(&envelope, Some("<p>Hello</p>"), &VerifyOptions::default()).unwrap();
+        // A bound envelope checked without its page is not a match
+        assert_eq!(verify_cose(&envelope, None, &VerifyOptions::default()).unwrap_err().code(), "CONTENT_MISMATCH");
+        let error = verify_cose(&envelope, Some("<p>Goodbye</p>"), &VerifyOptions::default()).unwrap_err();
+        assert_eq!(error.code(), "CONTENT_MISMATCH");
+
+        // The EdDSA envelope cannot vouch for a manifest that claims another signature algorithm
+        let mut relabelled = bound.clone();
+        relabelled["signature_algorithm"] = json!("ecdsa-p256");
+        let relabelled = sign_manifest_cose(&keystore, &key.key_id, &relabelled).unwrap();
+        let error = verify_cose(&relabelled, Some("<p>Hello</p>"), &VerifyOptions::default()).unwrap_err();
+        assert_eq!(error.code(), "MANIFEST_SCHEMA");
+
+        // A changed content_hash digit still decodes, but no longer matches the signature
+        let mut forged = envelope.clone();
+        let position = forged.windows(hash.len()).position(|window| window == hash.as_bytes()).unwrap();
+        forged[position] = if forged[position] == b'0' { b'1' } else { b'0' };
+        assert_eq!(verify_cose(&forged, None, &VerifyOptions::default()).unwrap_err().code(), "SIGNATURE_MISMATCH");
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
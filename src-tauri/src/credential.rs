@@ -0,0 +1,269 @@
+// AIDEV-NOTE: W3C Verifiable Credential (VC Data Model 2.0) form of the provenance manifest, for
+// partners that only take VCs. The author's did:key is the issuer, the page's content_hash is the
+// subject, and the proof is a Data Integrity proof with the eddsa-jcs-2022 cryptosuite: Ed25519 over
+// SHA-256(JCS(proof options)) || SHA-256(JCS(credential without proof)).
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine as _;
+use chrono::{SecondsFormat, Utc};
+use ed25519_dalek::{Signature, Verifier};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+
+use crate::error::SonnunError;
+use crate::jcs::canonicalize_bytes;
+use crate::key_formats::{did_key, parse_did_key};
+use crate::keystore::Keystore;
+use crate::replay::parse_timestamp;
+use crate::verifier::{reject_html_only_options, verify_sole_signer, VerificationResult, VerifyOptions};
+use crate::ManifestData;
+
+pub const CREDENTIALS_V2_CONTEXT: &str = "https://www.w3.org/ns/credentials/v2";
+pub const PROVENANCE_CREDENTIAL_TYPE: &str = "ProvenanceManifestCredential";
+const VERIFIABLE_CREDENTIAL_TYPE: &str = "VerifiableCredential";
+const PROOF_TYPE: &str = "DataIntegrityProof";
+const CRYPTOSUITE: &str = "eddsa-jcs-2022";
+const PROOF_PURPOSE: &str = "assertionMethod";
+// Multibase prefix for base58btc
+const MULTIBASE_BASE58BTC: char = 'z';
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VerifiableCredential {
+    #[serde(rename = "@context")]
+    pub context: Vec<String>,
+    #[serde(rename = "type")]
+    pub types: Vec<String>,
+    pub issuer: String, // did:key of the author
+    pub valid_from: String,
+    pub credential_subject: CredentialSubject,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub proof: Option<DataIntegrityProof>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CredentialSubject {
+    pub id: String, // content_hash as a URN
+    pub content_hash: String,
+    pub manifest: ManifestData,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DataIntegrityProof {
+    #[serde(rename = "@context", default, skip_serializing_if = "Option::is_none")]
+    pub context: Option<Vec<String>>,
+    #[serde(rename = "type")]
+    pub proof_type: String,
+    pub cryptosuite: String,
+    pub created: String,
+    pub verification_method: String, // did:key URL: the DID, '#', its own method-specific id
+    pub proof_purpose: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub proof_value: String, // multibase base58btc Ed25519 signature
+}
+
+fn subject_id(content_hash: &str) -> String {
+    format!("urn:sha256:{}", content_hash)
+}
+
+fn verification_method(did: &str) -> String {
+    let fragment = did.strip_prefix("did:key:").unwrap_or(did);
+    format!("{}#{}", did, fragment)
+}
+
+fn to_value<T: Serialize>(value: &T) -> Result<Value, SonnunError> {
+    serde_json::to_value(value).map_err(|e| SonnunError::ManifestSchema(format!("Failed to serialize credential: {}", e)))
+}
+
+// AIDEV-NOTE: The eddsa-jcs-2022 hash data. proof_options is the proof without proofValue, document
+// the credential without proof; both are hashed separately so neither can be moved into the other.
+fn hash_data(proof_options: &Value, document: &Value) -> Result<Vec<u8>, SonnunError> {
+    let mut data = Sha256::digest(canonicalize_bytes(proof_options)?).to_vec();
+    data.extend_from_slice(&Sha256::digest(canonicalize_bytes(document)?));
+    Ok(data)
+}
+
+pub fn issue_credential(
+    keystore: &Keystore,
+    key_id: &str,
+    manifest: ManifestData,
+    content_hash: &str,
+) -> Result<VerifiableCredential, SonnunError> {
+    let issuer = did_key(&keystore.ed25519_public_key(key_id)?);
+    if manifest.author_did.as_deref().is_some_and(|author_did| author_did != issuer) {
+        return Err(SonnunError::InvalidKey("Manifest author_did is not the issuing key".to_string()));
+    }
+    let issued_at = Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true);
+    let mut credential = VerifiableCredential {
+        context: vec![CREDENTIALS_V2_CONTEXT.to_string()],
+        types: vec![VERIFIABLE_CREDENTIAL_TYPE.to_string(), PROVENANCE_CREDENTIAL_TYPE.to_string()],
+        issuer: issuer.clone(),
+        valid_from: issued_at.clone(),
+        credential_subject: CredentialSubject {
+            id: subject_id(content_hash),
+            content_hash: content_hash.to_string(),
+            manifest,
+        },
+        proof: None,
+    };
+    let mut proof = DataIntegrityProof {
+        context: Some(credential.context.clone()),
+        proof_type: PROOF_TYPE.to_string(),
+        cryptosuite: CRYPTOSUITE.to_string(),
+        created: issued_at,
+        verification_method: verification_method(&issuer),
+        proof_purpose: PROOF_PURPOSE.to_string(),
+        proof_value: String::new(),
+    };
+    let signature = keystore.sign(key_id, &hash_data(&to_value(&proof)?, &to_value(&credential)?)?)?;
+    let signature = BASE64
+        .decode(signature)
+        .map_err(|e| SonnunError::InvalidSignature(format!("Invalid signature encoding: {}", e)))?;
+    proof.proof_value = format!("{}{}", MULTIBASE_BASE58BTC, bs58::encode(signature).into_string());
+    credential.proof = Some(proof);
+    Ok(credential)
+}
+
+// AIDEV-NOTE: The proof is checked over the JSON exactly as received (minus proofValue / proof), not over
+// a re-serialization of the typed struct, so fields this version does not know still count as signed.
+// `page`, when given, is the published HTML; its content must hash to the credential subject.
+pub fn verify_credential(
+    json: &str,
+    page: Option<&str>,
+    options: &VerifyOptions,
+) -> Result<VerificationResult, SonnunError> {
+    let mut document: Value =
+        serde_json::from_str(json).map_err(|e| SonnunError::ManifestSchema(format!("Invalid credential JSON: {}", e)))?;
+    let credential: VerifiableCredential = serde_json::from_value(document.clone())
+        .map_err(|e| SonnunError::ManifestSchema(format!("Invalid provenance credential: {}", e)))?;
+    let is_credential = credential.types.iter().any(|t| t == VERIFIABLE_CREDENTIAL_TYPE)
+        && credential.context.first().map(String::as_str) == Some(CREDENTIALS_V2_CONTEXT);
+    if !is_credential {
+        return Err(SonnunError::ManifestSchema("Not a W3C Verifiable Credential (v2)".to_string()));
+    }
+    reject_html_only_options(options, "credentials")?;
+
+    let proof = credential
+        .proof
+        .as_ref()
+        .ok_or_else(|| SonnunError::ManifestSchema("Credential has no proof".to_string()))?;
+    if proof.proof_type != PROOF_TYPE || proof.cryptosuite != CRYPTOSUITE {
+        return Err(SonnunError::ManifestSchema(format!(
+            "Unsupported proof {} / {}",
+            proof.proof_type, proof.cryptosuite
+        )));
+    }
+    if proof.proof_purpose != PROOF_PURPOSE {
+        return Err(SonnunError::ManifestSchema(format!("Unexpected proof purpose {}", proof.proof_purpose)));
+    }
+    if let Some(context) = &proof.context {
+        if !credential.context.starts_with(context) {
+            return Err(SonnunError::ManifestSchema("Proof @context does not match the credential".to_string()));
+        }
+    }
+    let verifying_key = parse_did_key(&credential.issuer)?;
+    if proof.verification_method != verification_method(&credential.issuer) {
+        return Err(SonnunError::InvalidKey("Proof verification method is not the issuer's key".to_string()));
+    }
+    if let Some(author_did) = &credential.credential_subject.manifest.author_did {
+        if *author_did != credential.issuer {
+            return Err(SonnunError::InvalidKey(format!(
+                "author_did {} does not match the credential issuer",
+                author_did
+            )));
+        }
+    }
+
+    let signature = proof
+        .proof_value
+        .strip_prefix(MULTIBASE_BASE58BTC)
+        .ok_or_else(|| SonnunError::InvalidSignature("proofValue must be multibase base58btc".to_string()))
+        .and_then(|encoded| {
+            bs58::decode(encoded)
+                .into_vec()
+                .map_err(|e| SonnunError::InvalidSignature(format!("Invalid proofValue encoding: {}", e)))
+        })?;
+    let signature = Signature::from_slice(&signature)
+        .map_err(|e| SonnunError::InvalidSignature(format!("Invalid signature: {}", e)))?;
+    let mut proof_options = document
+        .as_object_mut()
+        .and_then(|document| document.remove("proof"))
+        .expect("credential with a proof is an object holding it");
+    proof_options
+        .as_object_mut()
+        .ok_or_else(|| SonnunError::ManifestSchema("Credential proof must be an object".to_string()))?
+        .remove("proofValue");
+    verifying_key
+        .verify(&hash_data(&proof_options, &document)?, &signature)
+        .map_err(|_| SonnunError::SignatureMismatch("Credential proof does not match".to_string()))?;
+
+    let subject = &credential.credential_subject;
+    if subject.id != subject_id(&subject.content_hash) {
+        return Err(SonnunError::ManifestSchema("Credential subject id does not name its content_hash".to_string()));
+    }
+    let signed_at = parse_timestamp(&credential.valid_from)
+        .map_err(|e| SonnunError::ManifestSchema(format!("Invalid validFrom: {}", e.message())))?;
+    verify_sole_signer(
+        to_value(&subject.manifest)?,
+        Some(&subject.content_hash),
+        &verifying_key,
+        Some(&signed_at),
+        page,
+        options,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::content_hash;
+    use crate::keystore::test_keys::{test_keystore, unlocked_key};
+    use crate::test_manifest::manifest_data;
+    use crate::verifier::ContentBinding;
+
+    #[test]
+    fn test_credential_proof_verifies_and_binds_content() {
+        let (keystore, dir) = test_keystore("credential");
+        let key = unlocked_key(&keystore, "Author");
+
+        let page = "<p>Hello</p>";
+        let credential = issue_credential(&keystore, &key.key_id, manifest_data(), &content_hash(page)).unwrap();
+        let json = serde_json::to_string_pretty(&credential).unwrap();
+        assert!(json.contains("\"cryptosuite\": \"eddsa-jcs-2022\""));
+
+        let options = VerifyOptions {
+            trusted_key: Some(key.public_key.clone()),
+            ..Default::default()
+        };
+        let result = verify_credential(&json, Some(page), &options).unwrap();
+        assert_eq!(result.author_did, credential.issuer);
+        assert_eq!(result.public_key, key.public_key);
+        assert_eq!(result.rotation_path.len(), 0);
+        assert_eq!(result.content, ContentBinding::Matched(content_hash(page)));
+        // Without the page nothing can be compared; the result says so when that is allowed
+        assert_eq!(verify_credential(&json, None, &options).unwrap_err().code(), "CONTENT_MISMATCH");
+        let unchecked_options = VerifyOptions {
+            allow_unbound_content: true,
+            ..options.clone()
+        };
+        let unchecked = verify_credential(&json, None, &unchecked_options).unwrap();
+        assert_eq!(unchecked.content, ContentBinding::Unchecked(content_hash(page)));
+
+        let error = verify_credential(&json, Some("<p>Goodbye</p>"), &options).unwrap_err();
+        assert_eq!(error.code(), "CONTENT_MISMATCH");
+
+        // Fields the typed struct ignores are still covered by the proof
+        let mut tampered: Value = serde_json::from_str(&json).unwrap();
+        tampered["credentialSubject"]["note"] = Value::String("added later".to_string());
+        let error = verify_credential(&tampered.to_string(), None, &options).unwrap_err();
+        assert_eq!(error.code(), "SIGNATURE_MISMATCH");
+
+        let mut tampered: Value = serde_json::from_str(&json).unwrap();
+        tampered["credentialSubject"]["manifest"]["ai_percentage"] = Value::from(0.0);
+        let error = verify_credential(&tampered.to_string(), None, &options).unwrap_err();
+        assert_eq!(error.code(), "SIGNATURE_MISMATCH");
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -1,5 +1,12 @@
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine as _;
+use ed25519_dalek::{Signature, VerifyingKey};
 use sha2::{Digest, Sha256};
 
+use crate::algorithms::{signature_verifier, signer_from_bytes};
+use crate::error::SonnunError;
+use crate::SignatureAlgorithm;
+
 /// Generate a SHA256 hex digest for the provided text.
 pub fn hash_text(text: &str) -> String {
     let mut hasher = Sha256::new();
@@ -8,31 +15,24 @@ pub fn hash_text(text: &str) -> String {
 }
 
 /// Sign the given document contents using the provided private key bytes.
-#[tauri::command]
+/// `algorithm` defaults to ed25519; both algorithms take a 32-byte private key.
+/// Not exposed over IPC - the app signs through the keystore (`sign_with_key`).
 pub async fn sign_document(
     content: String,
     private_key_bytes: Vec<u8>,
-) -> Result<String, String> {
-    use ed25519_dalek::{Signer, SigningKey};
-
+    algorithm: Option<SignatureAlgorithm>,
+) -> Result<String, SonnunError> {
     if content.is_empty() {
-        return Err("Content cannot be empty".to_string());
+        return Err(SonnunError::InvalidInput("Content cannot be empty".to_string()));
     }
 
-    let signing_key = SigningKey::from_bytes(
-        &private_key_bytes
-            .try_into()
-            .map_err(|_| "Invalid private key length")?,
-    );
-
-    let signature = signing_key.sign(content.as_bytes());
-    Ok(base64::encode(signature.to_bytes()))
+    signer_from_bytes(algorithm.unwrap_or_default(), &private_key_bytes)?.sign(content.as_bytes())
 }
 
 /// Generate an ed25519 key pair returned as base64 encoded strings.
-#[tauri::command]
-pub fn generate_keypair() -> Result<(String, String), String> {
-    use ed25519_dalek::{SigningKey, VerifyingKey};
+/// Not exposed over IPC - use the keystore's `create_key` so the secret stays in Rust.
+pub fn generate_keypair() -> Result<(String, String), SonnunError> {
+    use ed25519_dalek::SigningKey;
     use rand::rngs::OsRng;
 
     let mut csprng = OsRng {};
@@ -46,39 +46,39 @@ pub fn generate_keypair() -> Result<(String, String), String> {
 }
 
 /// Verify the signature for the given document using the provided public key.
+/// `algorithm` defaults to ed25519, the algorithm of manifests that do not name one.
 #[tauri::command]
 pub fn verify_signature(
     content: String,
     signature_b64: String,
     public_key_b64: String,
-) -> Result<bool, String> {
-    use ed25519_dalek::{Signature, Verifier, VerifyingKey};
-
+    algorithm: Option<SignatureAlgorithm>,
+) -> Result<bool, SonnunError> {
     if content.is_empty() {
-        return Err("Content cannot be empty".to_string());
+        return Err(SonnunError::InvalidInput("Content cannot be empty".to_string()));
     }
 
-    let public_key_bytes = base64::decode(public_key_b64)
-        .map_err(|_| "Invalid public key encoding")?;
-    let signature_bytes = base64::decode(signature_b64)
-        .map_err(|_| "Invalid signature encoding")?;
-
-    let verifying_key = VerifyingKey::from_bytes(
-        &public_key_bytes
-            .try_into()
-            .map_err(|_| "Invalid public key length")?,
-    )
-    .map_err(|_| "Invalid public key format")?;
-
-    let signature = Signature::from_bytes(
-        &signature_bytes
-            .try_into()
-            .map_err(|_| "Invalid signature length")?,
-    );
+    signature_verifier(algorithm.unwrap_or_default(), &public_key_b64)?.verify(content.as_bytes(), &signature_b64)
+}
 
-    match verifying_key.verify(content.as_bytes(), &signature) {
-        Ok(()) => Ok(true),
-        Err(_) => Ok(false),
-    }
+/// Decode a base64 ed25519 public key, the encoding used in manifests and key files.
+pub fn parse_public_key(public_key_b64: &str) -> Result<VerifyingKey, SonnunError> {
+    let bytes = BASE64
+        .decode(public_key_b64)
+        .map_err(|e| SonnunError::InvalidKey(format!("Invalid public key encoding: {}", e)))?;
+    let bytes: [u8; 32] = bytes
+        .try_into()
+        .map_err(|_| SonnunError::InvalidKey("Invalid public key length".to_string()))?;
+    VerifyingKey::from_bytes(&bytes).map_err(|e| SonnunError::InvalidKey(format!("Invalid public key: {}", e)))
 }
 
+/// Decode a base64 ed25519 signature.
+pub fn parse_signature(signature_b64: &str) -> Result<Signature, SonnunError> {
+    let bytes = BASE64
+        .decode(signature_b64)
+        .map_err(|e| SonnunError::InvalidSignature(format!("Invalid signature encoding: {}", e)))?;
+    let bytes: [u8; 64] = bytes
+        .try_into()
+        .map_err(|_| SonnunError::InvalidSignature("Invalid signature length".to_string()))?;
+    Ok(Signature::from_bytes(&bytes))
+}
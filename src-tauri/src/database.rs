@@ -1,268 +1,1186 @@
-// AIDEV-NOTE: Database layer for provenance event persistence using SQLite
-use std::collections::HashMap;
-use sqlx::{Row, SqlitePool};
-
-use crate::{ProvenanceEvent, EventResponse, ManifestData};
-
-// AIDEV-NOTE: Database connection pool wrapper
-pub struct Database {
-    pool: SqlitePool,
-}
-
-impl Database {
-    pub async fn new(url: &str) -> Result<Self, String> {
-        let pool = SqlitePool::connect(url).await.map_err(|e| e.to_string())?;
-        sqlx::query(
-            "CREATE TABLE IF NOT EXISTS events (\n  id INTEGER PRIMARY KEY AUTOINCREMENT,\n  timestamp TEXT NOT NULL,\n  event_type TEXT NOT NULL,\n  text_hash TEXT NOT NULL,\n  source TEXT,\n  span_length INTEGER\n)"
-        )
-        .execute(&pool)
-        .await
-        .map_err(|e| e.to_string())?;
-        Ok(Self { pool })
-    }
-
-    // AIDEV-NOTE: Stores provenance event and returns generated ID
-    pub async fn insert_event(&self, event: ProvenanceEvent) -> Result<EventResponse, String> {
-        let result = sqlx::query(
-            "INSERT INTO events (timestamp, event_type, text_hash, source, span_length) VALUES (?1, ?2, ?3, ?4, ?5)"
-        )
-        .bind(&event.timestamp)
-        .bind(&event.event_type)
-        .bind(&event.text_hash)
-        .bind(&event.source)
-        .bind(event.span_length as i64)
-        .execute(&self.pool)
-        .await
-        .map_err(|e| e.to_string())?;
-
-        Ok(EventResponse {
-            id: result.last_insert_rowid(),
-            text_hash: event.text_hash,
-        })
-    }
-
-    // AIDEV-NOTE: Retrieves filtered event history with optional pagination
-    pub async fn get_events(
-        &self,
-        limit: Option<u32>,
-        event_type: Option<String>,
-    ) -> Result<Vec<ProvenanceEvent>, String> {
-        let mut query = String::from(
-            "SELECT timestamp, event_type, text_hash, source, span_length FROM events",
-        );
-        if event_type.is_some() {
-            query.push_str(" WHERE event_type = ?1");
-        }
-        query.push_str(" ORDER BY timestamp DESC");
-        if let Some(l) = limit {
-            query.push_str(&format!(" LIMIT {}", l));
-        }
-
-        let mut q = sqlx::query(&query);
-        if let Some(t) = &event_type {
-            q = q.bind(t);
-        }
-
-        let rows = q.fetch_all(&self.pool).await.map_err(|e| e.to_string())?;
-        let events = rows
-            .into_iter()
-            .map(|row| ProvenanceEvent {
-                timestamp: row.get::<String, _>("timestamp"),
-                event_type: row.get::<String, _>("event_type"),
-                text_hash: row.get::<String, _>("text_hash"),
-                source: row.get::<String, _>("source"),
-                span_length: row.get::<i64, _>("span_length") as usize,
-            })
-            .collect();
-        Ok(events)
-    }
-
-    // AIDEV-NOTE: Generates manifest data with statistics from all stored events
-    pub async fn generate_manifest(&self) -> Result<ManifestData, String> {
-        let events = self.get_events(None, None).await?;
-        
-        let mut human_chars = 0;
-        let mut ai_chars = 0;
-        let mut cited_chars = 0;
-        
-        for event in &events {
-            match event.event_type.as_str() {
-                "human" => human_chars += event.span_length,
-                "ai" => ai_chars += event.span_length,
-                "cited" => cited_chars += event.span_length,
-                _ => {} // Ignore unknown types
-            }
-        }
-        
-        let total_chars = human_chars + ai_chars + cited_chars;
-        
-        let human_percentage = if total_chars > 0 {
-            (human_chars as f64 / total_chars as f64) * 100.0
-        } else {
-            100.0
-        };
-        
-        let ai_percentage = if total_chars > 0 {
-            (ai_chars as f64 / total_chars as f64) * 100.0
-        } else {
-            0.0
-        };
-        
-        let cited_percentage = if total_chars > 0 {
-            (cited_chars as f64 / total_chars as f64) * 100.0
-        } else {
-            0.0
-        };
-        
-        Ok(ManifestData {
-            human_percentage,
-            ai_percentage,
-            cited_percentage,
-            total_characters: total_chars,
-            events,
-        })
-    }
-
-    // AIDEV-NOTE: Clear all events (useful for testing and development)
-    pub async fn clear_events(&self) -> Result<(), String> {
-        sqlx::query("DELETE FROM events")
-            .execute(&self.pool)
-            .await
-            .map_err(|e| e.to_string())?;
-        Ok(())
-    }
-
-    // AIDEV-NOTE: Get event count by type for analytics
-    pub async fn get_event_counts(&self) -> Result<HashMap<String, usize>, String> {
-        let rows = sqlx::query("SELECT event_type, COUNT(*) as count FROM events GROUP BY event_type")
-            .fetch_all(&self.pool)
-            .await
-            .map_err(|e| e.to_string())?;
-        let mut counts = HashMap::new();
-        for row in rows {
-            counts.insert(
-                row.get::<String, _>("event_type"),
-                row.get::<i64, _>("count") as usize,
-            );
-        }
-        Ok(counts)
-    }
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-    const TEST_DB_URL: &str = "sqlite::memory:";
-
-    fn create_test_event(event_type: &str, source: &str, span_length: usize) -> ProvenanceEvent {
-        ProvenanceEvent {
-            timestamp: chrono::Utc::now().to_rfc3339(),
-            event_type: event_type.to_string(),
-            text_hash: format!("hash_{}", source),
-            source: source.to_string(),
-            span_length,
-        }
-    }
-
-    #[tokio::test]
-    async fn test_insert_event() {
-        let db = Database::new(TEST_DB_URL).await.unwrap();
-        db.clear_events().await.unwrap();
-
-        let event = create_test_event("human", "user", 10);
-        let result = db.insert_event(event.clone()).await;
-        
-        assert!(result.is_ok());
-        let response = result.unwrap();
-        assert_eq!(response.text_hash, event.text_hash);
-        assert_eq!(response.id, 1);
-    }
-
-    #[tokio::test]
-    async fn test_get_events_with_filter() {
-        let db = Database::new(TEST_DB_URL).await.unwrap();
-        db.clear_events().await.unwrap();
-        
-        // Insert different types of events
-        db.insert_event(create_test_event("human", "user", 10)).await.unwrap();
-        db.insert_event(create_test_event("ai", "gpt-4", 15)).await.unwrap();
-        db.insert_event(create_test_event("cited", "wikipedia", 20)).await.unwrap();
-        
-        // Test filtering by type
-        let human_events = db.get_events(None, Some("human".to_string())).await.unwrap();
-        assert_eq!(human_events.len(), 1);
-        assert_eq!(human_events[0].event_type, "human");
-        
-        // Test no filter
-        let all_events = db.get_events(None, None).await.unwrap();
-        assert_eq!(all_events.len(), 3);
-    }
-
-    #[tokio::test]
-    async fn test_get_events_with_limit() {
-        let db = Database::new(TEST_DB_URL).await.unwrap();
-        db.clear_events().await.unwrap();
-        
-        // Insert multiple events
-        for i in 0..5 {
-            db.insert_event(create_test_event("human", &format!("user{}", i), 10)).await.unwrap();
-        }
-        
-        let limited_events = db.get_events(Some(3), None).await.unwrap();
-        assert_eq!(limited_events.len(), 3);
-    }
-
-    #[tokio::test]
-    async fn test_generate_manifest() {
-        let db = Database::new(TEST_DB_URL).await.unwrap();
-        db.clear_events().await.unwrap();
-        
-        // Insert events with known character counts
-        db.insert_event(create_test_event("human", "user", 60)).await.unwrap();
-        db.insert_event(create_test_event("ai", "gpt-4", 30)).await.unwrap();
-        db.insert_event(create_test_event("cited", "wikipedia", 10)).await.unwrap();
-        
-        let manifest = db.generate_manifest().await.unwrap();
-        
-        assert_eq!(manifest.human_percentage, 60.0);
-        assert_eq!(manifest.ai_percentage, 30.0);
-        assert_eq!(manifest.cited_percentage, 10.0);
-        assert_eq!(manifest.total_characters, 100);
-    }
-
-    #[tokio::test]
-    async fn test_get_event_counts() {
-        let db = Database::new(TEST_DB_URL).await.unwrap();
-        db.clear_events().await.unwrap();
-        
-        // Insert multiple events of different types
-        db.insert_event(create_test_event("human", "user1", 10)).await.unwrap();
-        db.insert_event(create_test_event("human", "user2", 10)).await.unwrap();
-        db.insert_event(create_test_event("ai", "gpt-4", 15)).await.unwrap();
-        
-        let counts = db.get_event_counts().await.unwrap();
-        
-        assert_eq!(counts.get("human"), Some(&2));
-        assert_eq!(counts.get("ai"), Some(&1));
-        assert_eq!(counts.get("cited"), None);
-    }
-
-    #[tokio::test]
-    async fn test_clear_events() {
-        let db = Database::new(TEST_DB_URL).await.unwrap();
-        
-        // Insert some events
-        db.insert_event(create_test_event("human", "user", 10)).await.unwrap();
-        
-        // Verify events exist
-        let events_before = db.get_events(None, None).await.unwrap();
-        assert!(!events_before.is_empty());
-        
-        // Clear events
-        db.clear_events().await.unwrap();
-        
-        // Verify events are cleared
-        let events_after = db.get_events(None, None).await.unwrap();
-        assert!(events_after.is_empty());
-    }
-}
\ No newline at end of file
+// AIDEV-NOTE: Database layer for provenance event persistence using SQLite
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::str::FromStr;
+use sqlx::sqlite::{SqliteConnectOptions, SqliteJournalMode, SqlitePoolOptions, SqliteSynchronous};
+use futures_util::stream::{self, BoxStream, StreamExt, TryStreamExt};
+use sqlx::{Row, SqliteConnection, SqlitePool};
+
+use crate::error::SonnunError;
+use crate::migrations;
+use crate::replay::{self, ReplayedDocument};
+use crate::spans::SpanRope;
+use crate::{
+    hash_text, ChainVerification, Document, EventCursor, EventPage, EventQuery, EventResponse, EventType,
+    LoggedEvent, ManifestData, Operation, ProvenanceEvent, SortOrder,
+};
+
+// AIDEV-NOTE: prev_hash of the very first row; every later row links to its predecessor's row_hash
+pub const GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+const DEFAULT_PAGE_SIZE: u32 = 100;
+const MAX_PAGE_SIZE: u32 = 1000;
+
+// AIDEV-NOTE: History query with every filter optional - a NULL parameter disables its clause, so the
+// SQL is fixed text and nothing user-supplied is ever formatted into it. Rows without a document are skipped.
+const EVENTS_DESC_SQL: &str = "SELECT id, document_id, timestamp, event_type, text_hash, source, span_length, operation, position_from, position_to, text FROM events \
+    WHERE document_id IS NOT NULL \
+    AND (?1 IS NULL OR document_id = ?1) \
+    AND (?2 IS NULL OR event_type = ?2) \
+    AND (?3 IS NULL OR source = ?3) \
+    AND (?4 IS NULL OR julianday(timestamp) >= julianday(?4)) \
+    AND (?5 IS NULL OR julianday(timestamp) <= julianday(?5)) \
+    AND (?6 IS NULL OR timestamp < ?6 OR (timestamp = ?6 AND id < ?7)) \
+    ORDER BY timestamp DESC, id DESC LIMIT ?8";
+
+const EVENTS_ASC_SQL: &str = "SELECT id, document_id, timestamp, event_type, text_hash, source, span_length, operation, position_from, position_to, text FROM events \
+    WHERE document_id IS NOT NULL \
+    AND (?1 IS NULL OR document_id = ?1) \
+    AND (?2 IS NULL OR event_type = ?2) \
+    AND (?3 IS NULL OR source = ?3) \
+    AND (?4 IS NULL OR julianday(timestamp) >= julianday(?4)) \
+    AND (?5 IS NULL OR julianday(timestamp) <= julianday(?5)) \
+    AND (?6 IS NULL OR timestamp > ?6 OR (timestamp = ?6 AND id > ?7)) \
+    ORDER BY timestamp ASC, id ASC LIMIT ?8";
+
+// AIDEV-NOTE: Database connection pool wrapper - opened once in run() and shared through tauri::State
+pub struct Database {
+    pool: SqlitePool,
+    // Per-document models, rebuilt from the log on first use and kept in step by insert_event
+    documents: tokio::sync::Mutex<HashMap<i64, DocumentState>>,
+}
+
+// AIDEV-NOTE: What replaying a document's log would produce, maintained one event at a time so that
+// insert_event can refuse an event replay would reject and generate_manifest never replays the log.
+struct DocumentState {
+    spans: SpanRope,
+    text: Option<Vec<u16>>,        // replayed text; None once any event lacks its payload
+    text_hash: Option<String>,     // hash of `text`, computed on demand and cleared by every edit
+    recorded_hash: Option<String>, // documents.text_hash as last written
+}
+
+impl DocumentState {
+    // The text is edited first: it checks everything the rope does and more, and both leave
+    // themselves untouched when they refuse an event
+    fn apply(&mut self, event: &ProvenanceEvent) -> Result<(), SonnunError> {
+        match (&mut self.text, &event.text) {
+            (Some(buffer), Some(payload)) => replay::apply_payload(buffer, event, payload)
+                .map_err(|reason| SonnunError::InvalidInput(format!("Event does not fit the document: {}", reason)))?,
+            (text, None) => *text = None,
+            (None, Some(_)) => {}
+        }
+        self.spans.apply(event)?;
+        self.text_hash = None;
+        Ok(())
+    }
+
+    fn text_hash(&mut self) -> Result<Option<String>, SonnunError> {
+        if let (None, Some(buffer)) = (&self.text_hash, &self.text) {
+            let text = String::from_utf16(buffer)
+                .map_err(|_| SonnunError::InvalidInput("Replayed text is not valid UTF-16".to_string()))?;
+            self.text_hash = Some(hash_text(&text));
+        }
+        Ok(self.text_hash.clone())
+    }
+}
+
+impl Database {
+    // AIDEV-NOTE: WAL lets history reads proceed while keystroke events are being appended
+    pub async fn new(url: &str) -> Result<Self, SonnunError> {
+        let options = SqliteConnectOptions::from_str(url)?
+            .create_if_missing(true)
+            .journal_mode(SqliteJournalMode::Wal)
+            .synchronous(SqliteSynchronous::Normal)
+            .foreign_keys(true);
+        let pool = SqlitePoolOptions::new()
+            .connect_with(options)
+            .await?;
+
+        migrations::run_migrations(&pool).await?;
+        Ok(Self {
+            pool,
+            documents: tokio::sync::Mutex::new(HashMap::new()),
+        })
+    }
+
+    pub async fn schema_version(&self) -> Result<i64, SonnunError> {
+        let mut conn = self.pool.acquire().await?;
+        migrations::current_version(&mut conn).await
+    }
+
+    pub async fn close(&self) {
+        self.pool.close().await;
+    }
+
+    // AIDEV-NOTE: Every article gets its own document row; events and manifests hang off its id
+    pub async fn create_document(&self, title: &str) -> Result<Document, SonnunError> {
+        let created_at = chrono::Utc::now().to_rfc3339();
+        let result = sqlx::query("INSERT INTO documents (title, created_at) VALUES (?1, ?2)")
+            .bind(title)
+            .bind(&created_at)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(Document {
+            id: result.last_insert_rowid(),
+            title: title.to_string(),
+            created_at,
+        })
+    }
+
+    pub async fn get_document(&self, document_id: i64) -> Result<Document, SonnunError> {
+        let row = sqlx::query("SELECT id, title, created_at FROM documents WHERE id = ?1")
+            .bind(document_id)
+            .fetch_optional(&self.pool)
+            .await?
+            .ok_or_else(|| SonnunError::NotFound(format!("Document {} not found", document_id)))?;
+
+        Ok(Document {
+            id: row.get::<i64, _>("id"),
+            title: row.get::<String, _>("title"),
+            created_at: row.get::<String, _>("created_at"),
+        })
+    }
+
+    pub async fn list_documents(&self) -> Result<Vec<Document>, SonnunError> {
+        let rows = sqlx::query("SELECT id, title, created_at FROM documents ORDER BY id DESC")
+            .fetch_all(&self.pool)
+            .await?;
+        let documents = rows
+            .into_iter()
+            .map(|row| Document {
+                id: row.get::<i64, _>("id"),
+                title: row.get::<String, _>("title"),
+                created_at: row.get::<String, _>("created_at"),
+            })
+            .collect();
+        Ok(documents)
+    }
+
+    // AIDEV-NOTE: Appends provenance event to the hash chain and returns generated ID.
+    // The event must fit the document: edits outside the current text, payloads that do not match
+    // their hash and deletions of text the document does not hold are rejected before anything is logged.
+    pub async fn insert_event(
+        &self,
+        event: ProvenanceEvent,
+    ) -> Result<EventResponse, SonnunError> {
+        // Resolve up front so an unknown document is reported clearly rather than as a constraint error
+        self.get_document(event.document_id).await?;
+
+        // Held across the write so the model and the log cannot drift apart
+        let mut documents = self.documents.lock().await;
+        let state = match documents.entry(event.document_id) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(self.load_state(event.document_id).await?),
+        };
+        state.apply(&event)?;
+
+        let result = self.append_event(event.clone()).await;
+        if result.is_err() {
+            // The state already has the edit; drop it so the next use replays from the log
+            documents.remove(&event.document_id);
+        }
+        result
+    }
+
+    // AIDEV-NOTE: BEGIN IMMEDIATE takes the write lock before reading the head so concurrent writers cannot fork the chain
+    async fn append_event(&self, event: ProvenanceEvent) -> Result<EventResponse, SonnunError> {
+        let mut tx = self
+            .pool
+            .begin_with("BEGIN IMMEDIATE")
+            .await?;
+        let prev_hash = sqlx::query("SELECT row_hash FROM events ORDER BY id DESC LIMIT 1")
+            .fetch_optional(&mut *tx)
+            .await?
+            .and_then(|row| row.get::<Option<String>, _>("row_hash"))
+            .unwrap_or_else(|| GENESIS_HASH.to_string());
+        let row_hash = chain_hash(&prev_hash, &ChainFields::from_event(&event));
+
+        let result = sqlx::query(
+            "INSERT INTO events (document_id, timestamp, event_type, text_hash, source, span_length, operation, position_from, position_to, text, prev_hash, row_hash) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)"
+        )
+        .bind(event.document_id)
+        .bind(&event.timestamp)
+        .bind(event.event_type.as_str())
+        .bind(&event.text_hash)
+        .bind(&event.source)
+        .bind(event.span_length as i64)
+        .bind(event.operation.as_str())
+        .bind(event.from.map(|pos| pos as i64))
+        .bind(event.to.map(|pos| pos as i64))
+        .bind(&event.text)
+        .bind(&prev_hash)
+        .bind(&row_hash)
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+
+        Ok(EventResponse {
+            id: result.last_insert_rowid(),
+            text_hash: event.text_hash,
+            row_hash,
+        })
+    }
+
+    // AIDEV-NOTE: Walks the whole log in id order and reports the first row whose link or hash is wrong
+    pub async fn verify_event_chain(&self) -> Result<ChainVerification, SonnunError> {
+        let rows = sqlx::query(
+            "SELECT id, document_id, timestamp, event_type, text_hash, source, span_length, operation, position_from, position_to, prev_hash, row_hash FROM events ORDER BY id ASC",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut expected_prev = GENESIS_HASH.to_string();
+        let mut events_checked = 0;
+        for row in rows {
+            let id = row.get::<i64, _>("id");
+            let prev_hash = row.get::<Option<String>, _>("prev_hash");
+            let row_hash = row.get::<Option<String>, _>("row_hash");
+
+            let broken = match (prev_hash, row_hash) {
+                (Some(prev), Some(stored)) => {
+                    if prev != expected_prev {
+                        Some("prev_hash does not match the previous row".to_string())
+                    } else if chain_hash(&prev, &ChainFields::from_row(&row)) != stored {
+                        Some("row_hash does not match the row contents".to_string())
+                    } else {
+                        expected_prev = stored;
+                        None
+                    }
+                }
+                _ => Some("row is missing its chain hashes".to_string()),
+            };
+
+            if let Some(reason) = broken {
+                return Ok(ChainVerification {
+                    valid: false,
+                    events_checked,
+                    first_broken_id: Some(id),
+                    reason: Some(reason),
+                    head_hash: None,
+                });
+            }
+            events_checked += 1;
+        }
+
+        Ok(ChainVerification {
+            valid: true,
+            events_checked,
+            first_broken_id: None,
+            reason: None,
+            head_hash: Some(expected_prev),
+        })
+    }
+
+    // Replay in log order - timestamps come from the frontend and may tie or go backwards
+    async fn events_in_log_order(&self, document_id: i64) -> Result<Vec<ProvenanceEvent>, SonnunError> {
+        let rows = sqlx::query(
+            "SELECT document_id, timestamp, event_type, text_hash, source, span_length, operation, position_from, position_to, text FROM events WHERE document_id = ?1 ORDER BY id ASC",
+        )
+        .bind(document_id)
+        .fetch_all(&self.pool)
+        .await?;
+        rows.iter().map(event_from_row).collect()
+    }
+
+    // Logs from before payloads were stored cannot be replayed, so they get no text
+    async fn load_state(&self, document_id: i64) -> Result<DocumentState, SonnunError> {
+        let events = self.events_in_log_order(document_id).await?;
+        let text = if events.iter().all(|event| event.text.is_some()) {
+            Some(replay::replay(&events, None)?.text.encode_utf16().collect())
+        } else {
+            None
+        };
+        Ok(DocumentState {
+            spans: SpanRope::from_events(&events)?,
+            text,
+            text_hash: None,
+            recorded_hash: self.recorded_text_hash(document_id).await?,
+        })
+    }
+
+    async fn recorded_text_hash(&self, document_id: i64) -> Result<Option<String>, SonnunError> {
+        Ok(sqlx::query("SELECT text_hash FROM documents WHERE id = ?1")
+            .bind(document_id)
+            .fetch_one(&self.pool)
+            .await?
+            .get::<Option<String>, _>("text_hash"))
+    }
+
+    // AIDEV-NOTE: Rebuilds text and spans from payloads. A full replay is checked against the
+    // text hash recorded by the last generate_manifest; `until` replays stop at that instant.
+    pub async fn replay_document(
+        &self,
+        document_id: i64,
+        until: Option<&str>,
+    ) -> Result<ReplayedDocument, SonnunError> {
+        self.get_document(document_id).await?;
+        let until = until.map(replay::parse_timestamp).transpose()?;
+        let events = self.events_in_log_order(document_id).await?;
+        let result = replay::replay(&events, until.as_ref())?;
+
+        let recorded_hash = self.recorded_text_hash(document_id).await?;
+        let text_hash = hash_text(&result.text);
+        let hash_matches = match (&recorded_hash, until) {
+            (Some(recorded), None) => Some(*recorded == text_hash),
+            _ => None,
+        };
+
+        Ok(ReplayedDocument {
+            document_id,
+            text: result.text,
+            spans: result.spans,
+            events_applied: result.events_applied,
+            text_hash,
+            recorded_hash,
+            hash_matches,
+        })
+    }
+
+    async fn chain_head(&self) -> Result<String, SonnunError> {
+        let head = sqlx::query("SELECT row_hash FROM events ORDER BY id DESC LIMIT 1")
+            .fetch_optional(&self.pool)
+            .await?
+            .and_then(|row| row.get::<Option<String>, _>("row_hash"));
+        Ok(head.unwrap_or_else(|| GENESIS_HASH.to_string()))
+    }
+
+    // AIDEV-NOTE: Retrieves a document's filtered event history, newest first
+    pub async fn get_events(
+        &self,
+        document_id: i64,
+        limit: Option<u32>,
+        event_type: Option<EventType>,
+    ) -> Result<Vec<ProvenanceEvent>, SonnunError> {
+        let query = EventQuery {
+            document_id: Some(document_id),
+            event_type,
+            limit,
+            ..EventQuery::default()
+        };
+        self.fetch_events(&query)
+            .map_ok(|logged| logged.event)
+            .try_collect()
+            .await
+    }
+
+    // AIDEV-NOTE: Streams matching rows straight from SQLite - exports can walk the whole log
+    // without holding it in memory. An invalid query yields a single error item.
+    pub fn fetch_events<'a>(
+        &'a self,
+        query: &EventQuery,
+    ) -> BoxStream<'a, Result<LoggedEvent, SonnunError>> {
+        if let Err(e) = query.validate() {
+            return stream::once(async move { Err(e) }).boxed();
+        }
+        let sql = match query.order {
+            SortOrder::Asc => EVENTS_ASC_SQL,
+            SortOrder::Desc => EVENTS_DESC_SQL,
+        };
+        sqlx::query(sql)
+            .bind(query.document_id)
+            .bind(query.event_type.map(|t| t.as_str()))
+            .bind(query.source.clone())
+            .bind(query.since.clone())
+            .bind(query.until.clone())
+            .bind(query.after.as_ref().map(|cursor| cursor.timestamp.clone()))
+            .bind(query.after.as_ref().map(|cursor| cursor.id))
+            .bind(query.limit.map(i64::from).unwrap_or(-1))  // SQLite: negative LIMIT means none
+            .fetch(&self.pool)
+            .map(|row| {
+                let row = row?;
+                Ok(LoggedEvent {
+                    id: row.get::<i64, _>("id"),
+                    event: event_from_row(&row)?,
+                })
+            })
+            .boxed()
+    }
+
+    // AIDEV-NOTE: One page of history. Reads a row past the page size to learn whether a next page exists.
+    pub async fn query_events(&self, query: &EventQuery) -> Result<EventPage, SonnunError> {
+        let page_size = query.limit.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE);
+        let probe = EventQuery {
+            limit: Some(page_size + 1),
+            ..query.clone()
+        };
+        let mut events: Vec<LoggedEvent> = self.fetch_events(&probe).try_collect().await?;
+
+        let next_cursor = if events.len() > page_size as usize {
+            events.truncate(page_size as usize);
+            events.last().map(|last| EventCursor {
+                id: last.id,
+                timestamp: last.event.timestamp.clone(),
+            })
+        } else {
+            None
+        };
+        Ok(EventPage { events, next_cursor })
+    }
+
+    // AIDEV-NOTE: Generates manifest data with statistics from one document's span model.
+    // Percentages describe the text that survives every edit, not everything ever inserted.
+    // `reported_counts` (characters per event type as the frontend sees them) must match the model exactly.
+    pub async fn generate_manifest(
+        &self,
+        document_id: i64,
+        reported_counts: Option<&HashMap<EventType, usize>>,
+    ) -> Result<ManifestData, SonnunError> {
+        self.get_document(document_id).await?;
+        let events = self.get_events(document_id, None, None).await?;
+        let event_chain_head = self.chain_head().await?;
+
+        // AIDEV-NOTE: The text hash comes from the maintained state and is written back only when it
+        // changed, so regenerating an unedited manifest touches nothing
+        let (chars, total_chars, document_text_hash) = {
+            let mut documents = self.documents.lock().await;
+            let state = match documents.entry(document_id) {
+                Entry::Occupied(entry) => entry.into_mut(),
+                Entry::Vacant(entry) => entry.insert(self.load_state(document_id).await?),
+            };
+            let text_hash = state.text_hash()?;
+            if text_hash.is_some() && text_hash != state.recorded_hash {
+                sqlx::query("UPDATE documents SET text_hash = ?1 WHERE id = ?2")
+                    .bind(&text_hash)
+                    .bind(document_id)
+                    .execute(&self.pool)
+                    .await?;
+                state.recorded_hash = text_hash.clone();
+            }
+            (state.spans.counts().clone(), state.spans.len(), text_hash)
+        };
+
+        if let Some(reported) = reported_counts {
+            for event_type in EventType::ALL {
+                let expected = chars.get(&event_type).copied().unwrap_or(0);
+                let claimed = reported.get(&event_type).copied().unwrap_or(0);
+                if claimed != expected {
+                    return Err(SonnunError::ProvenanceMismatch(format!(
+                        "Reported {} {} characters but the event log accounts for {}",
+                        claimed, event_type, expected
+                    )));
+                }
+            }
+        }
+
+        // An empty document is all-human by convention
+        let percentage = |event_type: EventType| {
+            if total_chars > 0 {
+                (chars.get(&event_type).copied().unwrap_or(0) as f64 / total_chars as f64) * 100.0
+            } else if event_type == EventType::Human {
+                100.0
+            } else {
+                0.0
+            }
+        };
+
+        Ok(ManifestData {
+            document_id,
+            human_percentage: percentage(EventType::Human),
+            ai_percentage: percentage(EventType::Ai),
+            cited_percentage: percentage(EventType::Cited),
+            ai_edited_percentage: percentage(EventType::AiEdited),
+            translated_percentage: percentage(EventType::Translated),
+            tool_assisted_percentage: percentage(EventType::ToolAssisted),
+            imported_percentage: percentage(EventType::Imported),
+            total_characters: total_chars,
+            events,
+            event_chain_head,
+            document_text_hash,
+            generated_at: Some(chrono::Utc::now().to_rfc3339()),
+            author_did: None,
+        })
+    }
+
+    // AIDEV-NOTE: Get a document's event count by type for analytics
+    pub async fn get_event_counts(
+        &self,
+        document_id: i64,
+    ) -> Result<HashMap<EventType, usize>, SonnunError> {
+        let rows = sqlx::query(
+            "SELECT event_type, COUNT(*) as count FROM events WHERE document_id = ?1 GROUP BY event_type",
+        )
+        .bind(document_id)
+        .fetch_all(&self.pool)
+        .await?;
+        let mut counts = HashMap::new();
+        for row in rows {
+            counts.insert(
+                row.get::<String, _>("event_type").parse::<EventType>()?,
+                row.get::<i64, _>("count") as usize,
+            );
+        }
+        Ok(counts)
+    }
+}
+
+fn event_from_row(row: &sqlx::sqlite::SqliteRow) -> Result<ProvenanceEvent, SonnunError> {
+    Ok(ProvenanceEvent {
+        document_id: row.get::<i64, _>("document_id"),
+        timestamp: row.get::<String, _>("timestamp"),
+        event_type: row.get::<String, _>("event_type").parse()?,
+        text_hash: row.get::<String, _>("text_hash"),
+        source: row.get::<String, _>("source"),
+        span_length: row.get::<i64, _>("span_length") as usize,
+        operation: row.get::<String, _>("operation").parse()?,
+        from: row.get::<Option<i64>, _>("position_from").map(|pos| pos as usize),
+        to: row.get::<Option<i64>, _>("position_to").map(|pos| pos as usize),
+        text: row.get::<Option<String>, _>("text"),
+    })
+}
+
+// AIDEV-NOTE: Backfill for migration 4 - links rows written before the hash chain existed, in id order
+pub(crate) async fn seal_event_rows(conn: &mut SqliteConnection) -> Result<(), SonnunError> {
+    let rows = sqlx::query(
+        "SELECT id, document_id, timestamp, event_type, text_hash, source, span_length FROM events ORDER BY id ASC",
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    let mut prev_hash = GENESIS_HASH.to_string();
+    for row in rows {
+        let row_hash = chain_hash(&prev_hash, &ChainFields::from_row(&row));
+        sqlx::query("UPDATE events SET prev_hash = ?1, row_hash = ?2 WHERE id = ?3")
+            .bind(&prev_hash)
+            .bind(&row_hash)
+            .bind(row.get::<i64, _>("id"))
+            .execute(&mut *conn)
+            .await?;
+        prev_hash = row_hash;
+    }
+    Ok(())
+}
+
+// AIDEV-NOTE: Canonical chain inputs - legacy rows may have NULL document_id/source, hashed as ""
+struct ChainFields {
+    document_id: String,
+    timestamp: String,
+    event_type: String,
+    text_hash: String,
+    source: String,
+    span_length: String,
+    operation: String,
+    from: Option<String>,
+    to: Option<String>,
+}
+
+impl ChainFields {
+    fn from_event(event: &ProvenanceEvent) -> Self {
+        Self {
+            document_id: event.document_id.to_string(),
+            timestamp: event.timestamp.clone(),
+            event_type: event.event_type.as_str().to_string(),
+            text_hash: event.text_hash.clone(),
+            source: event.source.clone(),
+            span_length: event.span_length.to_string(),
+            operation: event.operation.as_str().to_string(),
+            from: event.from.map(|pos| pos.to_string()),
+            to: event.to.map(|pos| pos.to_string()),
+        }
+    }
+
+    fn from_row(row: &sqlx::sqlite::SqliteRow) -> Self {
+        Self {
+            document_id: row
+                .get::<Option<i64>, _>("document_id")
+                .map(|id| id.to_string())
+                .unwrap_or_default(),
+            timestamp: row.get::<String, _>("timestamp"),
+            event_type: row.get::<String, _>("event_type"),
+            text_hash: row.get::<String, _>("text_hash"),
+            source: row.get::<Option<String>, _>("source").unwrap_or_default(),
+            span_length: row
+                .get::<Option<i64>, _>("span_length")
+                .map(|len| len.to_string())
+                .unwrap_or_default(),
+            // try_get: the migration 4 backfill runs before these columns exist
+            operation: row
+                .try_get::<String, _>("operation")
+                .unwrap_or_else(|_| Operation::Insert.as_str().to_string()),
+            from: row
+                .try_get::<Option<i64>, _>("position_from")
+                .ok()
+                .flatten()
+                .map(|pos| pos.to_string()),
+            to: row
+                .try_get::<Option<i64>, _>("position_to")
+                .ok()
+                .flatten()
+                .map(|pos| pos.to_string()),
+        }
+    }
+}
+
+// AIDEV-NOTE: Length-prefixing each field keeps the encoding unambiguous whatever the field contents.
+// Positional fields are appended only when present so rows sealed before migration 5 keep their hashes.
+fn chain_hash(prev_hash: &str, fields: &ChainFields) -> String {
+    let mut canonical = String::new();
+    for value in [
+        prev_hash,
+        &fields.document_id,
+        &fields.timestamp,
+        &fields.event_type,
+        &fields.text_hash,
+        &fields.source,
+        &fields.span_length,
+    ] {
+        canonical.push_str(&format!("{}:{};", value.len(), value));
+    }
+    let positional = fields.operation != Operation::Insert.as_str()
+        || fields.from.is_some()
+        || fields.to.is_some();
+    if positional {
+        for value in [
+            fields.operation.as_str(),
+            fields.from.as_deref().unwrap_or(""),
+            fields.to.as_deref().unwrap_or(""),
+        ] {
+            canonical.push_str(&format!("{}:{};", value.len(), value));
+        }
+    }
+    hash_text(&canonical)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    const TEST_DB_URL: &str = "sqlite::memory:";
+
+    fn create_test_event(
+        document_id: i64,
+        event_type: EventType,
+        source: &str,
+        span_length: usize,
+    ) -> ProvenanceEvent {
+        ProvenanceEvent {
+            document_id,
+            timestamp: chrono::Utc::now().to_rfc3339(),
+            event_type,
+            text_hash: format!("hash_{}", source),
+            source: source.to_string(),
+            span_length,
+            operation: Operation::Insert,
+            from: None,
+            to: None,
+            text: None,
+        }
+    }
+
+    fn create_text_event(
+        document_id: i64,
+        event_type: EventType,
+        operation: Operation,
+        from: usize,
+        to: Option<usize>,
+        text: &str,
+    ) -> ProvenanceEvent {
+        ProvenanceEvent {
+            text_hash: hash_text(text),
+            span_length: text.encode_utf16().count(),
+            operation,
+            from: Some(from),
+            to,
+            text: Some(text.to_string()),
+            ..create_test_event(document_id, event_type, "user", 0)
+        }
+    }
+
+    fn create_edit_event(
+        document_id: i64,
+        event_type: EventType,
+        operation: Operation,
+        from: usize,
+        to: usize,
+        span_length: usize,
+    ) -> ProvenanceEvent {
+        ProvenanceEvent {
+            operation,
+            from: Some(from),
+            to: (operation != Operation::Insert).then_some(to),
+            ..create_test_event(document_id, event_type, "user", span_length)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_insert_event() {
+        let db = Database::new(TEST_DB_URL).await.unwrap();
+        let doc = db.create_document("Test").await.unwrap();
+
+        let event = create_test_event(doc.id, EventType::Human, "user", 10);
+        let result = db.insert_event(event.clone()).await;
+        
+        assert!(result.is_ok());
+        let response = result.unwrap();
+        assert_eq!(response.text_hash, event.text_hash);
+        assert_eq!(response.id, 1);
+    }
+
+    #[tokio::test]
+    async fn test_get_events_with_filter() {
+        let db = Database::new(TEST_DB_URL).await.unwrap();
+        let doc = db.create_document("Test").await.unwrap();
+        
+        // Insert different types of events
+        db.insert_event(create_test_event(doc.id, EventType::Human, "user", 10)).await.unwrap();
+        db.insert_event(create_test_event(doc.id, EventType::Ai, "gpt-4", 15)).await.unwrap();
+        db.insert_event(create_test_event(doc.id, EventType::Cited, "wikipedia", 20)).await.unwrap();
+        
+        // Test filtering by type
+        let human_events = db.get_events(doc.id, None, Some(EventType::Human)).await.unwrap();
+        assert_eq!(human_events.len(), 1);
+        assert_eq!(human_events[0].event_type, EventType::Human);
+        
+        // Test no filter
+        let all_events = db.get_events(doc.id, None, None).await.unwrap();
+        assert_eq!(all_events.len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_get_events_with_limit() {
+        let db = Database::new(TEST_DB_URL).await.unwrap();
+        let doc = db.create_document("Test").await.unwrap();
+        
+        // Insert multiple events
+        for i in 0..5 {
+            db.insert_event(create_test_event(doc.id, EventType::Human, &format!("user{}", i), 10)).await.unwrap();
+        }
+        
+        let limited_events = db.get_events(doc.id, Some(3), None).await.unwrap();
+        assert_eq!(limited_events.len(), 3);
+    }
+
+    async fn seed_history(db: &Database) -> (i64, i64) {
+        let first = db.create_document("First").await.unwrap();
+        let second = db.create_document("Second").await.unwrap();
+        // Two events share 10:01 so the cursor has to break the tie by id
+        for (doc, minute, source) in [
+            (first.id, 0, "user"),
+            (first.id, 1, "gpt-4"),
+            (first.id, 1, "user"),
+            (second.id, 2, "user"),
+            (first.id, 3, "wikipedia"),
+            (first.id, 4, "user"),
+        ] {
+            let event = ProvenanceEvent {
+                timestamp: format!("2024-01-01T10:0{}:00Z", minute),
+                ..create_test_event(doc, EventType::Human, source, 1)
+            };
+            db.insert_event(event).await.unwrap();
+        }
+        (first.id, second.id)
+    }
+
+    #[tokio::test]
+    async fn test_query_events_pages_with_cursor() {
+        let db = Database::new(TEST_DB_URL).await.unwrap();
+        let (first, _) = seed_history(&db).await;
+
+        let mut query = EventQuery {
+            document_id: Some(first),
+            limit: Some(2),
+            ..EventQuery::default()
+        };
+        let mut seen = Vec::new();
+        loop {
+            let page = db.query_events(&query).await.unwrap();
+            assert!(page.events.len() <= 2);
+            seen.extend(page.events.iter().map(|e| e.id));
+            match page.next_cursor {
+                Some(cursor) => query.after = Some(cursor),
+                None => break,
+            }
+        }
+        // Newest first, tie at 10:01 ordered by id, document 2's row excluded
+        assert_eq!(seen, vec![6, 5, 3, 2, 1]);
+
+        query.after = None;
+        query.order = SortOrder::Asc;
+        query.limit = Some(3);
+        let page = db.query_events(&query).await.unwrap();
+        assert_eq!(page.events.iter().map(|e| e.id).collect::<Vec<_>>(), vec![1, 2, 3]);
+        query.after = page.next_cursor;
+        let page = db.query_events(&query).await.unwrap();
+        assert_eq!(page.events.iter().map(|e| e.id).collect::<Vec<_>>(), vec![5, 6]);
+        assert!(page.next_cursor.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_query_events_filters() {
+        let db = Database::new(TEST_DB_URL).await.unwrap();
+        let (first, second) = seed_history(&db).await;
+
+        let by_source = EventQuery {
+            source: Some("user".to_string()),
+            ..EventQuery::default()
+        };
+        assert_eq!(db.query_events(&by_source).await.unwrap().events.len(), 4);
+
+        // Bounds are inclusive and compare instants, not strings
+        let window = EventQuery {
+            document_id: Some(first),
+            since: Some("2024-01-01T11:01:00+01:00".to_string()),
+            until: Some("2024-01-01T10:03:00Z".to_string()),
+            ..EventQuery::default()
+        };
+        let ids: Vec<i64> = db.query_events(&window).await.unwrap().events.iter().map(|e| e.id).collect();
+        assert_eq!(ids, vec![5, 3, 2]);
+
+        let other = EventQuery {
+            document_id: Some(second),
+            ..EventQuery::default()
+        };
+        assert_eq!(db.query_events(&other).await.unwrap().events.len(), 1);
+
+        let bad = EventQuery {
+            since: Some("last tuesday".to_string()),
+            ..EventQuery::default()
+        };
+        assert_eq!(db.query_events(&bad).await.unwrap_err().code(), "INVALID_INPUT");
+    }
+
+    #[tokio::test]
+    async fn test_fetch_events_streams_whole_log() {
+        let db = Database::new(TEST_DB_URL).await.unwrap();
+        seed_history(&db).await;
+
+        let query = EventQuery {
+            order: SortOrder::Asc,
+            ..EventQuery::default()
+        };
+        let mut stream = db.fetch_events(&query);
+        let mut count = 0;
+        while let Some(event) = stream.next().await {
+            count += 1;
+            assert_eq!(event.unwrap().id, count);
+        }
+        assert_eq!(count, 6);
+    }
+
+    #[tokio::test]
+    async fn test_generate_manifest() {
+        let db = Database::new(TEST_DB_URL).await.unwrap();
+        let doc = db.create_document("Test").await.unwrap();
+        
+        // Insert events with known character counts
+        db.insert_event(create_test_event(doc.id, EventType::Human, "user", 60)).await.unwrap();
+        db.insert_event(create_test_event(doc.id, EventType::Ai, "gpt-4", 30)).await.unwrap();
+        db.insert_event(create_test_event(doc.id, EventType::Cited, "wikipedia", 10)).await.unwrap();
+        
+        let manifest = db.generate_manifest(doc.id, None).await.unwrap();
+        
+        assert_eq!(manifest.human_percentage, 60.0);
+        assert_eq!(manifest.ai_percentage, 30.0);
+        assert_eq!(manifest.cited_percentage, 10.0);
+        assert_eq!(manifest.total_characters, 100);
+        assert_eq!(manifest.document_id, doc.id);
+    }
+
+    #[tokio::test]
+    async fn test_generate_manifest_extended_categories() {
+        let db = Database::new(TEST_DB_URL).await.unwrap();
+        let doc = db.create_document("Test").await.unwrap();
+
+        db.insert_event(create_test_event(doc.id, EventType::Human, "user", 40)).await.unwrap();
+        db.insert_event(create_test_event(doc.id, EventType::AiEdited, "gpt-4", 20)).await.unwrap();
+        db.insert_event(create_test_event(doc.id, EventType::Translated, "deepl", 15)).await.unwrap();
+        db.insert_event(create_test_event(doc.id, EventType::ToolAssisted, "grammar", 5)).await.unwrap();
+        db.insert_event(create_test_event(doc.id, EventType::Imported, "notes.md", 20)).await.unwrap();
+
+        let manifest = db.generate_manifest(doc.id, None).await.unwrap();
+
+        assert_eq!(manifest.total_characters, 100);
+        assert_eq!(manifest.human_percentage, 40.0);
+        assert_eq!(manifest.ai_edited_percentage, 20.0);
+        assert_eq!(manifest.translated_percentage, 15.0);
+        assert_eq!(manifest.tool_assisted_percentage, 5.0);
+        assert_eq!(manifest.imported_percentage, 20.0);
+        assert_eq!(manifest.ai_percentage, 0.0);
+    }
+
+    #[tokio::test]
+    async fn test_generate_manifest_counts_surviving_text_only() {
+        let db = Database::new(TEST_DB_URL).await.unwrap();
+        let doc = db.create_document("Test").await.unwrap();
+
+        // 20 human chars, 30 AI chars inserted in the middle, then a human deletes 20 of the AI chars
+        db.insert_event(create_edit_event(doc.id, EventType::Human, Operation::Insert, 0, 0, 20)).await.unwrap();
+        db.insert_event(create_edit_event(doc.id, EventType::Ai, Operation::Insert, 10, 10, 30)).await.unwrap();
+        db.insert_event(create_edit_event(doc.id, EventType::Human, Operation::Delete, 15, 35, 20)).await.unwrap();
+
+        let manifest = db.generate_manifest(doc.id, None).await.unwrap();
+        assert_eq!(manifest.total_characters, 30);
+        assert!((manifest.ai_percentage - 100.0 / 3.0).abs() < 1e-9);
+        assert!((manifest.human_percentage - 200.0 / 3.0).abs() < 1e-9);
+        assert_eq!(manifest.events.len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_generate_manifest_replace_reattributes_text() {
+        let db = Database::new(TEST_DB_URL).await.unwrap();
+        let doc = db.create_document("Test").await.unwrap();
+
+        db.insert_event(create_test_event(doc.id, EventType::Ai, "gpt-4", 40)).await.unwrap();
+        // A human rewrites the first 10 AI characters as 30 of their own
+        db.insert_event(create_edit_event(doc.id, EventType::Human, Operation::Replace, 0, 10, 30)).await.unwrap();
+
+        let manifest = db.generate_manifest(doc.id, None).await.unwrap();
+        assert_eq!(manifest.total_characters, 60);
+        assert_eq!(manifest.human_percentage, 50.0);
+        assert_eq!(manifest.ai_percentage, 50.0);
+    }
+
+    #[tokio::test]
+    async fn test_insert_event_rejects_edits_past_the_end() {
+        let db = Database::new(TEST_DB_URL).await.unwrap();
+        let doc = db.create_document("Test").await.unwrap();
+
+        db.insert_event(create_test_event(doc.id, EventType::Human, "user", 10)).await.unwrap();
+        let result = db.insert_event(create_edit_event(doc.id, EventType::Human, Operation::Delete, 5, 15, 10)).await;
+        assert_eq!(result.unwrap_err().code(), "INVALID_INPUT");
+
+        // Nothing was logged and the model is unchanged
+        assert_eq!(db.get_events(doc.id, None, None).await.unwrap().len(), 1);
+        assert_eq!(db.generate_manifest(doc.id, None).await.unwrap().total_characters, 10);
+    }
+
+    #[tokio::test]
+    async fn test_generate_manifest_checks_reported_counts() {
+        let db = Database::new(TEST_DB_URL).await.unwrap();
+        let doc = db.create_document("Test").await.unwrap();
+        db.insert_event(create_test_event(doc.id, EventType::Human, "user", 30)).await.unwrap();
+        db.insert_event(create_test_event(doc.id, EventType::Ai, "gpt-4", 10)).await.unwrap();
+
+        let honest = HashMap::from([(EventType::Human, 30), (EventType::Ai, 10)]);
+        let manifest = db.generate_manifest(doc.id, Some(&honest)).await.unwrap();
+        assert_eq!(manifest.human_percentage, 75.0);
+
+        // Claiming the AI text as human is rejected
+        let inflated = HashMap::from([(EventType::Human, 40)]);
+        let result = db.generate_manifest(doc.id, Some(&inflated)).await;
+        assert_eq!(result.unwrap_err().code(), "PROVENANCE_MISMATCH");
+    }
+
+    #[tokio::test]
+    async fn test_span_model_survives_reopen() {
+        let path = std::env::temp_dir().join(format!("sonnun-spans-{}.db", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let url = format!("sqlite:{}", path.display());
+
+        let db = Database::new(&url).await.unwrap();
+        let doc = db.create_document("Test").await.unwrap();
+        db.insert_event(create_test_event(doc.id, EventType::Ai, "gpt-4", 20)).await.unwrap();
+        db.insert_event(create_edit_event(doc.id, EventType::Human, Operation::Replace, 0, 5, 5)).await.unwrap();
+        db.close().await;
+
+        // A fresh process rebuilds the model from the log
+        let db = Database::new(&url).await.unwrap();
+        let manifest = db.generate_manifest(doc.id, None).await.unwrap();
+        assert_eq!(manifest.ai_percentage, 75.0);
+        db.insert_event(create_edit_event(doc.id, EventType::Human, Operation::Delete, 0, 20, 20)).await.unwrap();
+        assert_eq!(db.generate_manifest(doc.id, None).await.unwrap().total_characters, 0);
+
+        db.close().await;
+        for suffix in ["", "-wal", "-shm"] {
+            let _ = std::fs::remove_file(format!("{}{}", path.display(), suffix));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_replay_document_matches_manifest_hash() {
+        let db = Database::new(TEST_DB_URL).await.unwrap();
+        let doc = db.create_document("Test").await.unwrap();
+        db.insert_event(create_text_event(doc.id, EventType::Human, Operation::Insert, 0, None, "The cat sat")).await.unwrap();
+        db.insert_event(create_text_event(doc.id, EventType::Ai, Operation::Replace, 4, Some(7), "dog")).await.unwrap();
+
+        // Nothing recorded until a manifest is generated
+        let before = db.replay_document(doc.id, None).await.unwrap();
+        assert_eq!(before.text, "The dog sat");
+        assert_eq!(before.hash_matches, None);
+
+        let manifest = db.generate_manifest(doc.id, None).await.unwrap();
+        assert_eq!(manifest.document_text_hash, Some(hash_text("The dog sat")));
+
+        let replayed = db.replay_document(doc.id, None).await.unwrap();
+        assert_eq!(replayed.hash_matches, Some(true));
+        assert_eq!(replayed.events_applied, 2);
+
+        let future = "2999-01-01T00:00:00Z";
+        let as_of = db.replay_document(doc.id, Some(future)).await.unwrap();
+        assert_eq!(as_of.hash_matches, None);
+        assert!(db.replay_document(doc.id, Some("yesterday")).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_insert_event_rejects_payloads_replay_would_reject() {
+        let db = Database::new(TEST_DB_URL).await.unwrap();
+        let doc = db.create_document("Test").await.unwrap();
+        db.insert_event(create_text_event(doc.id, EventType::Human, Operation::Insert, 0, None, "The cat sat")).await.unwrap();
+
+        // In range, but deleting text the document does not hold
+        let wrong_text = create_text_event(doc.id, EventType::Human, Operation::Delete, 4, Some(7), "dog");
+        assert_eq!(db.insert_event(wrong_text).await.unwrap_err().code(), "INVALID_INPUT");
+        let wrong_hash = ProvenanceEvent {
+            text_hash: hash_text("cow"),
+            ..create_text_event(doc.id, EventType::Ai, Operation::Replace, 4, Some(7), "dog")
+        };
+        assert_eq!(db.insert_event(wrong_hash).await.unwrap_err().code(), "INVALID_INPUT");
+
+        // Neither reached the log, so the document still replays and manifests still generate
+        assert_eq!(db.get_events(doc.id, None, None).await.unwrap().len(), 1);
+        let manifest = db.generate_manifest(doc.id, None).await.unwrap();
+        assert_eq!(manifest.document_text_hash, Some(hash_text("The cat sat")));
+        db.insert_event(create_text_event(doc.id, EventType::Human, Operation::Delete, 4, Some(8), "cat ")).await.unwrap();
+        let manifest = db.generate_manifest(doc.id, None).await.unwrap();
+        assert_eq!(manifest.document_text_hash, Some(hash_text("The sat")));
+        assert_eq!(db.replay_document(doc.id, None).await.unwrap().hash_matches, Some(true));
+    }
+
+    #[tokio::test]
+    async fn test_legacy_events_get_no_text_hash() {
+        let db = Database::new(TEST_DB_URL).await.unwrap();
+        let doc = db.create_document("Test").await.unwrap();
+        db.insert_event(create_test_event(doc.id, EventType::Human, "user", 10)).await.unwrap();
+
+        let manifest = db.generate_manifest(doc.id, None).await.unwrap();
+        assert_eq!(manifest.document_text_hash, None);
+        assert!(db.replay_document(doc.id, None).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_manifests_are_scoped_to_documents() {
+        let db = Database::new(TEST_DB_URL).await.unwrap();
+        let first = db.create_document("First").await.unwrap();
+        let second = db.create_document("Second").await.unwrap();
+
+        db.insert_event(create_test_event(first.id, EventType::Human, "user", 40)).await.unwrap();
+        db.insert_event(create_test_event(second.id, EventType::Ai, "gpt-4", 25)).await.unwrap();
+
+        let first_manifest = db.generate_manifest(first.id, None).await.unwrap();
+        assert_eq!(first_manifest.human_percentage, 100.0);
+        assert_eq!(first_manifest.total_characters, 40);
+        assert_eq!(first_manifest.events.len(), 1);
+
+        let second_manifest = db.generate_manifest(second.id, None).await.unwrap();
+        assert_eq!(second_manifest.ai_percentage, 100.0);
+        assert_eq!(second_manifest.total_characters, 25);
+        assert_eq!(second_manifest.events.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_insert_event_unknown_document() {
+        let db = Database::new(TEST_DB_URL).await.unwrap();
+
+        let result = db.insert_event(create_test_event(42, EventType::Human, "user", 10)).await;
+        assert_eq!(
+            result.unwrap_err(),
+            SonnunError::NotFound("Document 42 not found".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_get_event_counts() {
+        let db = Database::new(TEST_DB_URL).await.unwrap();
+        let doc = db.create_document("Test").await.unwrap();
+        
+        // Insert multiple events of different types
+        db.insert_event(create_test_event(doc.id, EventType::Human, "user1", 10)).await.unwrap();
+        db.insert_event(create_test_event(doc.id, EventType::Human, "user2", 10)).await.unwrap();
+        db.insert_event(create_test_event(doc.id, EventType::Ai, "gpt-4", 15)).await.unwrap();
+        
+        let counts = db.get_event_counts(doc.id).await.unwrap();
+        
+        assert_eq!(counts.get(&EventType::Human), Some(&2));
+        assert_eq!(counts.get(&EventType::Ai), Some(&1));
+        assert_eq!(counts.get(&EventType::Cited), None);
+    }
+
+    #[tokio::test]
+    async fn test_events_are_append_only() {
+        let db = Database::new(TEST_DB_URL).await.unwrap();
+        let doc = db.create_document("Test").await.unwrap();
+        db.insert_event(create_test_event(doc.id, EventType::Human, "user", 10)).await.unwrap();
+
+        let delete = sqlx::query("DELETE FROM events").execute(&db.pool).await;
+        assert!(delete.unwrap_err().to_string().contains("events are append-only"));
+
+        let update = sqlx::query("UPDATE events SET span_length = 99").execute(&db.pool).await;
+        assert!(update.unwrap_err().to_string().contains("events are append-only"));
+
+        let events = db.get_events(doc.id, None, None).await.unwrap();
+        assert_eq!(events.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_event_chain_links_rows() {
+        let db = Database::new(TEST_DB_URL).await.unwrap();
+        let doc = db.create_document("Test").await.unwrap();
+
+        let first = db.insert_event(create_test_event(doc.id, EventType::Human, "user", 10)).await.unwrap();
+        let second = db.insert_event(create_test_event(doc.id, EventType::Ai, "gpt-4", 5)).await.unwrap();
+        assert_ne!(first.row_hash, second.row_hash);
+
+        let verification = db.verify_event_chain().await.unwrap();
+        assert!(verification.valid);
+        assert_eq!(verification.events_checked, 2);
+        assert_eq!(verification.head_hash, Some(second.row_hash.clone()));
+
+        let manifest = db.generate_manifest(doc.id, None).await.unwrap();
+        assert_eq!(manifest.event_chain_head, second.row_hash);
+    }
+
+    #[tokio::test]
+    async fn test_verify_event_chain_reports_first_broken_link() {
+        let db = Database::new(TEST_DB_URL).await.unwrap();
+        let doc = db.create_document("Test").await.unwrap();
+        for i in 0..3 {
+            db.insert_event(create_test_event(doc.id, EventType::Human, &format!("user{}", i), 10)).await.unwrap();
+        }
+
+        // Simulate someone editing the SQLite file directly
+        sqlx::query("DROP TRIGGER events_append_only_update").execute(&db.pool).await.unwrap();
+        sqlx::query("UPDATE events SET span_length = 500 WHERE id = 2").execute(&db.pool).await.unwrap();
+
+        let verification = db.verify_event_chain().await.unwrap();
+        assert!(!verification.valid);
+        assert_eq!(verification.events_checked, 1);
+        assert_eq!(verification.first_broken_id, Some(2));
+        assert!(verification.reason.unwrap().contains("row_hash"));
+    }
+
+    #[tokio::test]
+    async fn test_event_chain_covers_positions() {
+        let db = Database::new(TEST_DB_URL).await.unwrap();
+        let doc = db.create_document("Test").await.unwrap();
+        db.insert_event(create_test_event(doc.id, EventType::Ai, "gpt-4", 20)).await.unwrap();
+        db.insert_event(create_edit_event(doc.id, EventType::Human, Operation::Delete, 0, 10, 10)).await.unwrap();
+        assert!(db.verify_event_chain().await.unwrap().valid);
+
+        // Moving a deletion onto other text must break the chain
+        sqlx::query("DROP TRIGGER events_append_only_update").execute(&db.pool).await.unwrap();
+        sqlx::query("UPDATE events SET position_from = 10, position_to = 20 WHERE id = 2").execute(&db.pool).await.unwrap();
+
+        let verification = db.verify_event_chain().await.unwrap();
+        assert!(!verification.valid);
+        assert_eq!(verification.first_broken_id, Some(2));
+    }
+
+    #[tokio::test]
+    async fn test_file_database_uses_wal() {
+        let path = std::env::temp_dir().join(format!("sonnun-wal-{}.db", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        // The file does not exist yet - Database::new must create it
+        let db = Database::new(&format!("sqlite:{}", path.display())).await.unwrap();
+        let mode = sqlx::query("PRAGMA journal_mode")
+            .fetch_one(&db.pool)
+            .await
+            .unwrap()
+            .get::<String, _>(0);
+        assert_eq!(mode, "wal");
+
+        db.pool.close().await;
+        for suffix in ["", "-wal", "-shm"] {
+            let _ = std::fs::remove_file(format!("{}{}", path.display(), suffix));
+        }
+    }
+}
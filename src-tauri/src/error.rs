@@ -0,0 +1,193 @@
+// AIDEV-NOTE: Shared error type for commands, library APIs and sonnun-verify.
+// `code()` strings and `exit_code()` values are a public contract - add new variants, never renumber.
+use serde::ser::SerializeStruct;
+use serde::{Serialize, Serializer};
+
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum SonnunError {
+    #[error("Database unavailable: {0}")]
+    DbUnavailable(String),
+    #[error("Database error: {0}")]
+    Database(String),
+    #[error("Not found: {0}")]
+    NotFound(String),
+    #[error("Invalid input: {0}")]
+    InvalidInput(String),
+    #[error("Invalid key: {0}")]
+    InvalidKey(String),
+    #[error("Invalid signature: {0}")]
+    InvalidSignature(String),
+    #[error("Signature mismatch: {0}")]
+    SignatureMismatch(String),
+    #[error("Manifest schema error: {0}")]
+    ManifestSchema(String),
+    #[error("AI provider error: {0}")]
+    AiProvider(String),
+    #[error("I/O error: {0}")]
+    Io(String),
+    #[error("Provenance mismatch: {0}")]
+    ProvenanceMismatch(String),
+    #[error("Key locked: {0}")]
+    KeyLocked(String),
+    #[error("Key revoked: {0}")]
+    KeyRevoked(String),
+    #[error("Content mismatch: {0}")]
+    ContentMismatch(String),
+    #[error("Policy unsatisfied: {0}")]
+    PolicyUnsatisfied(String),
+    #[error("Timestamp error: {0}")]
+    Timestamp(String),
+    #[error("Transparency log error: {0}")]
+    Transparency(String),
+}
+
+impl SonnunError {
+    // Stable machine-readable code for the frontend to match on
+    pub fn code(&self) -> &'static str {
+        match self {
+            SonnunError::DbUnavailable(_) => "DB_UNAVAILABLE",
+            SonnunError::Database(_) => "DATABASE",
+            SonnunError::NotFound(_) => "NOT_FOUND",
+            SonnunError::InvalidInput(_) => "INVALID_INPUT",
+            SonnunError::InvalidKey(_) => "INVALID_KEY",
+            SonnunError::InvalidSignature(_) => "INVALID_SIGNATURE",
+            SonnunError::SignatureMismatch(_) => "SIGNATURE_MISMATCH",
+            SonnunError::ManifestSchema(_) => "MANIFEST_SCHEMA",
+            SonnunError::AiProvider(_) => "AI_PROVIDER",
+            SonnunError::Io(_) => "IO",
+            SonnunError::ProvenanceMismatch(_) => "PROVENANCE_MISMATCH",
+            SonnunError::KeyLocked(_) => "KEY_LOCKED",
+            SonnunError::KeyRevoked(_) => "KEY_REVOKED",
+            SonnunError::ContentMismatch(_) => "CONTENT_MISMATCH",
+            SonnunError::PolicyUnsatisfied(_) => "POLICY_UNSATISFIED",
+            SonnunError::Timestamp(_) => "TIMESTAMP",
+            SonnunError::Transparency(_) => "TRANSPARENCY",
+        }
+    }
+
+    // AIDEV-NOTE: sonnun-verify exit codes. 2 is left to clap for usage errors.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            SonnunError::SignatureMismatch(_) => 1,
+            SonnunError::ManifestSchema(_) => 3,
+            SonnunError::InvalidKey(_) => 4,
+            SonnunError::InvalidSignature(_) => 5,
+            SonnunError::Io(_) => 6,
+            SonnunError::InvalidInput(_) => 7,
+            SonnunError::NotFound(_) => 8,
+            SonnunError::ProvenanceMismatch(_) => 9,
+            SonnunError::DbUnavailable(_) => 10,
+            SonnunError::KeyRevoked(_) => 11,
+            SonnunError::ContentMismatch(_) => 12,
+            SonnunError::PolicyUnsatisfied(_) => 13,
+            SonnunError::Timestamp(_) => 14,
+            SonnunError::Transparency(_) => 15,
+            SonnunError::Database(_) => 16,
+            SonnunError::AiProvider(_) => 17,
+            SonnunError::KeyLocked(_) => 18,
+        }
+    }
+
+    pub fn message(&self) -> String {
+        match self {
+            SonnunError::DbUnavailable(m)
+            | SonnunError::Database(m)
+            | SonnunError::NotFound(m)
+            | SonnunError::InvalidInput(m)
+            | SonnunError::InvalidKey(m)
+            | SonnunError::InvalidSignature(m)
+            | SonnunError::SignatureMismatch(m)
+            | SonnunError::ManifestSchema(m)
+            | SonnunError::AiProvider(m)
+            | SonnunError::Io(m)
+            | SonnunError::ProvenanceMismatch(m)
+            | SonnunError::KeyLocked(m)
+            | SonnunError::KeyRevoked(m)
+            | SonnunError::ContentMismatch(m)
+            | SonnunError::PolicyUnsatisfied(m)
+            | SonnunError::Timestamp(m)
+            | SonnunError::Transparency(m) => m.clone(),
+        }
+    }
+}
+
+// AIDEV-NOTE: Commands reject with {"code": "...", "message": "..."} instead of a bare string
+impl Serialize for SonnunError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("SonnunError", 2)?;
+        state.serialize_field("code", self.code())?;
+        state.serialize_field("message", &self.message())?;
+        state.end()
+    }
+}
+
+impl From<sqlx::Error> for SonnunError {
+    fn from(e: sqlx::Error) -> Self {
+        match e {
+            sqlx::Error::Configuration(_)
+            | sqlx::Error::Io(_)
+            | sqlx::Error::Tls(_)
+            | sqlx::Error::PoolTimedOut
+            | sqlx::Error::PoolClosed
+            | sqlx::Error::WorkerCrashed => SonnunError::DbUnavailable(e.to_string()),
+            sqlx::Error::RowNotFound => SonnunError::NotFound(e.to_string()),
+            _ => SonnunError::Database(e.to_string()),
+        }
+    }
+}
+
+impl From<std::io::Error> for SonnunError {
+    fn from(e: std::io::Error) -> Self {
+        SonnunError::Io(e.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_serializes_code_and_message() {
+        let error = SonnunError::InvalidKey("Invalid public key length".to_string());
+        let json = serde_json::to_value(&error).unwrap();
+        assert_eq!(json["code"], "INVALID_KEY");
+        assert_eq!(json["message"], "Invalid public key length");
+    }
+
+    #[test]
+    fn test_exit_codes_are_distinct() {
+        let errors = [
+            SonnunError::DbUnavailable(String::new()),
+            SonnunError::Database(String::new()),
+            SonnunError::NotFound(String::new()),
+            SonnunError::InvalidInput(String::new()),
+            SonnunError::SignatureMismatch(String::new()),
+            SonnunError::ManifestSchema(String::new()),
+            SonnunError::InvalidKey(String::new()),
+            SonnunError::InvalidSignature(String::new()),
+            SonnunError::AiProvider(String::new()),
+            SonnunError::Io(String::new()),
+            SonnunError::ProvenanceMismatch(String::new()),
+            SonnunError::KeyLocked(String::new()),
+            SonnunError::KeyRevoked(String::new()),
+            SonnunError::ContentMismatch(String::new()),
+            SonnunError::PolicyUnsatisfied(String::new()),
+            SonnunError::Timestamp(String::new()),
+            SonnunError::Transparency(String::new()),
+        ];
+        let mut codes: Vec<i32> = errors.iter().map(|e| e.exit_code()).collect();
+        codes.sort();
+        codes.dedup();
+        assert_eq!(codes.len(), errors.len());
+        assert!(!codes.contains(&0) && !codes.contains(&2));
+    }
+
+    #[test]
+    fn test_sqlx_pool_errors_map_to_db_unavailable() {
+        assert_eq!(
+            SonnunError::from(sqlx::Error::PoolTimedOut).code(),
+            "DB_UNAVAILABLE"
+        );
+        assert_eq!(SonnunError::from(sqlx::Error::RowNotFound).code(), "NOT_FOUND");
+    }
+}
@@ -0,0 +1,543 @@
+// AIDEV-NOTE: Signed HTML export. The manifest carries `content_hash`, a SHA-256 over the canonicalized
+// page with the manifest <script> element cut out, so a signed manifest cannot be lifted into a
+// different article. The page is rendered twice: once without the script to hash it, once with the
+// signed manifest spliced in at the same spot - removing that element gives back the hashed bytes.
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine as _;
+use serde_json::Value;
+
+use crate::algorithms::{decode_signature, signer_did, KeystoreSigner, ManifestSigner};
+use crate::c2pa::build_c2pa_manifest;
+use crate::cose::sign_manifest_cose;
+use crate::credential::issue_credential;
+use crate::jws::sign_manifest_jws;
+use crate::crypto_utils::hash_text;
+use crate::error::SonnunError;
+use crate::keystore::Keystore;
+use crate::signing::{cosign_manifest, manifest_signing_bytes, sign_manifest_with};
+use crate::timestamp::request_timestamp;
+use crate::transparency::TransparencyLog;
+use crate::verifier::find_manifest_script;
+use crate::{ManifestData, SignatureRole, SignedManifest};
+
+pub const CONTENT_HASH_FIELD: &str = "content_hash";
+
+// Elements whose whitespace is rendered as written, so it is part of the content
+const PRESERVED_ELEMENTS: [&str; 3] = ["pre", "code", "textarea"];
+
+// AIDEV-NOTE: Canonical body: line endings normalised, every whitespace run outside <pre>, <code> and
+// <textarea> collapsed to one space, ends trimmed. Tolerates re-indentation and CRLF conversion by
+// publishing pipelines; any change to tags, attributes or text - whitespace inside those elements
+// included - still changes the hash.
+pub fn canonicalize_body(html: &str) -> String {
+    let mut canonical = String::with_capacity(html.len());
+    let mut rest = html;
+    while let Some((start, end)) = find_preserved_element(rest) {
+        push_collapsed(&mut canonical, &rest[..start]);
+        canonical.push_str(&rest[start..end].replace("\r\n", "\n").replace('\r', "\n"));
+        rest = &rest[end..];
+    }
+    push_collapsed(&mut canonical, rest);
+    canonical.trim_matches(|c: char| c.is_ascii_whitespace()).to_string()
+}
+
+fn push_collapsed(canonical: &mut String, text: &str) {
+    let mut in_whitespace = false;
+    for c in text.chars() {
+        if !c.is_ascii_whitespace() {
+            canonical.push(c);
+        } else if !in_whitespace {
+            canonical.push(' ');
+        }
+        in_whitespace = c.is_ascii_whitespace();
+    }
+}
+
+// Byte range of the first preserved element, from its opening tag through its matching closing tag;
+// an element that is never closed runs to the end of the input
+fn find_preserved_element(html: &str) -> Option<(usize, usize)> {
+    let bytes = html.as_bytes();
+    let (start, name) = (0..bytes.len()).find_map(|at| {
+        PRESERVED_ELEMENTS
+            .into_iter()
+            .find(|name| tag_at(bytes, at, "<", name))
+            .map(|name| (at, name))
+    })?;
+    let mut depth = 0;
+    for at in start..bytes.len() {
+        if tag_at(bytes, at, "<", name) {
+            depth += 1;
+        } else if tag_at(bytes, at, "</", name) {
+            depth -= 1;
+            if depth == 0 {
+                return Some((start, html[at..].find('>').map_or(html.len(), |gt| at + gt + 1)));
+            }
+        }
+    }
+    Some((start, html.len()))
+}
+
+// `prefix` and the tag name, in any case, ending where the name does
+fn tag_at(bytes: &[u8], at: usize, prefix: &str, name: &str) -> bool {
+    let rest = &bytes[at..];
+    let name_end = prefix.len() + name.len();
+    rest.len() > name_end
+        && rest.starts_with(prefix.as_bytes())
+        && rest[prefix.len()..name_end].eq_ignore_ascii_case(name.as_bytes())
+        && (rest[name_end].is_ascii_whitespace() || matches!(rest[name_end], b'>' | b'/'))
+}
+
+pub fn content_hash(html_without_manifest: &str) -> String {
+    hash_text(&canonicalize_body(html_without_manifest))
+}
+
+// The hash sonnun-verify recomputes: everything outside the manifest script element
+pub fn document_content_hash(html: &str) -> Result<String, SonnunError> {
+    let (range, _) = find_manifest_script(html)
+        .ok_or_else(|| SonnunError::ManifestSchema("No Sonnun manifest found in document".to_string()))?;
+    Ok(content_hash(&format!("{}{}", &html[..range.start], &html[range.end..])))
+}
+
+fn manifest_script(signed: &SignedManifest) -> Result<String, SonnunError> {
+    let json = serde_json::to_string_pretty(signed)
+        .map_err(|e| SonnunError::ManifestSchema(format!("Failed to serialize manifest: {}", e)))?;
+    // "</" inside a JSON string would end the script element early; "<\/" is the same JSON
+    Ok(format!(
+        "<script type=\"application/json\" id=\"sonnun-manifest\">\n{}\n</script>",
+        json.replace("</", "<\\/")
+    ))
+}
+
+pub fn render_document(title: &str, content_html: &str, signed: Option<&SignedManifest>) -> Result<String, SonnunError> {
+    let manifest_script = match signed {
+        Some(signed) => manifest_script(signed)?,
+        None => String::new(),
+    };
+    Ok(format!(
+        r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="UTF-8">
+<meta name="viewport" content="width=device-width, initial-scale=1.0">
+<title>{}</title>
+<style>
+body {{ font-family: system-ui, sans-serif; max-width: 800px; margin: 0 auto; padding: 20px; }}
+span[data-provenance="ai"] {{ background: rgba(253, 126, 20, 0.1); border-left: 3px solid #fd7e14; padding-left: 4px; }}
+span[data-provenance="cited"] {{ background: rgba(0, 123, 255, 0.1); border-left: 3px solid #007bff; padding-left: 4px; }}
+</style>
+</head>
+<body>
+<div class="content">
+{}
+</div>
+{}</body>
+</html>
+"#,
+        escape_html(title),
+        content_html,
+        manifest_script
+    ))
+}
+
+// AIDEV-NOTE: Takes the ManifestData generate_manifest built from the span model, never caller JSON.
+// author_did and content_hash are written into it before signing, so the signature covers both.
+pub fn export_signed_document(
+    keystore: &Keystore,
+    key_id: &str,
+    title: &str,
+    content_html: &str,
+    manifest: ManifestData,
+) -> Result<String, SonnunError> {
+    export_signed_document_with(&KeystoreSigner::new(keystore, key_id)?, title, content_html, manifest)
+}
+
+// Same, for a signer outside the keystore such as a P-256 key held by the caller
+pub fn export_signed_document_with(
+    signer: &dyn ManifestSigner,
+    title: &str,
+    content_html: &str,
+    mut manifest: ManifestData,
+) -> Result<String, SonnunError> {
+    manifest.author_did = Some(signer_did(signer)?);
+    let mut manifest = serde_json::to_value(&manifest)
+        .map_err(|e| SonnunError::ManifestSchema(format!("Failed to serialize manifest: {}", e)))?;
+    let unsigned = render_document(title, content_html, None)?;
+    manifest
+        .as_object_mut()
+        .ok_or_else(|| SonnunError::ManifestSchema("Manifest must be a JSON object".to_string()))?
+        .insert(CONTENT_HASH_FIELD.to_string(), Value::String(content_hash(&unsigned)));
+    let signed = sign_manifest_with(signer, manifest)?;
+    render_document(title, content_html, Some(&signed))
+}
+
+pub fn cosign_document(
+    keystore: &Keystore,
+    key_id: &str,
+    role: SignatureRole,
+    html: &str,
+) -> Result<String, SonnunError> {
+    let mut signed = read_signed_manifest(html)?;
+    cosign_manifest(keystore, key_id, role, &mut signed)?;
+    replace_signed_manifest(html, &signed)
+}
+
+pub async fn timestamp_document(html: &str, tsa_url: &str) -> Result<String, SonnunError> {
+    let mut signed = read_signed_manifest(html)?;
+    let token = request_timestamp(tsa_url, &decode_signature(&signed.signature)?).await?;
+    signed.timestamp = Some(BASE64.encode(token));
+    replace_signed_manifest(html, &signed)
+}
+
+// The logged entry is the manifest's signing bytes, so the proof can be checked from the page alone
+pub fn log_document(log: &TransparencyLog, html: &str) -> Result<String, SonnunError> {
+    let mut signed = read_signed_manifest(html)?;
+    if signed.transparency.is_some() {
+        return Err(SonnunError::InvalidInput("Document is already in the transparency log".to_string()));
+    }
+    signed.transparency = Some(log.append(&manifest_signing_bytes(&signed.manifest)?)?);
+    replace_signed_manifest(html, &signed)
+}
+
+// The store is built from the manifest already signed into the page; its hard binding is these exact
+// page bytes, so it stops matching as soon as the page changes
+pub fn c2pa_document(keystore: &Keystore, key_id: &str, title: &str, html: &str) -> Result<Vec<u8>, SonnunError> {
+    let (_, manifest_data, _) = read_author_manifest(keystore, key_id, html)?;
+    build_c2pa_manifest(keystore, key_id, &manifest_data, title, html.as_bytes())
+}
+
+// The credential restates the page's signed manifest under the same author key
+pub fn credential_document(keystore: &Keystore, key_id: &str, html: &str) -> Result<String, SonnunError> {
+    let (_, manifest_data, content_hash) = read_author_manifest(keystore, key_id, html)?;
+    let credential = issue_credential(keystore, key_id, manifest_data, &content_hash)?;
+    serde_json::to_string_pretty(&credential)
+        .map_err(|e| SonnunError::ManifestSchema(format!("Failed to serialize credential: {}", e)))
+}
+
+// The manifest bytes change encoding, not content, so content_hash still binds the envelope to the page
+pub fn cose_document(keystore: &Keystore, key_id: &str, html: &str) -> Result<Vec<u8>, SonnunError> {
+    let (signed, _, _) = read_author_manifest(keystore, key_id, html)?;
+    sign_manifest_cose(keystore, key_id, &signed.manifest)
+}
+
+// Derived formats speak for the author, so only the key that signed the page may issue them
+fn read_author_manifest(
+    keystore: &Keystore,
+    key_id: &str,
+    html: &str,
+) -> Result<(SignedManifest, ManifestData, String), SonnunError> {
+    let signed = read_signed_manifest(html)?;
+    if keystore.public_key(key_id)? != signed.public_key {
+        return Err(SonnunError::InvalidKey("Only the document's author key can sign derived formats".to_string()));
+    }
+    let manifest_data: ManifestData = serde_json::from_value(signed.manifest.clone())
+        .map_err(|e| SonnunError::ManifestSchema(format!("Signed manifest is not a provenance manifest: {}", e)))?;
+    let content_hash = signed
+        .manifest
+        .get(CONTENT_HASH_FIELD)
+        .and_then(Value::as_str)
+        .ok_or_else(|| SonnunError::ContentMismatch("Manifest does not bind the document content (no content_hash)".to_string()))?
+        .to_string();
+    Ok((signed, manifest_data, content_hash))
+}
+
+// The manifest stays readable JSON in the page; the JWS payload segment is left empty (detached)
+pub fn jws_document(keystore: &Keystore, key_id: &str, html: &str) -> Result<String, SonnunError> {
+    let mut signed = read_signed_manifest(html)?;
+    if keystore.public_key(key_id)? != signed.public_key {
+        return Err(SonnunError::InvalidKey("The JWS must be by the document's primary signer".to_string()));
+    }
+    signed.jws = Some(sign_manifest_jws(keystore, key_id, &signed.manifest, true)?);
+    replace_signed_manifest(html, &signed)
+}
+
+fn read_signed_manifest(html: &str) -> Result<SignedManifest, SonnunError> {
+    let (_, json) = find_manifest_script(html)
+        .ok_or_else(|| SonnunError::ManifestSchema("No Sonnun manifest found in document".to_string()))?;
+    serde_json::from_str(json.trim()).map_err(|e| SonnunError::ManifestSchema(format!("Invalid signed manifest: {}", e)))
+}
+
+// AIDEV-NOTE: Only the manifest script element is rewritten, and it is outside content_hash
+fn replace_signed_manifest(html: &str, signed: &SignedManifest) -> Result<String, SonnunError> {
+    let (range, _) = find_manifest_script(html)
+        .ok_or_else(|| SonnunError::ManifestSchema("No Sonnun manifest found in document".to_string()))?;
+    Ok(format!("{}{}{}", &html[..range.start], manifest_script(signed)?, &html[range.end..]))
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keystore::test_keys::{public, test_keystore, unlocked_key, unlocked_key_of};
+    use crate::revocation::{RevocationList, RevocationReason, RevokedKey};
+    use crate::rotation::RotationStatement;
+    use crate::key_formats::did_key;
+    use crate::test_manifest::manifest_data;
+    use crate::parse_public_key;
+    use crate::credential::verify_credential;
+    use crate::database::Database;
+    use crate::verifier::{verify_html, ContentBinding, VerifyOptions};
+    use crate::{EventType, Operation, ProvenanceEvent, SignatureAlgorithm};
+    use ed25519_dalek::SigningKey;
+    use rand::rngs::OsRng;
+
+    #[test]
+    fn test_exported_document_verifies_and_is_bound_to_its_content() {
+        let (keystore, dir) = test_keystore("export");
+        let key = unlocked_key(&keystore, "Author");
+        let mut manifest = manifest_data();
+        manifest.events[0].source = "ends in </script> on purpose".to_string();
+
+        let html = export_signed_document(&keystore, &key.key_id, "A & B", "<p>Hello world</p>", manifest).unwrap();
+        let result = verify_html(&html, &VerifyOptions::default()).unwrap();
+        assert_eq!(result.content, ContentBinding::Matched(document_content_hash(&html).unwrap()));
+        assert_eq!(result.author_did, did_key(&parse_public_key(&key.public_key).unwrap()));
+
+        // Re-indenting and CRLF line endings keep the hash
+        let reformatted = html.replace('\n', "\r\n    ");
+        assert!(verify_html(&reformatted, &VerifyOptions::default()).is_ok());
+
+        // The same signed manifest lifted into another article fails
+        let lifted = html.replace("<p>Hello world</p>", "<p>Goodbye world</p>");
+        let error = verify_html(&lifted, &VerifyOptions::default()).unwrap_err();
+        assert_eq!(error.code(), "CONTENT_MISMATCH");
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    // A P-256 keystore key signs the page; the EdDSA-only formats refuse it by name
+    #[test]
+    fn test_p256_keystore_key_exports_ecdsa_pages() {
+        let (keystore, dir) = test_keystore("export-p256");
+        let key = unlocked_key_of(&keystore, "Author", SignatureAlgorithm::EcdsaP256);
+        let editor = unlocked_key(&keystore, "Editor");
+
+        let html = export_signed_document(&keystore, &key.key_id, "Post", "<p>Hello</p>", manifest_data()).unwrap();
+        let result = verify_html(&html, &VerifyOptions::default()).unwrap();
+        assert_eq!(result.algorithm, SignatureAlgorithm::EcdsaP256);
+        assert_eq!(result.public_key, key.public_key);
+        assert!(result.author_did.starts_with("did:key:zDn"));
+
+        assert_eq!(cose_document(&keystore, &key.key_id, &html).unwrap_err().code(), "INVALID_KEY");
+        assert_eq!(c2pa_document(&keystore, &key.key_id, "Post", &html).unwrap_err().code(), "INVALID_KEY");
+        // An ed25519 co-signature still goes on top
+        cosign_document(&keystore, &editor.key_id, SignatureRole::Editor, &html).unwrap();
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_cosigning_keeps_content_hash_and_adds_role() {
+        let (keystore, dir) = test_keystore("cosign");
+        let author = unlocked_key(&keystore, "Author");
+        let editor = unlocked_key(&keystore, "Editor");
+        let manifest = manifest_data();
+
+        let html = export_signed_document(&keystore, &author.key_id, "Draft", "<p>Hello</p>", manifest).unwrap();
+        let cosigned = cosign_document(&keystore, &editor.key_id, SignatureRole::Editor, &html).unwrap();
+        assert_eq!(document_content_hash(&cosigned).unwrap(), document_content_hash(&html).unwrap());
+
+        let options = VerifyOptions {
+            required_signers: vec!["author".parse().unwrap(), "editor".parse().unwrap()],
+            trusted_key: Some(author.public_key.clone()),
+            trusted_signers: vec![editor.public_key.clone()],
+            ..Default::default()
+        };
+        let result = verify_html(&cosigned, &options).unwrap();
+        assert_eq!(result.public_key, author.public_key);
+        assert_eq!(result.signers[1].public_key, editor.public_key);
+
+        let again = cosign_document(&keystore, &editor.key_id, SignatureRole::Editor, &cosigned).unwrap_err();
+        assert_eq!(again.code(), "INVALID_INPUT");
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_logged_document_proves_inclusion() {
+        let (keystore, dir) = test_keystore("export-log");
+        let key = unlocked_key(&keystore, "Author");
+        let log = TransparencyLog::new(dir.join("transparency.jsonl"));
+        let manifest = manifest_data();
+
+        let html = export_signed_document(&keystore, &key.key_id, "Post", "<p>Hello</p>", manifest).unwrap();
+        let logged = log_document(&log, &html).unwrap();
+        let options = VerifyOptions {
+            log_key: Some(log.public_key().unwrap()),
+            ..Default::default()
+        };
+        assert_eq!(verify_html(&logged, &options).unwrap().transparency.unwrap().leaf_index, 0);
+        assert_eq!(verify_html(&html, &options).unwrap_err().code(), "TRANSPARENCY");
+        // The log vouches with its own key, not the author's
+        let author_as_log = VerifyOptions {
+            log_key: Some(key.public_key.clone()),
+            ..Default::default()
+        };
+        assert_eq!(verify_html(&logged, &author_as_log).unwrap_err().code(), "TRANSPARENCY");
+        assert_eq!(log_document(&log, &logged).unwrap_err().code(), "INVALID_INPUT");
+
+        // A proof lifted onto a different manifest does not verify
+        let other = ManifestData {
+            total_characters: 31,
+            ..manifest_data()
+        };
+        let other_html = export_signed_document(&keystore, &key.key_id, "Post", "<p>Hello</p>", other).unwrap();
+        let mut other_signed = read_signed_manifest(&other_html).unwrap();
+        other_signed.transparency = read_signed_manifest(&logged).unwrap().transparency;
+        let lifted = replace_signed_manifest(&other_html, &other_signed).unwrap();
+        assert_eq!(verify_html(&lifted, &VerifyOptions::default()).unwrap_err().code(), "TRANSPARENCY");
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    // "Hello world!" logged as human, AI and cited text, and the manifest generate_manifest builds from it
+    async fn logged_manifest() -> ManifestData {
+        let db = Database::new("sqlite::memory:").await.unwrap();
+        let doc = db.create_document("Post").await.unwrap();
+        for (event_type, source, from, text) in [
+            (EventType::Human, "keyboard", 0, "Hello "),
+            (EventType::Ai, "gpt-4o", 6, "world"),
+            (EventType::Cited, "https://example.com/quote", 11, "!"),
+        ] {
+            db.insert_event(ProvenanceEvent {
+                document_id: doc.id,
+                timestamp: chrono::Utc::now().to_rfc3339(),
+                event_type,
+                text_hash: hash_text(text),
+                source: source.to_string(),
+                span_length: text.encode_utf16().count(),
+                operation: Operation::Insert,
+                from: Some(from),
+                to: None,
+                text: Some(text.to_string()),
+            })
+            .await
+            .unwrap();
+        }
+        db.generate_manifest(doc.id, None).await.unwrap()
+    }
+
+    // The manifest comes from generate_manifest, exactly as the app signs it
+    #[tokio::test]
+    async fn test_c2pa_manifest_and_credential_verify_against_their_page_only() {
+        let (keystore, dir) = test_keystore("export-c2pa");
+        let author = unlocked_key(&keystore, "Author");
+        let other = unlocked_key(&keystore, "Other");
+        let manifest = logged_manifest().await;
+        let export = |manifest: &ManifestData| {
+            export_signed_document(&keystore, &author.key_id, "Post", "<p>Hello world!</p>", manifest.clone()).unwrap()
+        };
+
+        let html = export(&manifest);
+        let store = c2pa_document(&keystore, &author.key_id, "Post", &html).unwrap();
+        let options = VerifyOptions {
+            c2pa_manifest: Some(store),
+            ..Default::default()
+        };
+        let c2pa = verify_html(&html, &options).unwrap().c2pa.unwrap();
+        assert_eq!(c2pa.actions()[1]["parameters"]["sonnun.event_type"], "ai");
+        assert_eq!(c2pa.ingredients(), ["https://example.com/quote"]);
+        assert_eq!(c2pa_document(&keystore, &other.key_id, "Post", &html).unwrap_err().code(), "INVALID_KEY");
+
+        let credential = credential_document(&keystore, &author.key_id, &html).unwrap();
+        let result = verify_credential(&credential, Some(&html), &VerifyOptions::default()).unwrap();
+        assert_eq!(result.content, ContentBinding::Matched(document_content_hash(&html).unwrap()));
+
+        // Same text, different signed breakdown: the old store does not carry over
+        let all_human = ManifestData {
+            human_percentage: 100.0,
+            ai_percentage: 0.0,
+            cited_percentage: 0.0,
+            ..manifest.clone()
+        };
+        assert_eq!(verify_html(&export(&all_human), &options).unwrap_err().code(), "CONTENT_MISMATCH");
+        let other_page = html.replace("<p>Hello world!</p>", "<p>Hullo world!</p>");
+        let error = verify_credential(&credential, Some(&other_page), &VerifyOptions::default()).unwrap_err();
+        assert_eq!(error.code(), "CONTENT_MISMATCH");
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    // generated_at is the signing time rotations and revocations are dated against
+    #[tokio::test]
+    async fn test_generated_manifest_is_judged_by_its_signing_time() {
+        let old = SigningKey::generate(&mut OsRng);
+        let new = SigningKey::generate(&mut OsRng);
+        let manifest = logged_manifest().await;
+        assert!(manifest.generated_at.is_some());
+        let html = export_signed_document_with(&new, "Post", "<p>Hello world!</p>", manifest).unwrap();
+        let day = chrono::Duration::days(1);
+        let yesterday = (chrono::Utc::now() - day).to_rfc3339();
+        let tomorrow = (chrono::Utc::now() + day).to_rfc3339();
+
+        let rotated = |effective_date: &str| VerifyOptions {
+            trusted_key: Some(public(&old)),
+            rotations: vec![RotationStatement::new(&public(&old), &public(&new), effective_date)
+                .unwrap()
+                .sign(&old)
+                .unwrap()],
+            ..Default::default()
+        };
+        assert_eq!(verify_html(&html, &rotated(&yesterday)).unwrap().rotation_path.len(), 1);
+        assert!(verify_html(&html, &rotated(&tomorrow)).is_err());
+
+        let superseded = |revoked_at: &str| VerifyOptions {
+            revocations: vec![RevocationList::new(
+                &public(&old),
+                vec![RevokedKey {
+                    public_key: public(&new),
+                    reason: RevocationReason::Superseded,
+                    revoked_at: revoked_at.to_string(),
+                }],
+            )
+            .unwrap()
+            .sign(&old)
+            .unwrap()],
+            ..rotated(&yesterday)
+        };
+        assert!(verify_html(&html, &superseded(&tomorrow)).is_ok());
+        assert_eq!(verify_html(&html, &superseded(&yesterday)).unwrap_err().code(), "KEY_REVOKED");
+    }
+
+    #[test]
+    fn test_detached_jws_rides_along_in_the_page() {
+        let (keystore, dir) = test_keystore("export-jws");
+        let author = unlocked_key(&keystore, "Author");
+        let editor = unlocked_key(&keystore, "Editor");
+        let manifest = manifest_data();
+
+        let html = export_signed_document(&keystore, &author.key_id, "Post", "<p>Hello</p>", manifest).unwrap();
+        let with_jws = jws_document(&keystore, &author.key_id, &html).unwrap();
+        assert!(read_signed_manifest(&with_jws).unwrap().jws.unwrap().contains(".."));
+        verify_html(&with_jws, &VerifyOptions::default()).unwrap();
+        assert_eq!(jws_document(&keystore, &editor.key_id, &html).unwrap_err().code(), "INVALID_KEY");
+
+        // A JWS by another key cannot stand in for the author's
+        let mut signed = read_signed_manifest(&html).unwrap();
+        signed.jws = Some(sign_manifest_jws(&keystore, &editor.key_id, &signed.manifest, true).unwrap());
+        let swapped = replace_signed_manifest(&html, &signed).unwrap();
+        assert_eq!(verify_html(&swapped, &VerifyOptions::default()).unwrap_err().code(), "INVALID_KEY");
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_canonical_body_ignores_whitespace_layout_only() {
+        assert_eq!(canonicalize_body("  <p>a\r\n\tb</p>\n"), "<p>a b</p>");
+        assert_ne!(content_hash("<p>a b</p>"), content_hash("<p>a  c</p>"));
+        assert_ne!(content_hash("<p>ab</p>"), content_hash("<p>a b</p>"));
+    }
+
+    #[test]
+    fn test_canonical_body_keeps_whitespace_that_renders() {
+        assert_ne!(content_hash("<pre>a  b</pre>"), content_hash("<pre>a b</pre>"));
+        assert_ne!(content_hash("<p><code>x =  1</code></p>"), content_hash("<p><code>x = 1</code></p>"));
+        assert_ne!(content_hash("<textarea>a\n\nb</textarea>"), content_hash("<textarea>a\nb</textarea>"));
+        // Line endings are still normalised inside, and layout outside still collapses
+        assert_eq!(
+            canonicalize_body("<div>\n  <PRE class=\"x\">a\r\n  b</PRE>\n</div>"),
+            "<div> <PRE class=\"x\">a\n  b</PRE> </div>"
+        );
+        assert_eq!(canonicalize_body("<pre><pre> a </pre>  b </pre>  c"), "<pre><pre> a </pre>  b </pre> c");
+        assert_eq!(canonicalize_body("<codex>a  b</codex>"), "<codex>a b</codex>");
+    }
+}
@@ -0,0 +1,235 @@
+// AIDEV-NOTE: RFC 8785 JSON Canonicalization Scheme. Signatures are made and checked over these bytes,
+// so JS and Rust producers agree on key order, whitespace, string escaping and number formatting.
+// Object keys sort by UTF-16 code units (not serde_json's UTF-8 byte order) and numbers are written
+// the way ECMAScript's Number.prototype.toString writes them.
+use serde_json::{Map, Number, Value};
+
+use crate::error::SonnunError;
+
+pub fn canonicalize(value: &Value) -> Result<String, SonnunError> {
+    let mut out = String::new();
+    write_value(value, &mut out)?;
+    Ok(out)
+}
+
+pub fn canonicalize_bytes(value: &Value) -> Result<Vec<u8>, SonnunError> {
+    canonicalize(value).map(String::into_bytes)
+}
+
+fn write_value(value: &Value, out: &mut String) -> Result<(), SonnunError> {
+    match value {
+        Value::Null => out.push_str("null"),
+        Value::Bool(b) => out.push_str(if *b { "true" } else { "false" }),
+        Value::Number(n) => out.push_str(&format_number(n)?),
+        Value::String(s) => write_string(s, out),
+        Value::Array(items) => {
+            out.push('[');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_value(item, out)?;
+            }
+            out.push(']');
+        }
+        Value::Object(map) => write_object(map, out)?,
+    }
+    Ok(())
+}
+
+fn write_object(map: &Map<String, Value>, out: &mut String) -> Result<(), SonnunError> {
+    let mut entries: Vec<(&String, &Value)> = map.iter().collect();
+    entries.sort_by(|(a, _), (b, _)| a.encode_utf16().cmp(b.encode_utf16()));
+    out.push('{');
+    for (i, (key, value)) in entries.into_iter().enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        write_string(key, out);
+        out.push(':');
+        write_value(value, out)?;
+    }
+    out.push('}');
+    Ok(())
+}
+
+// RFC 8785 3.2.2.2: only '"', '\\' and control characters are escaped, with the short forms where
+// JSON has them and lowercase \u00xx otherwise. Everything else, '/' and non-ASCII included, is literal.
+fn write_string(s: &str, out: &mut String) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\u{8}' => out.push_str("\\b"),
+            '\u{c}' => out.push_str("\\f"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+// AIDEV-NOTE: JSON numbers are IEEE-754 doubles in JCS; integers beyond 2^53 lose precision exactly as
+// they would in JavaScript. Rust's `{:e}` yields the shortest round-trip digits, which are then laid
+// out per ECMAScript's Number::toString (ECMA-262 7.1.12.1).
+fn format_number(n: &Number) -> Result<String, SonnunError> {
+    let value = n
+        .as_f64()
+        .ok_or_else(|| SonnunError::InvalidInput(format!("Number {} cannot be canonicalized", n)))?;
+    format_f64(value)
+}
+
+pub fn format_f64(value: f64) -> Result<String, SonnunError> {
+    if !value.is_finite() {
+        return Err(SonnunError::InvalidInput("NaN and Infinity have no JSON form".to_string()));
+    }
+    if value == 0.0 {
+        return Ok("0".to_string()); // -0 included
+    }
+
+    let sign = if value < 0.0 { "-" } else { "" };
+    let (digits, exponent) = shortest_digits(value.abs());
+    let k = digits.len() as i32;
+    let n = exponent + 1; // position of the decimal point relative to the digits
+
+    let body = if k <= n && n <= 21 {
+        format!("{}{}", digits, "0".repeat((n - k) as usize))
+    } else if 0 < n && n <= 21 {
+        format!("{}.{}", &digits[..n as usize], &digits[n as usize..])
+    } else if -6 < n && n <= 0 {
+        format!("0.{}{}", "0".repeat((-n) as usize), digits)
+    } else {
+        let exponent_sign = if n - 1 < 0 { "-" } else { "+" };
+        let fraction = if k == 1 {
+            String::new()
+        } else {
+            format!(".{}", &digits[1..])
+        };
+        format!("{}{}e{}{}", &digits[..1], fraction, exponent_sign, (n - 1).abs())
+    };
+    Ok(format!("{}{}", sign, body))
+}
+
+fn scientific_digits(formatted: &str) -> (String, i32) {
+    let (mantissa, exponent) = formatted.split_once('e').expect("{:e} always has an exponent");
+    let digits = mantissa.chars().filter(|c| *c != '.').collect();
+    (digits, exponent.parse().expect("{:e} exponent is an integer"))
+}
+
+// AIDEV-NOTE: Rust and ECMAScript agree on the shortest digit count, but when the value sits exactly
+// halfway between two shortest candidates Rust rounds up while ECMAScript picks the even digit
+// (RFC 8785's 1424953923781206.2 vector). Only an odd final digit can need correcting, and the exact
+// expansion - at most 767 significant digits for a double - tells whether it was a tie.
+fn shortest_digits(value: f64) -> (String, i32) {
+    let (digits, exponent) = scientific_digits(&format!("{:e}", value));
+    if !digits.bytes().last().is_some_and(is_odd_digit) {
+        return (digits, exponent);
+    }
+    let (exact, exact_exponent) = scientific_digits(&format!("{:.800e}", value));
+    let k = digits.len();
+    let tie = exact_exponent == exponent
+        && exact.as_bytes()[k] == b'5'
+        && exact.bytes().skip(k + 1).all(|d| d == b'0');
+    let truncated = &exact[..k];
+    if tie && !truncated.bytes().last().is_some_and(is_odd_digit) {
+        return (truncated.to_string(), exponent);
+    }
+    (digits, exponent)
+}
+
+fn is_odd_digit(digit: u8) -> bool {
+    (digit - b'0') % 2 == 1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // RFC 8785 Appendix B: IEEE-754 bit patterns and their canonical forms
+    #[test]
+    fn test_rfc8785_number_vectors() {
+        let vectors: [(u64, &str); 24] = [
+            (0x0000000000000000, "0"),
+            (0x8000000000000000, "0"),
+            (0x0000000000000001, "5e-324"),
+            (0x8000000000000001, "-5e-324"),
+            (0x7fefffffffffffff, "1.7976931348623157e+308"),
+            (0xffefffffffffffff, "-1.7976931348623157e+308"),
+            (0x4340000000000000, "9007199254740992"),
+            (0xc340000000000000, "-9007199254740992"),
+            (0x4430000000000000, "295147905179352830000"),
+            (0x44b52d02c7e14af5, "9.999999999999997e+22"),
+            (0x44b52d02c7e14af6, "1e+23"),
+            (0x44b52d02c7e14af7, "1.0000000000000001e+23"),
+            (0x444b1ae4d6e2ef4e, "999999999999999700000"),
+            (0x444b1ae4d6e2ef4f, "999999999999999900000"),
+            (0x444b1ae4d6e2ef50, "1e+21"),
+            (0x3eb0c6f7a0b5ed8c, "9.999999999999997e-7"),
+            (0x3eb0c6f7a0b5ed8d, "0.000001"),
+            (0x41b3de4355555553, "333333333.3333332"),
+            (0x41b3de4355555554, "333333333.33333325"),
+            (0x41b3de4355555555, "333333333.3333333"),
+            (0x41b3de4355555556, "333333333.3333334"),
+            (0x41b3de4355555557, "333333333.33333343"),
+            (0xbecbf647612f3696, "-0.0000033333333333333333"),
+            (0x43143ff3c1cb0959, "1424953923781206.2"),
+        ];
+        for (bits, expected) in vectors {
+            assert_eq!(format_f64(f64::from_bits(bits)).unwrap(), expected, "{:016x}", bits);
+        }
+        assert!(format_f64(f64::from_bits(0x7fffffffffffffff)).is_err());
+        assert!(format_f64(f64::INFINITY).is_err());
+    }
+
+    // RFC 8785 section 3.2.2 example
+    #[test]
+    fn test_rfc8785_structure_example() {
+        let input = r#"{
+            "numbers": [333333333.33333329, 1E30, 4.50, 2e-3, 0.000000000000000000000000001],
+            "string": "\u20ac$\u000F\u000aA'\u0042\u0022\u005c\\\"\/",
+            "literals": [null, true, false]
+        }"#;
+        let value: Value = serde_json::from_str(input).unwrap();
+        assert_eq!(
+            canonicalize(&value).unwrap(),
+            r#"{"literals":[null,true,false],"numbers":[333333333.3333333,1e+30,4.5,0.002,1e-27],"string":"€$\u000f\nA'B\"\\\\\"/"}"#
+        );
+    }
+
+    // RFC 8785 section 3.2.3: keys sort by UTF-16 code units, so the emoji's surrogates come before U+FB33
+    #[test]
+    fn test_rfc8785_sorting_example() {
+        let input = r#"{
+            "€": "Euro Sign",
+            "\r": "Carriage Return",
+            "דּ": "Hebrew Letter Dalet With Dagesh",
+            "1": "One",
+            "😀": "Emoji: Grinning Face",
+            "\u0080": "Control",
+            "ö": "Latin Small Letter O With Diaeresis"
+        }"#;
+        let value: Value = serde_json::from_str(input).unwrap();
+        let canonical = canonicalize(&value).unwrap();
+        let expected = [
+            "Carriage Return",
+            "One",
+            "Control",
+            "Latin Small Letter O With Diaeresis",
+            "Euro Sign",
+            "Emoji: Grinning Face",
+            "Hebrew Letter Dalet With Dagesh",
+        ];
+        let positions: Vec<usize> = expected.iter().map(|name| canonical.find(name).unwrap()).collect();
+        assert!(positions.windows(2).all(|pair| pair[0] < pair[1]), "{}", canonical);
+    }
+
+    #[test]
+    fn test_integers_and_nesting() {
+        let value = serde_json::json!({"b": [1, -2, {"z": 0, "a": 10000000000000000000u64}], "a": "x"});
+        assert_eq!(canonicalize(&value).unwrap(), r#"{"a":"x","b":[1,-2,{"a":10000000000000000000,"z":0}]}"#);
+    }
+}
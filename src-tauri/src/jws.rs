@@ -0,0 +1,206 @@
+// AIDEV-NOTE: JWS (RFC 7515) signing mode for JOSE pipelines: compact serialization with alg EdDSA
+// (RFC 8037) and kid set to the keystore fingerprint. The payload is always the manifest's RFC 8785
+// bytes. Detached mode (Appendix F) leaves the payload segment empty; the reader rebuilds it from the
+// manifest, which is how the JWS rides along in HTML without hiding the manifest inside base64.
+use base64::engine::general_purpose::{STANDARD as BASE64, URL_SAFE_NO_PAD as BASE64URL};
+use base64::engine::{DecodePaddingMode, GeneralPurpose, GeneralPurposeConfig};
+use base64::{alphabet, Engine as _};
+use ed25519_dalek::{Signature, Verifier, VerifyingKey};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::error::SonnunError;
+use crate::keystore::{key_fingerprint, Keystore};
+use crate::signing::manifest_signing_bytes;
+use crate::verifier::{
+    reject_html_only_options, resolve_fingerprinted_key, verify_sole_signer_manifest, VerificationResult, VerifyOptions,
+};
+
+pub const JWS_ALGORITHM: &str = "EdDSA";
+
+// AIDEV-NOTE: Other encoders may leave stray low bits in the last character of the payload segment.
+// Those still decode, and since the signature covers the segment as transmitted, nothing is re-encoded.
+const PAYLOAD_BASE64URL: GeneralPurpose = GeneralPurpose::new(
+    &alphabet::URL_SAFE,
+    GeneralPurposeConfig::new()
+        .with_encode_padding(false)
+        .with_decode_allow_trailing_bits(true)
+        .with_decode_padding_mode(DecodePaddingMode::RequireNone),
+);
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JwsHeader {
+    pub alg: String,
+    pub kid: String, // keystore fingerprint of the signing key
+    // Extensions this verifier would have to understand; none are supported, so any entry fails
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub crit: Vec<String>,
+}
+
+// A compact JWS split into its parts; payload is None when detached
+#[derive(Debug, Clone, PartialEq)]
+pub struct DecodedJws {
+    pub header: JwsHeader,
+    pub payload: Option<Vec<u8>>,
+    encoded_header: String,          // as transmitted
+    encoded_payload: Option<String>, // as transmitted; None when detached
+    signature: Vec<u8>,
+}
+
+fn jws_error(message: impl std::fmt::Display) -> SonnunError {
+    SonnunError::ManifestSchema(format!("Invalid JWS: {}", message))
+}
+
+pub fn sign_manifest_jws(
+    keystore: &Keystore,
+    key_id: &str,
+    manifest: &Value,
+    detached: bool,
+) -> Result<String, SonnunError> {
+    let public_key = keystore.ed25519_public_key(key_id)?;
+    let header = JwsHeader {
+        alg: JWS_ALGORITHM.to_string(),
+        kid: key_fingerprint(public_key.as_bytes()),
+        crit: Vec::new(),
+    };
+    let header = serde_json::to_vec(&header).map_err(jws_error)?;
+    let header = BASE64URL.encode(header);
+    let payload = BASE64URL.encode(manifest_signing_bytes(manifest)?);
+    let signature = BASE64
+        .decode(keystore.sign(key_id, format!("{}.{}", header, payload).as_bytes())?)
+        .map_err(|e| SonnunError::InvalidSignature(format!("Invalid signature encoding: {}", e)))?;
+    let payload = if detached { "" } else { payload.as_str() };
+    Ok(format!("{}.{}.{}", header, payload, BASE64URL.encode(signature)))
+}
+
+pub fn decode_jws(compact: &str) -> Result<DecodedJws, SonnunError> {
+    let parts: Vec<&str> = compact.trim().split('.').collect();
+    let [header_b64, payload_b64, signature_b64] = parts[..] else {
+        return Err(jws_error("expected three dot-separated parts"));
+    };
+    let header_json = BASE64URL.decode(header_b64).map_err(jws_error)?;
+    let header: JwsHeader = serde_json::from_slice(&header_json).map_err(jws_error)?;
+    if header.alg != JWS_ALGORITHM {
+        return Err(jws_error(format!("unsupported alg {}", header.alg)));
+    }
+    if let Some(extension) = header.crit.first() {
+        return Err(jws_error(format!("unsupported critical header {}", extension)));
+    }
+    let payload = match payload_b64 {
+        "" => None,
+        encoded => Some(PAYLOAD_BASE64URL.decode(encoded).map_err(jws_error)?),
+    };
+    Ok(DecodedJws {
+        header,
+        payload,
+        encoded_header: header_b64.to_string(),
+        encoded_payload: (!payload_b64.is_empty()).then(|| payload_b64.to_string()),
+        signature: BASE64URL.decode(signature_b64).map_err(jws_error)?,
+    })
+}
+
+impl DecodedJws {
+    // `detached_payload` replaces an empty payload segment; an attached payload must not be overridden
+    pub fn verify(&self, key: &VerifyingKey, detached_payload: Option<&[u8]>) -> Result<(), SonnunError> {
+        let encoded_payload = match (&self.encoded_payload, detached_payload) {
+            (Some(encoded), None) => encoded.clone(),
+            (None, Some(payload)) => BASE64URL.encode(payload),
+            (Some(_), Some(_)) => return Err(jws_error("payload is attached but a detached payload was given")),
+            (None, None) => return Err(jws_error("payload is detached and was not supplied")),
+        };
+        let signing_input = format!("{}.{}", self.encoded_header, encoded_payload);
+        let signature = Signature::from_slice(&self.signature)
+            .map_err(|e| SonnunError::InvalidSignature(format!("Invalid signature: {}", e)))?;
+        key.verify(signing_input.as_bytes(), &signature)
+            .map_err(|_| SonnunError::SignatureMismatch("JWS signature does not match manifest".to_string()))
+    }
+}
+
+// AIDEV-NOTE: The detached JWS in an HTML envelope is by the primary signer, so its kid has to name
+// the same key as the top-level public_key
+pub fn verify_detached_jws(jws: &str, manifest: &Value, key: &VerifyingKey) -> Result<(), SonnunError> {
+    let decoded = decode_jws(jws)?;
+    if decoded.payload.is_some() {
+        return Err(jws_error("the JWS in a signed document must be detached"));
+    }
+    if decoded.header.kid != key_fingerprint(key.as_bytes()) {
+        return Err(SonnunError::InvalidKey("JWS kid does not match the document public key".to_string()));
+    }
+    decoded.verify(key, Some(&manifest_signing_bytes(manifest)?))
+}
+
+// A compact JWS with the manifest attached, verified on its own like a COSE envelope
+pub fn verify_jws(compact: &str, page: Option<&str>, options: &VerifyOptions) -> Result<VerificationResult, SonnunError> {
+    reject_html_only_options(options, "JWS envelopes")?;
+    let decoded = decode_jws(compact)?;
+    let payload = decoded
+        .payload
+        .as_deref()
+        .ok_or_else(|| jws_error("payload is detached; detached JWS is verified inside the signed HTML"))?;
+    let manifest: Value = serde_json::from_slice(payload).map_err(jws_error)?;
+    if !manifest.is_object() {
+        return Err(SonnunError::ManifestSchema("Manifest must be a JSON object".to_string()));
+    }
+    let verifying_key = resolve_fingerprinted_key(&manifest, &decoded.header.kid, options)?;
+    decoded.verify(&verifying_key, None)?;
+    verify_sole_signer_manifest(manifest, &verifying_key, page, options)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto_utils::parse_public_key;
+    use crate::keystore::test_keys::{test_keystore, unlocked_key};
+    use crate::test_manifest::manifest_value;
+    use serde_json::json;
+
+    #[test]
+    fn test_compact_and_detached_jws_verify() {
+        let (keystore, dir) = test_keystore("jws");
+        let key = unlocked_key(&keystore, "Author");
+        let verifying_key = parse_public_key(&key.public_key).unwrap();
+        let manifest = manifest_value();
+
+        let compact = sign_manifest_jws(&keystore, &key.key_id, &manifest, false).unwrap();
+        let header: Value = serde_json::from_slice(&BASE64URL.decode(compact.split('.').next().unwrap()).unwrap()).unwrap();
+        assert_eq!(header, json!({"alg": "EdDSA", "kid": key.key_id}));
+        let options = VerifyOptions {
+            trusted_key: Some(key.public_key.clone()),
+            allow_unbound_content: true,
+            ..Default::default()
+        };
+        let signed_bytes = manifest_signing_bytes(&manifest).unwrap();
+        let verified = verify_jws(&compact, None, &options).unwrap();
+        assert_eq!(manifest_signing_bytes(&verified.manifest).unwrap(), signed_bytes);
+
+        // A payload segment with stray trailing bits, signed as sent by some other encoder
+        let header_b64 = compact.split('.').next().unwrap();
+        let mut payload_b64 = BASE64URL.encode(&signed_bytes).into_bytes();
+        // The canonical last character has its unused low bits clear; set the lowest one
+        let last = payload_b64.last_mut().unwrap();
+        let alphabet = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+        *last = alphabet[alphabet.iter().position(|c| c == last).unwrap() + 1];
+        let payload_b64 = String::from_utf8(payload_b64).unwrap();
+        assert!(BASE64URL.decode(&payload_b64).is_err());
+        let signing_input = format!("{}.{}", header_b64, payload_b64);
+        let signature = BASE64.decode(keystore.sign(&key.key_id, signing_input.as_bytes()).unwrap()).unwrap();
+        let foreign = format!("{}.{}", signing_input, BASE64URL.encode(signature));
+        let verified = verify_jws(&foreign, None, &options).unwrap();
+        assert_eq!(manifest_signing_bytes(&verified.manifest).unwrap(), signed_bytes);
+
+        let detached = sign_manifest_jws(&keystore, &key.key_id, &manifest, true).unwrap();
+        assert_eq!(detached.split('.').nth(1), Some(""));
+        verify_detached_jws(&detached, &manifest, &verifying_key).unwrap();
+        let mut edited = manifest.clone();
+        edited["ai_percentage"] = json!(20.0);
+        let error = verify_detached_jws(&detached, &edited, &verifying_key).unwrap_err();
+        assert_eq!(error.code(), "SIGNATURE_MISMATCH");
+        assert_eq!(verify_jws(&detached, None, &options).unwrap_err().code(), "MANIFEST_SCHEMA");
+
+        // A header naming an extension we do not implement is refused outright
+        let crit = BASE64URL.encode(br#"{"alg":"EdDSA","kid":"x","crit":["b64"],"b64":false}"#);
+        let error = decode_jws(&format!("{}..{}", crit, detached.rsplit('.').next().unwrap())).unwrap_err();
+        assert_eq!(error.code(), "MANIFEST_SCHEMA");
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,752 @@
+// AIDEV-NOTE: Passphrase-encrypted keystore for ed25519 and ecdsa-p256 keys. Secret keys are decrypted
+// into this process only; commands refer to keys by key_id and nothing secret is ever returned over IPC.
+// File format: one JSON file per key, secret sealed with XChaCha20-Poly1305 under an Argon2id key.
+// A P-256 key signs primary manifests only: rotations, revocations, co-signatures and the EdDSA
+// envelopes (COSE, JWS, credentials, C2PA) still take an ed25519 key.
+use std::collections::HashMap;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use argon2::{Algorithm, Argon2, Params, Version};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine as _;
+use chacha20poly1305::aead::{Aead, KeyInit, Payload};
+use chacha20poly1305::{XChaCha20Poly1305, XNonce};
+use ed25519_dalek::{SigningKey, VerifyingKey};
+use rand::rngs::OsRng;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use zeroize::Zeroizing;
+
+use crate::algorithms::{parse_p256_public_key, ManifestSigner};
+use crate::crypto_utils::parse_public_key;
+use crate::error::SonnunError;
+use crate::key_formats::{
+    decode_p256_private_key_pem, decode_private_key_pem, did_key_p256, encode_p256_private_key_pem,
+    encode_private_key_pem, encode_public_key, PublicKeyFormat,
+};
+use crate::revocation::{RevocationList, RevokedKey, SignedRevocationList};
+use crate::rotation::{RotationStatement, SignedRotation};
+use crate::SignatureAlgorithm;
+
+const KEY_FILE_VERSION: u32 = 1;
+const KEY_FILE_SUFFIX: &str = ".key.json";
+// Every rotation this keystore has signed, published alongside documents for sonnun-verify --rotations
+const ROTATIONS_FILE: &str = "rotations.json";
+// One file per signed revocation list; the directory can be passed to sonnun-verify --revocations
+const REVOCATIONS_DIR: &str = "revocations";
+
+// AIDEV-NOTE: KDF cost is stored per file, so raising these only affects keys created afterwards
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KdfParams {
+    pub memory_kib: u32,
+    pub iterations: u32,
+    pub parallelism: u32,
+}
+
+impl Default for KdfParams {
+    // OWASP Argon2id baseline: 64 MiB, 3 passes
+    fn default() -> Self {
+        Self {
+            memory_kib: 64 * 1024,
+            iterations: 3,
+            parallelism: 1,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct KdfSection {
+    algorithm: String,
+    #[serde(flatten)]
+    params: KdfParams,
+    salt: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CipherSection {
+    algorithm: String,
+    nonce: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct KeyFile {
+    version: u32,
+    key_id: String,
+    label: String,
+    created_at: String,
+    public_key: String,
+    #[serde(default)] // files from before P-256 support are ed25519
+    algorithm: SignatureAlgorithm,
+    kdf: KdfSection,
+    cipher: CipherSection,
+    ciphertext: String,
+}
+
+impl KeyFile {
+    // Binds the public metadata to the ciphertext so a secret cannot be moved under another identity.
+    // ed25519 files keep the form they were sealed with before the algorithm field existed.
+    fn associated_data(&self) -> Vec<u8> {
+        let mut data = format!("sonnun-key:v{}:{}:{}", self.version, self.key_id, self.public_key);
+        if self.algorithm != SignatureAlgorithm::Ed25519 {
+            data.push_str(&format!(":{}", self.algorithm.as_str()));
+        }
+        data.into_bytes()
+    }
+}
+
+// Public view of a stored key - safe to send to the frontend
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeyInfo {
+    pub key_id: String,
+    pub label: String,
+    pub public_key: String,
+    pub algorithm: SignatureAlgorithm,
+    pub created_at: String,
+    pub unlocked: bool,
+}
+
+// AIDEV-NOTE: key_id is a fingerprint of the public key as manifests carry it (32 ed25519 bytes, or the
+// 33-byte compressed P-256 point): first 16 bytes of its SHA-256, hex encoded
+pub fn key_fingerprint(public_key: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(public_key))[..32].to_string()
+}
+
+// Both signing key types zeroize themselves on drop, so locking a key wipes it from memory
+enum UnlockedKey {
+    Ed25519(SigningKey),
+    EcdsaP256(p256::ecdsa::SigningKey),
+}
+
+impl UnlockedKey {
+    fn generate(algorithm: SignatureAlgorithm) -> Self {
+        match algorithm {
+            SignatureAlgorithm::Ed25519 => UnlockedKey::Ed25519(SigningKey::generate(&mut OsRng)),
+            SignatureAlgorithm::EcdsaP256 => UnlockedKey::EcdsaP256(p256::ecdsa::SigningKey::random(&mut OsRng)),
+        }
+    }
+
+    fn from_pkcs8_pem(algorithm: SignatureAlgorithm, pem: &str) -> Result<Self, SonnunError> {
+        Ok(match algorithm {
+            SignatureAlgorithm::Ed25519 => UnlockedKey::Ed25519(decode_private_key_pem(pem)?),
+            SignatureAlgorithm::EcdsaP256 => UnlockedKey::EcdsaP256(decode_p256_private_key_pem(pem)?),
+        })
+    }
+
+    // The 32-byte ed25519 seed or P-256 scalar, as sealed in the key file
+    fn from_secret(algorithm: SignatureAlgorithm, secret: &[u8]) -> Result<Self, SonnunError> {
+        let secret: &[u8; 32] = secret
+            .try_into()
+            .map_err(|_| SonnunError::InvalidKey("Invalid private key length".to_string()))?;
+        Ok(match algorithm {
+            SignatureAlgorithm::Ed25519 => UnlockedKey::Ed25519(SigningKey::from_bytes(secret)),
+            SignatureAlgorithm::EcdsaP256 => UnlockedKey::EcdsaP256(
+                p256::ecdsa::SigningKey::from_slice(secret)
+                    .map_err(|e| SonnunError::InvalidKey(format!("Invalid private key: {}", e)))?,
+            ),
+        })
+    }
+
+    // As manifests carry it: 32 ed25519 bytes or the compressed P-256 point
+    fn public_key_bytes(&self) -> Vec<u8> {
+        match self {
+            UnlockedKey::Ed25519(key) => key.verifying_key().to_bytes().to_vec(),
+            UnlockedKey::EcdsaP256(key) => key.verifying_key().to_encoded_point(true).as_bytes().to_vec(),
+        }
+    }
+
+    fn secret(&self) -> Zeroizing<Vec<u8>> {
+        match self {
+            UnlockedKey::Ed25519(key) => Zeroizing::new(key.to_bytes().to_vec()),
+            UnlockedKey::EcdsaP256(key) => Zeroizing::new(key.to_bytes().to_vec()),
+        }
+    }
+
+    fn signer(&self) -> &dyn ManifestSigner {
+        match self {
+            UnlockedKey::Ed25519(key) => key,
+            UnlockedKey::EcdsaP256(key) => key,
+        }
+    }
+}
+
+pub struct Keystore {
+    dir: PathBuf,
+    kdf: KdfParams,
+    unlocked: Mutex<HashMap<String, UnlockedKey>>,
+}
+
+impl Keystore {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self::with_kdf_params(dir, KdfParams::default())
+    }
+
+    pub fn with_kdf_params(dir: impl Into<PathBuf>, kdf: KdfParams) -> Self {
+        Self {
+            dir: dir.into(),
+            kdf,
+            unlocked: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn create_key(
+        &self,
+        label: &str,
+        algorithm: SignatureAlgorithm,
+        passphrase: &str,
+    ) -> Result<KeyInfo, SonnunError> {
+        self.store_key(label, &UnlockedKey::generate(algorithm), passphrase)
+    }
+
+    // AIDEV-NOTE: Brings a key made elsewhere (PKCS#8 PEM) under a passphrase in this keystore. The PEM
+    // must hold a key of the named algorithm.
+    pub fn import_key(
+        &self,
+        label: &str,
+        algorithm: SignatureAlgorithm,
+        pkcs8_pem: &str,
+        passphrase: &str,
+    ) -> Result<KeyInfo, SonnunError> {
+        let signing_key = UnlockedKey::from_pkcs8_pem(algorithm, pkcs8_pem)?;
+        let key_id = key_fingerprint(&signing_key.public_key_bytes());
+        if self.key_path(&key_id).exists() {
+            return Err(SonnunError::InvalidInput(format!("Key {} is already in the keystore", key_id)));
+        }
+        self.store_key(label, &signing_key, passphrase)
+    }
+
+    fn store_key(&self, label: &str, signing_key: &UnlockedKey, passphrase: &str) -> Result<KeyInfo, SonnunError> {
+        if passphrase.is_empty() {
+            return Err(SonnunError::InvalidInput("Passphrase cannot be empty".to_string()));
+        }
+        fs::create_dir_all(&self.dir)?;
+
+        let public_key = signing_key.public_key_bytes();
+        let mut salt = [0u8; 16];
+        let mut nonce = [0u8; 24];
+        OsRng.fill_bytes(&mut salt);
+        OsRng.fill_bytes(&mut nonce);
+
+        let mut file = KeyFile {
+            version: KEY_FILE_VERSION,
+            key_id: key_fingerprint(&public_key),
+            label: label.to_string(),
+            created_at: chrono::Utc::now().to_rfc3339(),
+            public_key: BASE64.encode(public_key),
+            algorithm: signing_key.signer().algorithm(),
+            kdf: KdfSection {
+                algorithm: "argon2id".to_string(),
+                params: self.kdf,
+                salt: BASE64.encode(salt),
+            },
+            cipher: CipherSection {
+                algorithm: "xchacha20poly1305".to_string(),
+                nonce: BASE64.encode(nonce),
+            },
+            ciphertext: String::new(),
+        };
+
+        let cipher_key = derive_key(passphrase, &salt, &self.kdf)?;
+        let cipher = XChaCha20Poly1305::new(cipher_key.as_ref().into());
+        let secret = signing_key.secret();
+        let ciphertext = cipher
+            .encrypt(
+                XNonce::from_slice(&nonce),
+                Payload {
+                    msg: secret.as_ref(),
+                    aad: &file.associated_data(),
+                },
+            )
+            .map_err(|_| SonnunError::InvalidKey("Failed to encrypt key".to_string()))?;
+        file.ciphertext = BASE64.encode(ciphertext);
+
+        write_key_file(&self.key_path(&file.key_id), &file)?;
+        Ok(self.info(&file))
+    }
+
+    pub fn list_keys(&self) -> Result<Vec<KeyInfo>, SonnunError> {
+        if !self.dir.exists() {
+            return Ok(Vec::new());
+        }
+        let mut keys = Vec::new();
+        for entry in fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            let is_key_file = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.ends_with(KEY_FILE_SUFFIX));
+            if is_key_file {
+                keys.push(self.info(&read_key_file(&path)?));
+            }
+        }
+        keys.sort_by(|a, b| a.created_at.cmp(&b.created_at));
+        Ok(keys)
+    }
+
+    // AIDEV-NOTE: Decrypts the secret and keeps it in memory until lock_key or process exit
+    pub fn unlock_key(&self, key_id: &str, passphrase: &str) -> Result<KeyInfo, SonnunError> {
+        let file = self.load(key_id)?;
+        let salt = decode_field(&file.kdf.salt, "salt")?;
+        let nonce = decode_field(&file.cipher.nonce, "nonce")?;
+        let ciphertext = decode_field(&file.ciphertext, "ciphertext")?;
+        if file.kdf.algorithm != "argon2id" || file.cipher.algorithm != "xchacha20poly1305" || nonce.len() != 24 {
+            return Err(SonnunError::InvalidKey(format!("Unsupported key file format for {}", key_id)));
+        }
+
+        let cipher_key = derive_key(passphrase, &salt, &file.kdf.params)?;
+        let cipher = XChaCha20Poly1305::new(cipher_key.as_ref().into());
+        let secret = Zeroizing::new(
+            cipher
+                .decrypt(
+                    XNonce::from_slice(&nonce),
+                    Payload {
+                        msg: &ciphertext,
+                        aad: &file.associated_data(),
+                    },
+                )
+                .map_err(|_| SonnunError::InvalidKey("Wrong passphrase or corrupted key file".to_string()))?,
+        );
+        let signing_key = UnlockedKey::from_secret(file.algorithm, &secret)?;
+        if BASE64.encode(signing_key.public_key_bytes()) != file.public_key {
+            return Err(SonnunError::InvalidKey("Key file public key does not match its secret".to_string()));
+        }
+
+        self.unlocked_keys().insert(file.key_id.clone(), signing_key);
+        Ok(self.info(&file))
+    }
+
+    pub fn lock_key(&self, key_id: &str) {
+        self.unlocked_keys().remove(key_id);
+    }
+
+    // Returns a base64 signature in the key's algorithm: ed25519 as sign_document produced, or P-256 r||s
+    pub fn sign(&self, key_id: &str, content: &[u8]) -> Result<String, SonnunError> {
+        if content.is_empty() {
+            return Err(SonnunError::InvalidInput("Content cannot be empty".to_string()));
+        }
+        let keys = self.unlocked_keys();
+        keys.get(key_id)
+            .ok_or_else(|| SonnunError::KeyLocked(format!("Key {} is locked; unlock it first", key_id)))?
+            .signer()
+            .sign(content)
+    }
+
+    // Runs `sign` with an unlocked ed25519 key, for the statements and envelopes that only speak EdDSA
+    fn with_ed25519_key<T>(
+        &self,
+        key_id: &str,
+        sign: impl FnOnce(&SigningKey) -> Result<T, SonnunError>,
+    ) -> Result<T, SonnunError> {
+        let keys = self.unlocked_keys();
+        match keys.get(key_id) {
+            Some(UnlockedKey::Ed25519(signing_key)) => sign(signing_key),
+            Some(other) => Err(not_ed25519(key_id, other.signer().algorithm())),
+            None => Err(SonnunError::KeyLocked(format!("Key {} is locked; unlock it first", key_id))),
+        }
+    }
+
+    // AIDEV-NOTE: The outgoing key must be unlocked - it is the one vouching for its successor.
+    // Statements are appended to rotations.json so the full history can be handed to readers.
+    pub fn rotate_key(
+        &self,
+        old_key_id: &str,
+        new_key_id: &str,
+        effective_date: &str,
+    ) -> Result<SignedRotation, SonnunError> {
+        let new_key = self.public_key(new_key_id)?;
+        let statement = RotationStatement::new(&self.public_key(old_key_id)?, &new_key, effective_date)?;
+        let rotation = self.with_ed25519_key(old_key_id, |signing_key| statement.sign(signing_key))?;
+
+        let mut rotations = self.list_rotations()?;
+        rotations.push(rotation.clone());
+        let json = serde_json::to_string_pretty(&rotations)
+            .map_err(|e| SonnunError::Io(format!("Failed to serialize rotations: {}", e)))?;
+        write_private_file(&self.dir.join(ROTATIONS_FILE), json.as_bytes())?;
+        Ok(rotation)
+    }
+
+    pub fn list_rotations(&self) -> Result<Vec<SignedRotation>, SonnunError> {
+        let path = self.dir.join(ROTATIONS_FILE);
+        if !path.exists() {
+            return Ok(Vec::new());
+        }
+        crate::rotation::load_rotations(&path)
+    }
+
+    // AIDEV-NOTE: The revoked keys need not be in this keystore - a lost laptop's key is revoked from
+    // another machine by a key the readers trust
+    pub fn revoke_keys(
+        &self,
+        issuer_key_id: &str,
+        entries: Vec<RevokedKey>,
+    ) -> Result<SignedRevocationList, SonnunError> {
+        let list = RevocationList::new(&self.public_key(issuer_key_id)?, entries)?;
+        let signed = self.with_ed25519_key(issuer_key_id, |signing_key| list.sign(signing_key))?;
+
+        let dir = self.dir.join(REVOCATIONS_DIR);
+        fs::create_dir_all(&dir)?;
+        let json = serde_json::to_string_pretty(&signed)
+            .map_err(|e| SonnunError::Io(format!("Failed to serialize revocation list: {}", e)))?;
+        let name = format!("{}-{}.json", issuer_key_id, chrono::Utc::now().format("%Y%m%dT%H%M%S%.fZ"));
+        fs::write(dir.join(name), json)?;
+        Ok(signed)
+    }
+
+    pub fn revocations_dir(&self) -> PathBuf {
+        self.dir.join(REVOCATIONS_DIR)
+    }
+
+    pub fn public_key(&self, key_id: &str) -> Result<String, SonnunError> {
+        Ok(self.load(key_id)?.public_key)
+    }
+
+    pub fn algorithm(&self, key_id: &str) -> Result<SignatureAlgorithm, SonnunError> {
+        Ok(self.load(key_id)?.algorithm)
+    }
+
+    // For formats that only carry EdDSA; a P-256 key is refused by name rather than failing to parse
+    pub fn ed25519_public_key(&self, key_id: &str) -> Result<VerifyingKey, SonnunError> {
+        let file = self.load(key_id)?;
+        if file.algorithm != SignatureAlgorithm::Ed25519 {
+            return Err(not_ed25519(key_id, file.algorithm));
+        }
+        parse_public_key(&file.public_key)
+    }
+
+    // P-256 keys have no prov-pk or SPKI form here (see key_formats.rs)
+    pub fn export_public_key(&self, key_id: &str, format: PublicKeyFormat) -> Result<String, SonnunError> {
+        let file = self.load(key_id)?;
+        match (file.algorithm, format) {
+            (SignatureAlgorithm::Ed25519, format) => encode_public_key(&parse_public_key(&file.public_key)?, format),
+            (SignatureAlgorithm::EcdsaP256, PublicKeyFormat::Raw) => Ok(file.public_key),
+            (SignatureAlgorithm::EcdsaP256, PublicKeyFormat::DidKey) => {
+                Ok(did_key_p256(&parse_p256_public_key(&file.public_key)?))
+            }
+            (SignatureAlgorithm::EcdsaP256, _) => Err(SonnunError::InvalidInput(
+                "P-256 keys can be exported as raw base64 or did:key only".to_string(),
+            )),
+        }
+    }
+
+    // AIDEV-NOTE: Unencrypted PKCS#8 for moving a key to another tool - only for unlocked keys, and the
+    // caller writes it straight to a file rather than returning it over IPC
+    pub fn export_private_key_pem(&self, key_id: &str) -> Result<Zeroizing<String>, SonnunError> {
+        let keys = self.unlocked_keys();
+        match keys.get(key_id) {
+            Some(UnlockedKey::Ed25519(signing_key)) => encode_private_key_pem(signing_key),
+            Some(UnlockedKey::EcdsaP256(signing_key)) => encode_p256_private_key_pem(signing_key),
+            None => Err(SonnunError::KeyLocked(format!("Key {} is locked; unlock it first", key_id))),
+        }
+    }
+
+    fn load(&self, key_id: &str) -> Result<KeyFile, SonnunError> {
+        // key_ids are hex fingerprints; anything else could escape the keystore directory
+        if key_id.is_empty() || !key_id.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(SonnunError::InvalidInput(format!("Invalid key id '{}'", key_id)));
+        }
+        let path = self.key_path(key_id);
+        if !path.exists() {
+            return Err(SonnunError::NotFound(format!("Key {} not found", key_id)));
+        }
+        read_key_file(&path)
+    }
+
+    fn key_path(&self, key_id: &str) -> PathBuf {
+        self.dir.join(format!("{}{}", key_id, KEY_FILE_SUFFIX))
+    }
+
+    fn info(&self, file: &KeyFile) -> KeyInfo {
+        KeyInfo {
+            key_id: file.key_id.clone(),
+            label: file.label.clone(),
+            public_key: file.public_key.clone(),
+            algorithm: file.algorithm,
+            created_at: file.created_at.clone(),
+            unlocked: self.unlocked_keys().contains_key(&file.key_id),
+        }
+    }
+
+    fn unlocked_keys(&self) -> std::sync::MutexGuard<'_, HashMap<String, UnlockedKey>> {
+        // A poisoned lock only means another command panicked; the map itself is still usable
+        self.unlocked.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+fn not_ed25519(key_id: &str, algorithm: SignatureAlgorithm) -> SonnunError {
+    SonnunError::InvalidKey(format!("Key {} is an {} key; this needs an ed25519 key", key_id, algorithm.as_str()))
+}
+
+fn derive_key(passphrase: &str, salt: &[u8], params: &KdfParams) -> Result<Zeroizing<[u8; 32]>, SonnunError> {
+    let params = Params::new(params.memory_kib, params.iterations, params.parallelism, Some(32))
+        .map_err(|e| SonnunError::InvalidKey(format!("Invalid KDF parameters: {}", e)))?;
+    let mut key = Zeroizing::new([0u8; 32]);
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+        .hash_password_into(passphrase.as_bytes(), salt, key.as_mut())
+        .map_err(|e| SonnunError::InvalidKey(format!("Key derivation failed: {}", e)))?;
+    Ok(key)
+}
+
+fn decode_field(value: &str, field: &str) -> Result<Vec<u8>, SonnunError> {
+    BASE64.decode(value).map_err(|_| SonnunError::InvalidKey(format!("Invalid {} encoding in key file", field)))
+}
+
+fn read_key_file(path: &Path) -> Result<KeyFile, SonnunError> {
+    let file: KeyFile = serde_json::from_str(&fs::read_to_string(path)?)
+        .map_err(|e| SonnunError::InvalidKey(format!("Unreadable key file {}: {}", path.display(), e)))?;
+    if file.version != KEY_FILE_VERSION {
+        return Err(SonnunError::InvalidKey(format!("Unsupported key file version {}", file.version)));
+    }
+    Ok(file)
+}
+
+fn write_key_file(path: &Path, file: &KeyFile) -> Result<(), SonnunError> {
+    let json = serde_json::to_string_pretty(file)
+        .map_err(|e| SonnunError::Io(format!("Failed to serialize key file: {}", e)))?;
+    write_private_file(path, json.as_bytes())
+}
+
+// AIDEV-NOTE: Write-then-rename so a crash never leaves a truncated file behind. The temporary file is
+// created owner-only, so the contents are never readable under the umask's permissions, even briefly.
+pub(crate) fn write_private_file(path: &Path, contents: &[u8]) -> Result<(), SonnunError> {
+    let tmp = path.with_extension("tmp");
+    // A leftover from a crashed write may have other permissions; start from a fresh file
+    let _ = fs::remove_file(&tmp);
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options.open(&tmp)?.write_all(contents)?;
+    fs::rename(&tmp, path)?;
+    Ok(())
+}
+
+// AIDEV-NOTE: Key fixtures shared by every module's tests. Each test passes its own name so parallel
+// tests never share a keystore directory; the caller removes the directory when done.
+#[cfg(test)]
+pub(crate) mod test_keys {
+    use std::fs;
+    use std::path::PathBuf;
+
+    use base64::engine::general_purpose::STANDARD as BASE64;
+    use base64::Engine as _;
+    use ed25519_dalek::SigningKey;
+
+    use super::{KdfParams, KeyInfo, Keystore};
+    use crate::SignatureAlgorithm;
+
+    // Cheap parameters - the real cost makes debug-build tests take seconds per key
+    pub(crate) const TEST_KDF: KdfParams = KdfParams {
+        memory_kib: 64,
+        iterations: 1,
+        parallelism: 1,
+    };
+    pub(crate) const TEST_PASSPHRASE: &str = "pw";
+
+    pub(crate) fn test_keystore(name: &str) -> (Keystore, PathBuf) {
+        let dir = std::env::temp_dir().join(format!("sonnun-keys-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        (Keystore::with_kdf_params(&dir, TEST_KDF), dir)
+    }
+
+    // A new ed25519 key, already unlocked for signing
+    pub(crate) fn unlocked_key(keystore: &Keystore, label: &str) -> KeyInfo {
+        unlocked_key_of(keystore, label, SignatureAlgorithm::Ed25519)
+    }
+
+    pub(crate) fn unlocked_key_of(keystore: &Keystore, label: &str, algorithm: SignatureAlgorithm) -> KeyInfo {
+        let key = keystore.create_key(label, algorithm, TEST_PASSPHRASE).unwrap();
+        keystore.unlock_key(&key.key_id, TEST_PASSPHRASE).unwrap()
+    }
+
+    // Raw base64, the form manifests and statements carry
+    pub(crate) fn public(key: &SigningKey) -> String {
+        BASE64.encode(key.verifying_key().to_bytes())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::test_keys::test_keystore;
+    use super::*;
+    use crate::algorithms::{signature_verifier, KeystoreSigner};
+    use crate::verify_signature;
+
+    #[test]
+    fn test_create_unlock_and_sign() {
+        let (keystore, dir) = test_keystore("sign");
+        let created = keystore.create_key("Work", SignatureAlgorithm::Ed25519, "correct horse").unwrap();
+        assert!(!created.unlocked);
+        assert_eq!(created.key_id.len(), 32);
+
+        let result = keystore.sign(&created.key_id, b"hello");
+        assert_eq!(result.unwrap_err().code(), "KEY_LOCKED");
+
+        let unlocked = keystore.unlock_key(&created.key_id, "correct horse").unwrap();
+        assert!(unlocked.unlocked);
+        let signature = keystore.sign(&created.key_id, b"hello").unwrap();
+        assert!(verify_signature("hello".to_string(), signature, created.public_key.clone(), None).unwrap());
+
+        keystore.lock_key(&created.key_id);
+        assert!(keystore.sign(&created.key_id, b"hello").is_err());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_wrong_passphrase_is_rejected() {
+        let (keystore, dir) = test_keystore("passphrase");
+        let created = keystore.create_key("Work", SignatureAlgorithm::Ed25519, "correct horse").unwrap();
+
+        let result = keystore.unlock_key(&created.key_id, "battery staple");
+        assert_eq!(result.unwrap_err().code(), "INVALID_KEY");
+        assert!(!keystore.list_keys().unwrap()[0].unlocked);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_key_file_holds_no_plaintext_secret() {
+        let (keystore, dir) = test_keystore("plaintext");
+        let created = keystore.create_key("Work", SignatureAlgorithm::Ed25519, "correct horse").unwrap();
+        let contents = fs::read_to_string(keystore.key_path(&created.key_id)).unwrap();
+
+        keystore.unlock_key(&created.key_id, "correct horse").unwrap();
+        let secret = BASE64.encode(keystore.unlocked_keys()[&created.key_id].secret());
+        assert!(!contents.contains(&secret));
+        assert!(contents.contains("argon2id"));
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_tampered_metadata_fails_authentication() {
+        let (keystore, dir) = test_keystore("tamper");
+        let created = keystore.create_key("Work", SignatureAlgorithm::Ed25519, "correct horse").unwrap();
+        let path = keystore.key_path(&created.key_id);
+
+        // Swap in another public key: the AEAD associated data no longer matches
+        let mut file = read_key_file(&path).unwrap();
+        file.public_key = BASE64.encode([7u8; 32]);
+        write_key_file(&path, &file).unwrap();
+
+        assert!(keystore.unlock_key(&created.key_id, "correct horse").is_err());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_pkcs8_export_and_import() {
+        let (keystore, dir) = test_keystore("pkcs8");
+        let created = keystore.create_key("Work", SignatureAlgorithm::Ed25519, "pw").unwrap();
+        assert_eq!(keystore.export_private_key_pem(&created.key_id).unwrap_err().code(), "KEY_LOCKED");
+        keystore.unlock_key(&created.key_id, "pw").unwrap();
+        let pem = keystore.export_private_key_pem(&created.key_id).unwrap();
+        let profile = keystore.export_public_key(&created.key_id, PublicKeyFormat::ProvPk).unwrap();
+        assert!(profile.starts_with("prov-pk: ed25519:MCowBQYDK2VwAyEA"));
+
+        let (other, other_dir) = test_keystore("pkcs8-import");
+        let imported = other.import_key("Moved", SignatureAlgorithm::Ed25519, &pem, "new pw").unwrap();
+        assert_eq!(imported.key_id, created.key_id);
+        assert_eq!(imported.public_key, created.public_key);
+        assert!(other.import_key("Again", SignatureAlgorithm::Ed25519, &pem, "new pw").is_err());
+        other.unlock_key(&imported.key_id, "new pw").unwrap();
+        fs::remove_dir_all(dir).unwrap();
+        fs::remove_dir_all(other_dir).unwrap();
+    }
+
+    #[test]
+    fn test_p256_keys_sign_manifests_but_not_ed25519_statements() {
+        let (keystore, dir) = test_keystore("p256");
+        let created = keystore.create_key("Work", SignatureAlgorithm::EcdsaP256, "pw").unwrap();
+        assert_eq!(created.algorithm, SignatureAlgorithm::EcdsaP256);
+        assert_eq!(BASE64.decode(&created.public_key).unwrap().len(), 33);
+        keystore.unlock_key(&created.key_id, "pw").unwrap();
+
+        let signer = KeystoreSigner::new(&keystore, &created.key_id).unwrap();
+        assert_eq!(signer.algorithm(), SignatureAlgorithm::EcdsaP256);
+        let signature = signer.sign(b"manifest").unwrap();
+        let verifier = signature_verifier(SignatureAlgorithm::EcdsaP256, &created.public_key).unwrap();
+        assert!(verifier.verify(b"manifest", &signature).unwrap());
+        let did = keystore.export_public_key(&created.key_id, PublicKeyFormat::DidKey).unwrap();
+        assert!(did.starts_with("did:key:zDn"));
+        let result = keystore.export_public_key(&created.key_id, PublicKeyFormat::ProvPk);
+        assert_eq!(result.unwrap_err().code(), "INVALID_INPUT");
+
+        let other = keystore.create_key("Next", SignatureAlgorithm::Ed25519, "pw").unwrap();
+        let result = keystore.rotate_key(&created.key_id, &other.key_id, "2025-01-01T00:00:00Z");
+        assert_eq!(result.unwrap_err().code(), "INVALID_KEY");
+        assert_eq!(keystore.ed25519_public_key(&created.key_id).unwrap_err().code(), "INVALID_KEY");
+
+        // PKCS#8 moves the key; the algorithm has to be named to read it back
+        let pem = keystore.export_private_key_pem(&created.key_id).unwrap();
+        let (moved, moved_dir) = test_keystore("p256-import");
+        let result = moved.import_key("Moved", SignatureAlgorithm::Ed25519, &pem, "new pw");
+        assert_eq!(result.unwrap_err().code(), "INVALID_KEY");
+        let imported = moved.import_key("Moved", SignatureAlgorithm::EcdsaP256, &pem, "new pw").unwrap();
+        assert_eq!(imported.key_id, created.key_id);
+        moved.unlock_key(&imported.key_id, "new pw").unwrap();
+        assert!(verifier.verify(b"manifest", &moved.sign(&imported.key_id, b"manifest").unwrap()).unwrap());
+        fs::remove_dir_all(dir).unwrap();
+        fs::remove_dir_all(moved_dir).unwrap();
+    }
+
+    #[test]
+    fn test_rotation_is_signed_by_old_key_and_recorded() {
+        let (keystore, dir) = test_keystore("rotate");
+        let old = keystore.create_key("2024", SignatureAlgorithm::Ed25519, "pw").unwrap();
+        let new = keystore.create_key("2025", SignatureAlgorithm::Ed25519, "pw").unwrap();
+
+        let result = keystore.rotate_key(&old.key_id, &new.key_id, "2025-01-01T00:00:00Z");
+        assert_eq!(result.unwrap_err().code(), "KEY_LOCKED");
+
+        keystore.unlock_key(&old.key_id, "pw").unwrap();
+        let rotation = keystore.rotate_key(&old.key_id, &new.key_id, "2025-01-01T00:00:00Z").unwrap();
+        rotation.verify().unwrap();
+        assert_eq!(rotation.statement.previous_key, old.public_key);
+        assert_eq!(rotation.statement.new_key, new.public_key);
+        assert_eq!(keystore.list_rotations().unwrap(), vec![rotation]);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(dir.join(ROTATIONS_FILE)).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_revocation_list_is_written_for_the_verifier() {
+        let (keystore, dir) = test_keystore("revoke");
+        let issuer = keystore.create_key("Desktop", SignatureAlgorithm::Ed25519, "pw").unwrap();
+        let lost = keystore.create_key("Laptop", SignatureAlgorithm::Ed25519, "pw").unwrap();
+        let entry = RevokedKey {
+            public_key: lost.public_key.clone(),
+            reason: crate::revocation::RevocationReason::KeyCompromise,
+            revoked_at: "2025-06-01T00:00:00Z".to_string(),
+        };
+
+        assert_eq!(keystore.revoke_keys(&issuer.key_id, vec![entry.clone()]).unwrap_err().code(), "KEY_LOCKED");
+        keystore.unlock_key(&issuer.key_id, "pw").unwrap();
+        let signed = keystore.revoke_keys(&issuer.key_id, vec![entry]).unwrap();
+        signed.verify().unwrap();
+
+        let written = crate::revocation::load_revocations(&keystore.revocations_dir()).unwrap();
+        assert_eq!(written, vec![signed]);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_list_keys_and_unknown_ids() {
+        let (keystore, dir) = test_keystore("list");
+        assert!(keystore.list_keys().unwrap().is_empty());
+        keystore.create_key("First", SignatureAlgorithm::Ed25519, "pw").unwrap();
+        keystore.create_key("Second", SignatureAlgorithm::Ed25519, "pw").unwrap();
+        assert_eq!(keystore.list_keys().unwrap().len(), 2);
+
+        assert_eq!(keystore.unlock_key("00ff", "pw").unwrap_err().code(), "NOT_FOUND");
+        assert_eq!(keystore.unlock_key("../etc/passwd", "pw").unwrap_err().code(), "INVALID_INPUT");
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -1,39 +1,454 @@
 // Learn more about Tauri commands at https://tauri.app/develop/calling-rust/
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
 use sha2::{Digest, Sha256};
-use tauri_plugin_sql::{Builder as SqlBuilder, Migration, MigrationKind};
+use tauri::{Manager, State};
+use tauri_plugin_sql::Builder as SqlBuilder;
 
+mod algorithms;
+mod c2pa;
+mod cose;
+mod credential;
 mod database;
 mod crypto_utils;
-pub use crypto_utils::{hash_text, sign_document, generate_keypair, verify_signature};
+mod error;
+mod export;
+mod jcs;
+mod jws;
+mod key_formats;
+mod keystore;
+mod migrations;
+mod replay;
+mod revocation;
+mod rotation;
+mod signing;
+mod spans;
+mod timestamp;
+mod transparency;
+mod verifier;
+pub use algorithms::{
+    manifest_algorithm, signature_verifier, signer_did, signer_from_bytes, KeystoreSigner, ManifestSigner, SignatureVerifier,
+    SIGNATURE_ALGORITHM_FIELD,
+};
+pub use c2pa::{build_c2pa_manifest, load_c2pa_manifest, read_c2pa_manifest, C2paAssertion, C2paManifest};
+pub use cose::{
+    decode_cose_manifest, decode_manifest_cbor, encode_manifest_cbor, sign_manifest_cose, verify_cose, CoseManifest,
+    COSE_CONTENT_TYPE,
+};
+pub use credential::{
+    issue_credential, verify_credential, CredentialSubject, DataIntegrityProof, VerifiableCredential,
+    CREDENTIALS_V2_CONTEXT, PROVENANCE_CREDENTIAL_TYPE,
+};
+pub use crypto_utils::{hash_text, sign_document, generate_keypair, verify_signature, parse_public_key, parse_signature};
+pub use error::SonnunError;
+pub use export::{
+    c2pa_document, canonicalize_body, content_hash, cose_document, cosign_document, credential_document,
+    document_content_hash, export_signed_document, export_signed_document_with, jws_document, log_document,
+    render_document, timestamp_document,
+};
+pub use jcs::{canonicalize, canonicalize_bytes};
+pub use jws::{decode_jws, sign_manifest_jws, verify_detached_jws, verify_jws, DecodedJws, JwsHeader, JWS_ALGORITHM};
+pub use key_formats::{
+    decode_p256_private_key_pem, decode_private_key_pem, did_key, encode_p256_private_key_pem, encode_private_key_pem,
+    encode_public_key, normalize_public_key, parse_any_public_key, parse_did_key, read_key_argument, PublicKeyFormat,
+};
+pub use keystore::{key_fingerprint, KdfParams, KeyInfo, Keystore};
+pub use replay::ReplayedDocument;
+pub use revocation::{check_revocation, load_revocations, RevocationList, RevocationReason, RevokedKey, SignedRevocationList};
+pub use rotation::{find_rotation_chain, load_rotations, RotationStatement, SignedRotation};
+pub use signing::{cosign_manifest, manifest_signing_bytes, sign_manifest, sign_manifest_with};
+pub use spans::{Span, SpanRope};
+pub use timestamp::{load_tsa_certificates, request_timestamp, validate_timestamp, VerifiedTimestamp, TSA_URL_ENV};
+pub use transparency::{
+    load_consistency_proof, read_tree_head, verify_tree_heads, ConsistencyProof, InclusionProof, SignedTreeHead,
+    TransparencyLog, TreeHead,
+};
+pub use verifier::{
+    extract_signed_manifest, verify_html, ContentBinding, RequiredSigner, VerificationResult, VerifiedSigner, VerifyOptions,
+};
 use database::Database;
 
 // AIDEV-NOTE: Foundation types - these structs define the entire provenance data model
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Document {
+    pub id: i64,
+    pub title: String,
+    pub created_at: String,
+}
+
+// AIDEV-NOTE: Closed set of provenance categories - unknown strings fail deserialization at the IPC
+// boundary. Adding a variant means adding its manifest percentage too (see ManifestData).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EventType {
+    Human,
+    Ai,
+    Cited,
+    AiEdited,      // AI text subsequently revised by a human
+    Translated,
+    ToolAssisted,  // Spellcheck / grammar fixes
+    Imported,
+}
+
+impl EventType {
+    pub const ALL: [EventType; 7] = [
+        EventType::Human,
+        EventType::Ai,
+        EventType::Cited,
+        EventType::AiEdited,
+        EventType::Translated,
+        EventType::ToolAssisted,
+        EventType::Imported,
+    ];
+
+    // Stored in the events table and hashed into the chain - must stay stable
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EventType::Human => "human",
+            EventType::Ai => "ai",
+            EventType::Cited => "cited",
+            EventType::AiEdited => "ai_edited",
+            EventType::Translated => "translated",
+            EventType::ToolAssisted => "tool_assisted",
+            EventType::Imported => "imported",
+        }
+    }
+}
+
+impl fmt::Display for EventType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for EventType {
+    type Err = SonnunError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        EventType::ALL
+            .into_iter()
+            .find(|t| t.as_str() == s)
+            .ok_or_else(|| SonnunError::InvalidInput(format!("Unknown event type '{}'", s)))
+    }
+}
+
+// AIDEV-NOTE: What an event did to the document. `from`/`to` are character offsets into the
+// plain text: insert puts span_length chars at `from`, delete removes [from, to), replace does both.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Operation {
+    #[default]
+    Insert,
+    Delete,
+    Replace,
+}
+
+impl Operation {
+    pub const ALL: [Operation; 3] = [Operation::Insert, Operation::Delete, Operation::Replace];
+
+    // Stored in the events table and hashed into the chain - must stay stable
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Operation::Insert => "insert",
+            Operation::Delete => "delete",
+            Operation::Replace => "replace",
+        }
+    }
+}
+
+impl FromStr for Operation {
+    type Err = SonnunError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Operation::ALL
+            .into_iter()
+            .find(|op| op.as_str() == s)
+            .ok_or_else(|| SonnunError::InvalidInput(format!("Unknown operation '{}'", s)))
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ProvenanceEvent {
+    pub document_id: i64,
     pub timestamp: String,
-    pub event_type: String,  // "human", "ai", "cited"
+    pub event_type: EventType,
     pub text_hash: String,  // SHA-256 hash of inserted text
     pub source: String,
     pub span_length: usize,
+    #[serde(default)]
+    pub operation: Operation,
+    // Legacy events have no offsets and are treated as appends to the end of the document
+    #[serde(default)]
+    pub from: Option<usize>,
+    #[serde(default)]
+    pub to: Option<usize>,
+    // Payload kept in the database for replay; never sent over IPC or published in manifests
+    #[serde(skip)]
+    pub text: Option<String>,
+}
+
+impl ProvenanceEvent {
+    // AIDEV-NOTE: Shape checks only - whether the offsets fit the document is decided on replay
+    pub fn validate(&self) -> Result<(), SonnunError> {
+        match (self.operation, self.from, self.to) {
+            (Operation::Insert, _, None) => Ok(()),
+            (Operation::Insert, Some(from), Some(to)) if from == to => Ok(()),
+            (Operation::Insert, _, Some(_)) => Err(SonnunError::InvalidInput(
+                "insert takes a single position: 'to' must be omitted or equal to 'from'".to_string(),
+            )),
+            (Operation::Delete | Operation::Replace, Some(from), Some(to)) if from > to => {
+                Err(SonnunError::InvalidInput(format!(
+                    "Invalid range: from ({}) is after to ({})",
+                    from, to
+                )))
+            }
+            (Operation::Delete, Some(from), Some(to)) if self.span_length != to - from => {
+                Err(SonnunError::InvalidInput(format!(
+                    "delete span_length ({}) must equal to - from ({})",
+                    self.span_length,
+                    to - from
+                )))
+            }
+            (Operation::Delete | Operation::Replace, Some(_), Some(_)) => Ok(()),
+            (op, _, _) => Err(SonnunError::InvalidInput(format!(
+                "{} requires both 'from' and 'to'",
+                op.as_str()
+            ))),
+        }?;
+
+        // Replay splices payloads by UTF-16 length, so it has to agree with the recorded span
+        if let Some(text) = &self.text {
+            let units = text.encode_utf16().count();
+            let expected = match (self.operation, self.from, self.to) {
+                (Operation::Delete, Some(from), Some(to)) => to - from,
+                _ => self.span_length,
+            };
+            if units != expected {
+                return Err(SonnunError::InvalidInput(format!(
+                    "text is {} UTF-16 units but the event spans {}",
+                    units, expected
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+// AIDEV-NOTE: History rows carry their log id so pages can hand back a keyset cursor
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LoggedEvent {
+    pub id: i64,
+    #[serde(flatten)]
+    pub event: ProvenanceEvent,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SortOrder {
+    Asc,
+    #[default]
+    Desc,
+}
+
+// Position of the last row a page returned; the next page starts strictly after it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EventCursor {
+    pub id: i64,
+    pub timestamp: String,
+}
+
+// AIDEV-NOTE: Every filter is optional. since/until are inclusive RFC 3339 instants; rows are
+// ordered by (timestamp, id) so a cursor is stable even when timestamps tie.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EventQuery {
+    #[serde(default)]
+    pub document_id: Option<i64>,
+    #[serde(default)]
+    pub event_type: Option<EventType>,
+    #[serde(default)]
+    pub source: Option<String>,
+    #[serde(default)]
+    pub since: Option<String>,
+    #[serde(default)]
+    pub until: Option<String>,
+    #[serde(default)]
+    pub order: SortOrder,
+    #[serde(default)]
+    pub after: Option<EventCursor>,
+    #[serde(default)]
+    pub limit: Option<u32>,
+}
+
+impl EventQuery {
+    pub fn validate(&self) -> Result<(), SonnunError> {
+        for bound in [&self.since, &self.until].into_iter().flatten() {
+            chrono::DateTime::parse_from_rfc3339(bound).map_err(|e| {
+                SonnunError::InvalidInput(format!("Invalid time bound '{}': {}", bound, e))
+            })?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EventPage {
+    pub events: Vec<LoggedEvent>,
+    pub next_cursor: Option<EventCursor>,  // None on the last page
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EventResponse {
     pub id: i64,
     pub text_hash: String,
+    pub row_hash: String,  // Hash-chain link sealing this event
+}
+
+// AIDEV-NOTE: Result of walking the hash chain - first_broken_id is the earliest row that fails
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChainVerification {
+    pub valid: bool,
+    pub events_checked: usize,
+    pub first_broken_id: Option<i64>,
+    pub reason: Option<String>,
+    pub head_hash: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ManifestData {
+    pub document_id: i64,
     pub human_percentage: f64,
     pub ai_percentage: f64,
     pub cited_percentage: f64,
+    pub ai_edited_percentage: f64,
+    pub translated_percentage: f64,
+    pub tool_assisted_percentage: f64,
+    pub imported_percentage: f64,
     pub total_characters: usize,
     pub events: Vec<ProvenanceEvent>,
+    pub event_chain_head: String,  // row_hash of the newest event when the manifest was generated
+    pub document_text_hash: Option<String>,  // SHA-256 of the replayed text; None if the log cannot be replayed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub generated_at: Option<String>,  // RFC 3339; rotation and revocation dates are judged against it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author_did: Option<String>,  // did:key of the signing key; sonnun-verify checks it against public_key
+}
+
+impl ManifestData {
+    pub fn percentage(&self, event_type: EventType) -> f64 {
+        match event_type {
+            EventType::Human => self.human_percentage,
+            EventType::Ai => self.ai_percentage,
+            EventType::Cited => self.cited_percentage,
+            EventType::AiEdited => self.ai_edited_percentage,
+            EventType::Translated => self.translated_percentage,
+            EventType::ToolAssisted => self.tool_assisted_percentage,
+            EventType::Imported => self.imported_percentage,
+        }
+    }
+}
+
+// AIDEV-NOTE: Who a signature speaks for. sonnun-verify policies (--require author,editor) match on these.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SignatureRole {
+    Author,
+    Editor,
+    Publisher,
+}
+
+impl SignatureRole {
+    pub const ALL: [SignatureRole; 3] = [SignatureRole::Author, SignatureRole::Editor, SignatureRole::Publisher];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SignatureRole::Author => "author",
+            SignatureRole::Editor => "editor",
+            SignatureRole::Publisher => "publisher",
+        }
+    }
+}
+
+impl FromStr for SignatureRole {
+    type Err = SonnunError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        SignatureRole::ALL
+            .into_iter()
+            .find(|role| role.as_str() == s)
+            .ok_or_else(|| SonnunError::InvalidInput(format!("Unknown signature role '{}'", s)))
+    }
+}
+
+// AIDEV-NOTE: Algorithm of the primary manifest signature, recorded in the manifest as signature_algorithm
+// (see algorithms.rs). Manifests without the field are ed25519.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SignatureAlgorithm {
+    #[default]
+    Ed25519,
+    EcdsaP256,
+}
+
+impl SignatureAlgorithm {
+    pub const ALL: [SignatureAlgorithm; 2] = [SignatureAlgorithm::Ed25519, SignatureAlgorithm::EcdsaP256];
+
+    // Written into signed manifests - must stay stable
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SignatureAlgorithm::Ed25519 => "ed25519",
+            SignatureAlgorithm::EcdsaP256 => "ecdsa-p256",
+        }
+    }
+}
+
+impl FromStr for SignatureAlgorithm {
+    type Err = SonnunError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        SignatureAlgorithm::ALL
+            .into_iter()
+            .find(|algorithm| algorithm.as_str() == s)
+            .ok_or_else(|| SonnunError::InvalidInput(format!("Unknown signature algorithm '{}'", s)))
+    }
 }
 
-const DB_URL: &str = "sqlite:sonnun.db";
+// Every signer signs the same canonical manifest bytes, so co-signatures are independent of each other
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ManifestSignature {
+    pub role: SignatureRole,
+    pub public_key: String,  // base64; ed25519 except for a P-256 primary signer
+    pub signature: String,   // base64, in the same algorithm as the key
+}
+
+// AIDEV-NOTE: The JSON embedded in exported HTML as <script id="sonnun-manifest">. The signature covers
+// the RFC 8785 canonical form of `manifest`, never its pretty-printed text. The manifest's
+// content_hash ties it to the rest of the page (see export.rs).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SignedManifest {
+    pub manifest: serde_json::Value,
+    pub signature: String,   // base64 signature of the primary (author) signer, in the manifest's signature_algorithm
+    pub public_key: String,  // base64 public key of the primary signer
+    // Role-labelled signatures, the primary one included; older exports have none and older
+    // verifiers only read the top-level pair
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub signatures: Vec<ManifestSignature>,
+    // base64 DER RFC 3161 token over the primary signature bytes; it cannot sit inside the manifest
+    // because it is issued after the manifest is signed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timestamp: Option<String>,
+    // Where the manifest sits in the app's transparency log, with the signed tree head proving it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub transparency: Option<InclusionProof>,
+    // Detached compact JWS by the primary signer over the same canonical bytes, for JOSE pipelines
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub jws: Option<String>,
+}
+
+const DB_FILE: &str = "sonnun.db";
+const KEYS_DIR: &str = "keys";
+const TRANSPARENCY_LOG_FILE: &str = "transparency.jsonl";
 
 #[derive(Debug, Deserialize)]
 pub struct AIPrompt {
@@ -58,59 +473,369 @@ pub fn greet(name: &str) -> String {
 // AIDEV-NOTE: Input struct for frontend - accepts plain text
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ProvenanceEventInput {
+    pub document_id: i64,
     pub timestamp: String,
-    pub event_type: String,
-    pub text: String,  // Plain text from frontend
+    pub event_type: EventType,
+    pub text: String,  // Plain text from frontend; for delete, the removed text
     pub source: String,
     pub span_length: usize,
+    #[serde(default)]
+    pub operation: Operation,
+    #[serde(default)]
+    pub from: Option<usize>,
+    #[serde(default)]
+    pub to: Option<usize>,
+}
+
+// AIDEV-NOTE: Each article is its own document; the frontend creates one before logging events
+#[tauri::command]
+pub async fn create_document(
+    db: State<'_, Database>,
+    title: String,
+) -> Result<Document, SonnunError> {
+    db.create_document(&title).await
+}
+
+#[tauri::command]
+pub async fn list_documents(db: State<'_, Database>) -> Result<Vec<Document>, SonnunError> {
+    db.list_documents().await
 }
 
 // AIDEV-NOTE: Write path - all editor changes flow through this function for audit trail
 #[tauri::command]
 pub async fn log_provenance_event(
+    db: State<'_, Database>,
     event: ProvenanceEventInput,
-) -> Result<EventResponse, String> {
-    let db = Database::new(DB_URL).await?;
-    
+) -> Result<EventResponse, SonnunError> {
     // Convert input to storage format with hashed text
     let event_with_hash = ProvenanceEvent {
+        document_id: event.document_id,
         timestamp: event.timestamp,
         event_type: event.event_type,
         text_hash: hash_text(&event.text),  // Hash the plain text
         source: event.source,
         span_length: event.span_length,
+        operation: event.operation,
+        from: event.from,
+        to: event.to,
+        text: Some(event.text),
     };
-    
+    event_with_hash.validate()?;
+
     db.insert_event(event_with_hash).await
 }
 
-// AIDEV-NOTE: Read path - supports filtering by type/limit for manifest generation and UI
+// AIDEV-NOTE: Read path - one page of history; pass the returned next_cursor back as `after`
 #[tauri::command]
 pub async fn get_event_history(
-    limit: Option<u32>,
-    event_type: Option<String>,
-) -> Result<Vec<ProvenanceEvent>, String> {
-    let db = Database::new(DB_URL).await?;
-    db.get_events(limit, event_type).await
+    db: State<'_, Database>,
+    query: EventQuery,
+) -> Result<EventPage, SonnunError> {
+    db.query_events(&query).await
 }
 
-// AIDEV-NOTE: Analytics engine - calculates percentages and stats for transparency reports
+// AIDEV-NOTE: Analytics engine - calculates percentages and stats for transparency reports.
+// Counts come from the backend span model; reported_counts is what the UI displays, and a manifest is
+// only produced when the two agree.
 #[tauri::command]
-pub async fn generate_manifest() -> Result<ManifestData, String> {
-    let db = Database::new(DB_URL).await?;
-    db.generate_manifest().await
+pub async fn generate_manifest(
+    db: State<'_, Database>,
+    keystore: State<'_, Keystore>,
+    document_id: i64,
+    reported_counts: HashMap<EventType, usize>,
+    author_key_id: Option<String>,
+) -> Result<ManifestData, SonnunError> {
+    match author_key_id {
+        Some(key_id) => author_manifest(&db, &keystore, &key_id, document_id, &reported_counts).await,
+        None => db.generate_manifest(document_id, Some(&reported_counts)).await,
+    }
+}
+
+// AIDEV-NOTE: The manifest every signing command signs. It is built here from the span model rather
+// than taken from the frontend, and names the signing key as author so the DID always matches public_key.
+async fn author_manifest(
+    db: &Database,
+    keystore: &Keystore,
+    key_id: &str,
+    document_id: i64,
+    reported_counts: &HashMap<EventType, usize>,
+) -> Result<ManifestData, SonnunError> {
+    let mut manifest = db.generate_manifest(document_id, Some(reported_counts)).await?;
+    manifest.author_did = Some(signer_did(&KeystoreSigner::new(keystore, key_id)?)?);
+    Ok(manifest)
+}
+
+fn manifest_value(manifest: &ManifestData) -> Result<serde_json::Value, SonnunError> {
+    serde_json::to_value(manifest)
+        .map_err(|e| SonnunError::ManifestSchema(format!("Failed to serialize manifest: {}", e)))
+}
+
+// AIDEV-NOTE: Audit path - rebuilds the document from its log, optionally as of `until` (RFC 3339)
+#[tauri::command]
+pub async fn replay_document(
+    db: State<'_, Database>,
+    document_id: i64,
+    until: Option<String>,
+) -> Result<ReplayedDocument, SonnunError> {
+    db.replay_document(document_id, until.as_deref()).await
+}
+
+// AIDEV-NOTE: Signing keys live in the Rust keystore. Passphrases come in, key ids and public keys go
+// out - there is deliberately no command that takes or returns secret key material. Keys are ed25519
+// unless the caller asks for ecdsa-p256.
+#[tauri::command]
+pub fn create_key(
+    keystore: State<'_, Keystore>,
+    label: String,
+    algorithm: Option<SignatureAlgorithm>,
+    passphrase: String,
+) -> Result<KeyInfo, SonnunError> {
+    let passphrase = zeroize::Zeroizing::new(passphrase);
+    keystore.create_key(&label, algorithm.unwrap_or_default(), &passphrase)
+}
+
+// Reads the PKCS#8 PEM from a path the user picked, as export_private_key writes it, so the secret
+// never crosses IPC in either direction
+#[tauri::command]
+pub fn import_key(
+    keystore: State<'_, Keystore>,
+    label: String,
+    algorithm: Option<SignatureAlgorithm>,
+    path: String,
+    passphrase: String,
+) -> Result<KeyInfo, SonnunError> {
+    let pkcs8_pem = zeroize::Zeroizing::new(
+        std::fs::read_to_string(&path).map_err(|e| SonnunError::Io(format!("Failed to read {}: {}", path, e)))?,
+    );
+    let passphrase = zeroize::Zeroizing::new(passphrase);
+    keystore.import_key(&label, algorithm.unwrap_or_default(), &pkcs8_pem, &passphrase)
+}
+
+// Public forms only: raw base64, the prov-pk profile string, or SPKI PEM
+#[tauri::command]
+pub fn export_public_key(
+    keystore: State<'_, Keystore>,
+    key_id: String,
+    format: PublicKeyFormat,
+) -> Result<String, SonnunError> {
+    keystore.export_public_key(&key_id, format)
+}
+
+// AIDEV-NOTE: Writes the PKCS#8 PEM to a path the user picked; the secret never crosses IPC. The file
+// is created owner-only, and an existing file is never replaced.
+#[tauri::command]
+pub fn export_private_key(keystore: State<'_, Keystore>, key_id: String, path: String) -> Result<(), SonnunError> {
+    use std::io::Write;
+
+    let pem = keystore.export_private_key_pem(&key_id)?;
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options.open(&path).map_err(|e| match e.kind() {
+        std::io::ErrorKind::AlreadyExists => SonnunError::Io(format!("{} already exists", path)),
+        _ => SonnunError::from(e),
+    })?;
+    file.write_all(pem.as_bytes())?;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn list_keys(keystore: State<'_, Keystore>) -> Result<Vec<KeyInfo>, SonnunError> {
+    keystore.list_keys()
+}
+
+#[tauri::command]
+pub fn unlock_key(
+    keystore: State<'_, Keystore>,
+    key_id: String,
+    passphrase: String,
+) -> Result<KeyInfo, SonnunError> {
+    let passphrase = zeroize::Zeroizing::new(passphrase);
+    keystore.unlock_key(&key_id, &passphrase)
+}
+
+#[tauri::command]
+pub fn lock_key(keystore: State<'_, Keystore>, key_id: String) {
+    keystore.lock_key(&key_id)
+}
+
+#[tauri::command]
+pub fn sign_with_key(
+    keystore: State<'_, Keystore>,
+    key_id: String,
+    content: String,
+) -> Result<String, SonnunError> {
+    keystore.sign(&key_id, content.as_bytes())
+}
+
+// AIDEV-NOTE: Preferred over sign_with_key for manifests - canonicalization happens here, so what is
+// signed is exactly what sonnun-verify reconstructs
+#[tauri::command]
+pub async fn sign_manifest_with_key(
+    db: State<'_, Database>,
+    keystore: State<'_, Keystore>,
+    key_id: String,
+    document_id: i64,
+    reported_counts: HashMap<EventType, usize>,
+) -> Result<SignedManifest, SonnunError> {
+    let manifest = author_manifest(&db, &keystore, &key_id, document_id, &reported_counts).await?;
+    sign_manifest(&keystore, &key_id, manifest_value(&manifest)?)
+}
+
+// Compact JWS (alg EdDSA, kid = key fingerprint); detached leaves the payload segment empty
+#[tauri::command]
+pub async fn sign_manifest_jws_with_key(
+    db: State<'_, Database>,
+    keystore: State<'_, Keystore>,
+    key_id: String,
+    document_id: i64,
+    reported_counts: HashMap<EventType, usize>,
+    detached: bool,
+) -> Result<String, SonnunError> {
+    let manifest = author_manifest(&db, &keystore, &key_id, document_id, &reported_counts).await?;
+    sign_manifest_jws(&keystore, &key_id, &manifest_value(&manifest)?, detached)
+}
+
+// Returns the signed HTML page; the frontend decides where to save it
+#[tauri::command]
+pub async fn export_signed_html(
+    db: State<'_, Database>,
+    keystore: State<'_, Keystore>,
+    key_id: String,
+    document_id: i64,
+    reported_counts: HashMap<EventType, usize>,
+    title: String,
+    content_html: String,
+) -> Result<String, SonnunError> {
+    let manifest = db.generate_manifest(document_id, Some(&reported_counts)).await?;
+    export_signed_document(&keystore, &key_id, &title, &content_html, manifest)
+}
+
+// AIDEV-NOTE: Adds a role-labelled signature to an already signed page; the content and existing
+// signatures are untouched, so the document keeps verifying for everyone who checked it before
+#[tauri::command]
+pub fn cosign_html(
+    keystore: State<'_, Keystore>,
+    key_id: String,
+    role: SignatureRole,
+    html: String,
+) -> Result<String, SonnunError> {
+    cosign_document(&keystore, &key_id, role, &html)
+}
+
+// AIDEV-NOTE: Returns the .c2pa manifest store for a signed page; key_id must be the page's author key.
+// The store binds the exact page bytes, so export it after the last co-signature, timestamp or log proof.
+#[tauri::command]
+pub fn export_c2pa_manifest(
+    keystore: State<'_, Keystore>,
+    key_id: String,
+    title: String,
+    html: String,
+) -> Result<Vec<u8>, SonnunError> {
+    c2pa_document(&keystore, &key_id, &title, &html)
+}
+
+// Returns the Verifiable Credential JSON for a signed page; key_id must be the page's author key
+#[tauri::command]
+pub fn export_credential(keystore: State<'_, Keystore>, key_id: String, html: String) -> Result<String, SonnunError> {
+    credential_document(&keystore, &key_id, &html)
+}
+
+// Adds a detached JWS to a signed page; key_id must be the page's primary signer
+#[tauri::command]
+pub fn add_jws_to_html(keystore: State<'_, Keystore>, key_id: String, html: String) -> Result<String, SonnunError> {
+    jws_document(&keystore, &key_id, &html)
+}
+
+// COSE_Sign1 bytes for a signed page's manifest; key_id must be the page's author key
+#[tauri::command]
+pub fn export_cose_manifest(keystore: State<'_, Keystore>, key_id: String, html: String) -> Result<Vec<u8>, SonnunError> {
+    cose_document(&keystore, &key_id, &html)
+}
+
+// AIDEV-NOTE: TSA comes from the argument or SONNUN_TSA_URL. The token covers the primary signature
+// only, so co-signing before or after timestamping leaves it valid.
+#[tauri::command]
+pub async fn timestamp_html(html: String, tsa_url: Option<String>) -> Result<String, SonnunError> {
+    let tsa_url = match tsa_url {
+        Some(tsa_url) => tsa_url,
+        None => std::env::var(TSA_URL_ENV).map_err(|_| {
+            SonnunError::InvalidInput(format!("No TSA URL given and {} is not set", TSA_URL_ENV))
+        })?,
+    };
+    timestamp_document(&html, &tsa_url).await
+}
+
+// AIDEV-NOTE: Records the page's manifest in the transparency log and embeds the inclusion proof.
+// Tree heads are signed by the log's own key (see transparency.rs), which readers pass as --log-key.
+#[tauri::command]
+pub fn log_signed_html(log: State<'_, TransparencyLog>, html: String) -> Result<String, SonnunError> {
+    log_document(&log, &html)
+}
+
+#[tauri::command]
+pub fn get_tree_head(log: State<'_, TransparencyLog>) -> Result<SignedTreeHead, SonnunError> {
+    log.tree_head()
+}
+
+// Published alongside documents so readers can run sonnun-consistency on two tree heads they hold
+#[tauri::command]
+pub fn get_consistency_proof(
+    log: State<'_, TransparencyLog>,
+    first_size: u64,
+    second_size: u64,
+) -> Result<ConsistencyProof, SonnunError> {
+    log.consistency_proof(first_size, second_size)
+}
+
+// AIDEV-NOTE: Rotation statements are public; effective_date defaults to now
+#[tauri::command]
+pub fn rotate_key(
+    keystore: State<'_, Keystore>,
+    old_key_id: String,
+    new_key_id: String,
+    effective_date: Option<String>,
+) -> Result<SignedRotation, SonnunError> {
+    let effective_date = effective_date.unwrap_or_else(|| chrono::Utc::now().to_rfc3339());
+    keystore.rotate_key(&old_key_id, &new_key_id, &effective_date)
+}
+
+#[tauri::command]
+pub fn list_rotations(keystore: State<'_, Keystore>) -> Result<Vec<SignedRotation>, SonnunError> {
+    keystore.list_rotations()
+}
+
+// AIDEV-NOTE: Writes the signed list into the keystore's revocations directory and returns it
+#[tauri::command]
+pub fn revoke_keys(
+    keystore: State<'_, Keystore>,
+    issuer_key_id: String,
+    entries: Vec<RevokedKey>,
+) -> Result<SignedRevocationList, SonnunError> {
+    keystore.revoke_keys(&issuer_key_id, entries)
+}
+
+// AIDEV-NOTE: Tamper check - recomputes every row hash so edits made outside the app are caught
+#[tauri::command]
+pub async fn verify_event_chain(db: State<'_, Database>) -> Result<ChainVerification, SonnunError> {
+    db.verify_event_chain().await
 }
 
 // AIDEV-NOTE: AI gateway - handles OpenAI API calls with proper error handling and attribution
 #[tauri::command]
 pub async fn query_ai_assistant(
     prompt_data: AIPrompt,
-) -> Result<AIResponse, String> {
-    let api_key = std::env::var("OPENAI_API_KEY")
-        .map_err(|_| "OPENAI_API_KEY environment variable not set")?;
+) -> Result<AIResponse, SonnunError> {
+    let api_key = std::env::var("OPENAI_API_KEY").map_err(|_| {
+        SonnunError::AiProvider("OPENAI_API_KEY environment variable not set".to_string())
+    })?;
     
     if prompt_data.prompt.trim().is_empty() {
-        return Err("Prompt cannot be empty".to_string());
+        return Err(SonnunError::InvalidInput("Prompt cannot be empty".to_string()));
     }
     
     let client = reqwest::Client::new();
@@ -135,19 +860,19 @@ pub async fn query_ai_assistant(
         .json(&request_body)
         .send()
         .await
-        .map_err(|e| format!("API request failed: {}", e))?;
+        .map_err(|e| SonnunError::AiProvider(format!("API request failed: {}", e)))?;
     
     if !response.status().is_success() {
         let status = response.status();
         let error_text = response.text().await
             .unwrap_or_else(|_| "Failed to read error response".to_string());
-        return Err(format!("API error {}: {}", status, error_text));
+        return Err(SonnunError::AiProvider(format!("API error {}: {}", status, error_text)));
     }
     
     let response_data: serde_json::Value = response
         .json()
         .await
-        .map_err(|e| format!("Failed to parse response: {}", e))?;
+        .map_err(|e| SonnunError::AiProvider(format!("Failed to parse response: {}", e)))?;
     
     let content = response_data["choices"][0]["message"]["content"]
         .as_str()
@@ -166,38 +891,113 @@ pub async fn query_ai_assistant(
 }
 
 
-fn create_migrations() -> Vec<Migration> {
-    vec![Migration {
-        version: 1,
-        description: "create events table",
-        sql: "CREATE TABLE IF NOT EXISTS events (\n  id INTEGER PRIMARY KEY AUTOINCREMENT,\n  timestamp TEXT NOT NULL,\n  event_type TEXT NOT NULL,\n  text_hash TEXT NOT NULL,\n  source TEXT,\n  span_length INTEGER\n);",
-        kind: MigrationKind::Up,
-    }]
-}
-
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
-        .plugin(
-            SqlBuilder::default()
-                .add_migrations("sqlite:sonnun.db", create_migrations())
-                .build(),
-        )
+        // AIDEV-NOTE: The SQL plugin only provides frontend access; schema lives in migrations.rs
+        // and is applied by Database::new, so the plugin must not run migrations of its own.
+        .plugin(SqlBuilder::default().build())
+        // AIDEV-NOTE: Single shared pool - same file the SQL plugin resolves (app config dir)
+        .setup(|app| {
+            let config_dir = app.path().app_config_dir()?;
+            std::fs::create_dir_all(&config_dir)?;
+            let db_url = format!("sqlite:{}", config_dir.join(DB_FILE).display());
+            let db = tauri::async_runtime::block_on(Database::new(&db_url))?;
+            app.manage(db);
+            app.manage(Keystore::new(app.path().app_data_dir()?.join(KEYS_DIR)));
+            app.manage(TransparencyLog::new(app.path().app_data_dir()?.join(TRANSPARENCY_LOG_FILE)));
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
             greet,
+            create_document,
+            list_documents,
             log_provenance_event,
             get_event_history,
             generate_manifest,
+            verify_event_chain,
+            replay_document,
             query_ai_assistant,
-            sign_document,
-            generate_keypair,
+            create_key,
+            list_keys,
+            import_key,
+            export_public_key,
+            export_private_key,
+            unlock_key,
+            lock_key,
+            sign_with_key,
+            sign_manifest_with_key,
+            export_signed_html,
+            cosign_html,
+            export_c2pa_manifest,
+            export_credential,
+            export_cose_manifest,
+            sign_manifest_jws_with_key,
+            add_jws_to_html,
+            timestamp_html,
+            log_signed_html,
+            get_tree_head,
+            get_consistency_proof,
+            rotate_key,
+            list_rotations,
+            revoke_keys,
             verify_signature
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
 
+#[cfg(test)]
+pub(crate) mod test_manifest {
+    use crate::{EventType, ManifestData, Operation, ProvenanceEvent};
+
+    pub(crate) fn event(event_type: EventType, source: &str) -> ProvenanceEvent {
+        ProvenanceEvent {
+            document_id: 1,
+            timestamp: "2025-01-01T00:00:00Z".to_string(),
+            event_type,
+            text_hash: String::new(),
+            source: source.to_string(),
+            span_length: 10,
+            operation: Operation::Insert,
+            from: None,
+            to: None,
+            text: None,
+        }
+    }
+
+    // The shape generate_manifest returns; cited text comes from two sources, one of them used twice
+    pub(crate) fn manifest_data() -> ManifestData {
+        ManifestData {
+            document_id: 1,
+            human_percentage: 60.0,
+            ai_percentage: 30.0,
+            cited_percentage: 10.0,
+            ai_edited_percentage: 0.0,
+            translated_percentage: 0.0,
+            tool_assisted_percentage: 0.0,
+            imported_percentage: 0.0,
+            total_characters: 30,
+            events: vec![
+                event(EventType::Human, "keyboard"),
+                event(EventType::Ai, "gpt-4o"),
+                event(EventType::Cited, "https://example.com/b"),
+                event(EventType::Cited, "https://example.com/a"),
+                event(EventType::Cited, "https://example.com/b"),
+            ],
+            event_chain_head: "0".repeat(64),
+            document_text_hash: None,
+            generated_at: Some("2025-03-01T12:00:00Z".to_string()),
+            author_did: None,
+        }
+    }
+
+    pub(crate) fn manifest_value() -> serde_json::Value {
+        serde_json::to_value(manifest_data()).unwrap()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -231,7 +1031,7 @@ mod tests {
         let private_key_bytes = base64::decode(&private_key_b64).unwrap();
         
         // Test signing
-        let signature_result = sign_document(content.to_string(), private_key_bytes).await;
+        let signature_result = sign_document(content.to_string(), private_key_bytes, None).await;
         assert!(signature_result.is_ok());
         
         let signature = signature_result.unwrap();
@@ -240,7 +1040,8 @@ mod tests {
         let verification_result = verify_signature(
             content.to_string(),
             signature,
-            public_key_b64
+            public_key_b64,
+            None
         );
         assert!(verification_result.is_ok());
         assert!(verification_result.unwrap());
@@ -249,40 +1050,46 @@ mod tests {
     #[tokio::test]
     async fn test_sign_document_validation() {
         // Test empty content
-        let result = sign_document("".to_string(), vec![0; 32]).await;
+        let result = sign_document("".to_string(), vec![0; 32], None).await;
         assert!(result.is_err());
-        assert!(result.unwrap_err().contains("Content cannot be empty"));
+        assert!(result.unwrap_err().to_string().contains("Content cannot be empty"));
         
         // Test invalid key length
-        let result = sign_document("test".to_string(), vec![0; 10]).await;
+        let result = sign_document("test".to_string(), vec![0; 10], None).await;
         assert!(result.is_err());
-        assert!(result.unwrap_err().contains("Invalid private key length"));
+        assert!(result.unwrap_err().to_string().contains("Invalid private key length"));
     }
 
     #[test]
     fn test_verify_signature_validation() {
         // Test empty content
-        let result = verify_signature("".to_string(), "sig".to_string(), "key".to_string());
+        let result = verify_signature("".to_string(), "sig".to_string(), "key".to_string(), None);
         assert!(result.is_err());
-        assert!(result.unwrap_err().contains("Content cannot be empty"));
+        assert!(result.unwrap_err().to_string().contains("Content cannot be empty"));
         
         // Test invalid base64
-        let result = verify_signature("test".to_string(), "invalid_base64!".to_string(), "key".to_string());
+        let result = verify_signature("test".to_string(), "invalid_base64!".to_string(), "key".to_string(), None);
         assert!(result.is_err());
     }
 
     #[test]
     fn test_provenance_event_serialization() {
         let event = ProvenanceEvent {
+            document_id: 1,
             timestamp: "2023-01-01T00:00:00Z".to_string(),
-            event_type: "human".to_string(),
+            event_type: EventType::Human,
             text_hash: "test_hash".to_string(),
             source: "user".to_string(),
             span_length: 10,
+            operation: Operation::Insert,
+            from: Some(0),
+            to: None,
+            text: Some("secret".to_string()),
         };
         
         let json = serde_json::to_string(&event);
         assert!(json.is_ok());
+        assert!(!json.as_ref().unwrap().contains("secret")); // payloads stay in the database
         
         let deserialized: Result<ProvenanceEvent, _> = serde_json::from_str(&json.unwrap());
         assert!(deserialized.is_ok());
@@ -290,5 +1097,83 @@ mod tests {
         let deserialized_event = deserialized.unwrap();
         assert_eq!(event.timestamp, deserialized_event.timestamp);
         assert_eq!(event.event_type, deserialized_event.event_type);
+        assert_eq!(event.document_id, deserialized_event.document_id);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_event_type_serialization() {
+        assert_eq!(serde_json::to_string(&EventType::AiEdited).unwrap(), "\"ai_edited\"");
+        assert_eq!(serde_json::to_string(&EventType::ToolAssisted).unwrap(), "\"tool_assisted\"");
+
+        for event_type in EventType::ALL {
+            let json = format!("\"{}\"", event_type.as_str());
+            assert_eq!(serde_json::from_str::<EventType>(&json).unwrap(), event_type);
+            assert_eq!(event_type.as_str().parse::<EventType>().unwrap(), event_type);
+        }
+    }
+
+    #[test]
+    fn test_event_input_rejects_unknown_event_type() {
+        let json = r#"{
+            "document_id": 1,
+            "timestamp": "2023-01-01T00:00:00Z",
+            "event_type": "robot",
+            "text": "beep",
+            "source": "user",
+            "span_length": 4
+        }"#;
+        let result: Result<ProvenanceEventInput, _> = serde_json::from_str(json);
+        assert!(result.is_err());
+        assert!("robot".parse::<EventType>().is_err());
+    }
+
+    #[test]
+    fn test_event_input_defaults_to_positionless_insert() {
+        let json = r#"{
+            "document_id": 1,
+            "timestamp": "2023-01-01T00:00:00Z",
+            "event_type": "human",
+            "text": "hello",
+            "source": "user",
+            "span_length": 5
+        }"#;
+        let input: ProvenanceEventInput = serde_json::from_str(json).unwrap();
+        assert_eq!(input.operation, Operation::Insert);
+        assert_eq!(input.from, None);
+        assert_eq!(input.to, None);
+    }
+
+    #[test]
+    fn test_event_validation() {
+        let event = |operation, from, to, span_length| ProvenanceEvent {
+            document_id: 1,
+            timestamp: "2023-01-01T00:00:00Z".to_string(),
+            event_type: EventType::Human,
+            text_hash: "hash".to_string(),
+            source: "user".to_string(),
+            span_length,
+            operation,
+            from,
+            to,
+            text: None,
+        };
+
+        assert!(event(Operation::Insert, None, None, 5).validate().is_ok());
+        assert!(event(Operation::Insert, Some(3), Some(3), 5).validate().is_ok());
+        assert!(event(Operation::Insert, Some(3), Some(4), 5).validate().is_err());
+        assert!(event(Operation::Delete, Some(2), Some(7), 5).validate().is_ok());
+        assert!(event(Operation::Delete, Some(2), Some(7), 3).validate().is_err());
+        assert!(event(Operation::Delete, None, None, 5).validate().is_err());
+        assert!(event(Operation::Replace, Some(7), Some(2), 5).validate().is_err());
+        assert!(event(Operation::Replace, Some(2), Some(2), 5).validate().is_ok());
+
+        let with_text = |operation, from, to, span_length, text: &str| ProvenanceEvent {
+            text: Some(text.to_string()),
+            ..event(operation, from, to, span_length)
+        };
+        assert!(with_text(Operation::Insert, Some(0), None, 2, "😀").validate().is_ok());
+        assert!(with_text(Operation::Insert, Some(0), None, 1, "😀").validate().is_err());
+        assert!(with_text(Operation::Delete, Some(0), Some(3), 3, "abc").validate().is_ok());
+        assert!(with_text(Operation::Replace, Some(0), Some(3), 2, "xy").validate().is_ok());
+    }
+}
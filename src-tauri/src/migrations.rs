@@ -0,0 +1,280 @@
+// AIDEV-NOTE: Single source of truth for the SQLite schema - ordered, versioned, applied through sqlx.
+// Add new migrations at the end with the next version number; never edit one that has shipped.
+use sqlx::{Row, SqliteConnection, SqlitePool};
+
+use crate::database::seal_event_rows;
+use crate::error::SonnunError;
+
+pub struct Migration {
+    pub version: i64,
+    pub description: &'static str,
+    pub sql: &'static str,
+    pub backfill: Option<Backfill>,
+}
+
+// AIDEV-NOTE: Data steps that need Rust (e.g. SHA-256); run inside the migration's transaction before `sql`
+pub enum Backfill {
+    SealEventChain,
+}
+
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        description: "create events table",
+        sql: include_str!("../migrations/0001_create_events.sql"),
+        backfill: None,
+    },
+    Migration {
+        version: 2,
+        description: "scope events to documents",
+        sql: include_str!("../migrations/0002_scope_events_to_documents.sql"),
+        backfill: None,
+    },
+    Migration {
+        version: 3,
+        description: "add event hash chain",
+        sql: include_str!("../migrations/0003_add_event_hash_chain.sql"),
+        backfill: None,
+    },
+    Migration {
+        version: 4,
+        description: "seal event chain and make events append-only",
+        sql: include_str!("../migrations/0004_append_only_events.sql"),
+        backfill: Some(Backfill::SealEventChain),
+    },
+    Migration {
+        version: 5,
+        description: "add event positions and operations",
+        sql: include_str!("../migrations/0005_add_event_positions.sql"),
+        backfill: None,
+    },
+    Migration {
+        version: 6,
+        description: "store event payloads for replay",
+        sql: include_str!("../migrations/0006_store_event_payloads.sql"),
+        backfill: None,
+    },
+];
+
+pub fn latest_version() -> i64 {
+    MIGRATIONS.last().map(|m| m.version).unwrap_or(0)
+}
+
+// AIDEV-NOTE: Brings the database up to latest_version(), one transaction per migration.
+// Returns the resulting schema version.
+pub async fn run_migrations(pool: &SqlitePool) -> Result<i64, SonnunError> {
+    let mut conn = pool.acquire().await?;
+
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS schema_version (\n  version INTEGER PRIMARY KEY,\n  description TEXT NOT NULL,\n  applied_at TEXT NOT NULL\n)",
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    let mut current = current_version(&mut conn).await?;
+    if current == 0 {
+        // Databases created before schema_version existed: record what is already there
+        let adopted = detect_unversioned_schema(&mut conn).await?;
+        for migration in MIGRATIONS.iter().filter(|m| m.version <= adopted) {
+            record_version(&mut conn, migration).await?;
+        }
+        current = adopted;
+    }
+    if current > latest_version() {
+        return Err(SonnunError::Database(format!(
+            "Database schema version {} is newer than this build supports ({})",
+            current,
+            latest_version()
+        )));
+    }
+
+    let pending: Vec<&Migration> = MIGRATIONS.iter().filter(|m| m.version > current).collect();
+    for migration in pending {
+        sqlx::query("BEGIN IMMEDIATE")
+            .execute(&mut *conn)
+            .await?;
+
+        if let Err(e) = apply(&mut conn, migration).await {
+            let _ = sqlx::query("ROLLBACK").execute(&mut *conn).await;
+            return Err(SonnunError::Database(format!(
+                "Migration {} ({}) failed: {}",
+                migration.version,
+                migration.description,
+                e.message()
+            )));
+        }
+        sqlx::query("COMMIT")
+            .execute(&mut *conn)
+            .await?;
+        current = migration.version;
+    }
+
+    Ok(current)
+}
+
+pub async fn current_version(conn: &mut SqliteConnection) -> Result<i64, SonnunError> {
+    let row = sqlx::query("SELECT COALESCE(MAX(version), 0) AS version FROM schema_version")
+        .fetch_one(&mut *conn)
+        .await?;
+    Ok(row.get::<i64, _>("version"))
+}
+
+async fn apply(conn: &mut SqliteConnection, migration: &Migration) -> Result<(), SonnunError> {
+    if let Some(backfill) = &migration.backfill {
+        match backfill {
+            Backfill::SealEventChain => seal_event_rows(conn).await?,
+        }
+    }
+    sqlx::raw_sql(migration.sql)
+        .execute(&mut *conn)
+        .await?;
+    record_version(conn, migration).await
+}
+
+async fn record_version(conn: &mut SqliteConnection, migration: &Migration) -> Result<(), SonnunError> {
+    sqlx::query("INSERT INTO schema_version (version, description, applied_at) VALUES (?1, ?2, ?3)")
+        .bind(migration.version)
+        .bind(migration.description)
+        .bind(chrono::Utc::now().to_rfc3339())
+        .execute(&mut *conn)
+        .await?;
+    Ok(())
+}
+
+// AIDEV-NOTE: Pre-versioning databases came from tauri-plugin-sql or the old inline CREATE TABLE.
+// Infer their version from the shape of `events` so only the missing steps are applied.
+async fn detect_unversioned_schema(conn: &mut SqliteConnection) -> Result<i64, SonnunError> {
+    let columns: Vec<String> = sqlx::query("SELECT name FROM pragma_table_info('events')")
+        .fetch_all(&mut *conn)
+        .await?
+        .into_iter()
+        .map(|row| row.get::<String, _>("name"))
+        .collect();
+    let has_column = |name: &str| columns.iter().any(|c| c == name);
+
+    let has_trigger = sqlx::query(
+        "SELECT COUNT(*) AS count FROM sqlite_master WHERE type = 'trigger' AND name = 'events_append_only_update'",
+    )
+    .fetch_one(&mut *conn)
+    .await?
+    .get::<i64, _>("count")
+        > 0;
+
+    let version = if columns.is_empty() {
+        0
+    } else if !has_column("document_id") {
+        1
+    } else if !has_column("row_hash") {
+        2
+    } else if !has_trigger {
+        3
+    } else {
+        4
+    };
+    Ok(version)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::Database;
+
+    // AIDEV-NOTE: Fixtures must live in a file - each sqlite::memory: pool is a fresh database
+    async fn database_from_fixture(name: &str, fixture: &str) -> (Database, std::path::PathBuf) {
+        let path = std::env::temp_dir().join(format!("sonnun-{}-{}.db", name, std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let url = format!("sqlite:{}", path.display());
+
+        let pool = SqlitePool::connect(&format!("{}?mode=rwc", url)).await.unwrap();
+        sqlx::raw_sql(fixture).execute(&pool).await.unwrap();
+        pool.close().await;
+
+        (Database::new(&url).await.unwrap(), path)
+    }
+
+    async fn cleanup(db: Database, path: std::path::PathBuf) {
+        db.close().await;
+        for suffix in ["", "-wal", "-shm"] {
+            let _ = std::fs::remove_file(format!("{}{}", path.display(), suffix));
+        }
+    }
+
+    #[test]
+    fn test_migrations_are_ordered() {
+        for pair in MIGRATIONS.windows(2) {
+            assert_eq!(pair[1].version, pair[0].version + 1);
+        }
+        assert_eq!(MIGRATIONS[0].version, 1);
+    }
+
+    #[tokio::test]
+    async fn test_fresh_database_reaches_latest_version() {
+        let pool = SqlitePool::connect("sqlite::memory:").await.unwrap();
+        assert_eq!(run_migrations(&pool).await.unwrap(), latest_version());
+
+        let recorded = sqlx::query("SELECT COUNT(*) AS count FROM schema_version")
+            .fetch_one(&pool)
+            .await
+            .unwrap()
+            .get::<i64, _>("count");
+        assert_eq!(recorded, MIGRATIONS.len() as i64);
+
+        // Running again is a no-op
+        assert_eq!(run_migrations(&pool).await.unwrap(), latest_version());
+    }
+
+    #[tokio::test]
+    async fn test_newer_schema_is_rejected() {
+        let pool = SqlitePool::connect("sqlite::memory:").await.unwrap();
+        run_migrations(&pool).await.unwrap();
+        sqlx::query("INSERT INTO schema_version (version, description, applied_at) VALUES (?1, 'future', '')")
+            .bind(latest_version() + 1)
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let result = run_migrations(&pool).await;
+        assert!(result.unwrap_err().to_string().contains("newer than this build supports"));
+    }
+
+    #[tokio::test]
+    async fn test_upgrade_from_v1_fixture() {
+        let (db, path) = database_from_fixture(
+            "fixture-v1",
+            include_str!("../migrations/fixtures/v1_events_only.sql"),
+        )
+        .await;
+
+        assert_eq!(db.schema_version().await.unwrap(), latest_version());
+
+        // Legacy rows survive, get sealed into the chain and become immutable
+        let verification = db.verify_event_chain().await.unwrap();
+        assert!(verification.valid);
+        assert_eq!(verification.events_checked, 3);
+
+        let doc = db.create_document("After upgrade").await.unwrap();
+        assert_eq!(db.get_events(doc.id, None, None).await.unwrap().len(), 0);
+
+        cleanup(db, path).await;
+    }
+
+    #[tokio::test]
+    async fn test_upgrade_from_v2_fixture() {
+        let (db, path) = database_from_fixture(
+            "fixture-v2",
+            include_str!("../migrations/fixtures/v2_documents_unchained.sql"),
+        )
+        .await;
+
+        assert_eq!(db.schema_version().await.unwrap(), latest_version());
+
+        let events = db.get_events(1, None, None).await.unwrap();
+        assert_eq!(events.len(), 2);
+
+        let verification = db.verify_event_chain().await.unwrap();
+        assert!(verification.valid);
+        assert_eq!(verification.events_checked, 3);
+
+        cleanup(db, path).await;
+    }
+}
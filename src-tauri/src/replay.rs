@@ -0,0 +1,189 @@
+// AIDEV-NOTE: Deterministic replay - rebuilds a document's text and provenance spans from its event log.
+// Offsets and span lengths are UTF-16 code units, the unit the editor (JavaScript strings) reports.
+use chrono::{DateTime, FixedOffset};
+use serde::{Deserialize, Serialize};
+
+use crate::error::SonnunError;
+use crate::spans::{Span, SpanRope};
+use crate::{hash_text, Operation, ProvenanceEvent};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplayedDocument {
+    pub document_id: i64,
+    pub text: String,
+    pub spans: Vec<Span>,
+    pub events_applied: usize,
+    pub text_hash: String,             // SHA-256 of the rebuilt text
+    pub recorded_hash: Option<String>, // Hash stored with the document at manifest generation
+    pub hash_matches: Option<bool>,    // None for point-in-time replays or when nothing was recorded
+}
+
+#[derive(Debug)]
+pub struct Replay {
+    pub text: String,
+    pub spans: Vec<Span>,
+    pub events_applied: usize,
+}
+
+pub fn parse_timestamp(timestamp: &str) -> Result<DateTime<FixedOffset>, SonnunError> {
+    DateTime::parse_from_rfc3339(timestamp)
+        .map_err(|e| SonnunError::InvalidInput(format!("Invalid timestamp '{}': {}", timestamp, e)))
+}
+
+// AIDEV-NOTE: Events must be in log (id) order. With `until`, replay stops before the first event
+// stamped later than it. Every payload is checked against its chained text_hash, and deletions
+// against the text they claim to remove, so a log that cannot produce the document fails loudly.
+pub fn replay(
+    events: &[ProvenanceEvent],
+    until: Option<&DateTime<FixedOffset>>,
+) -> Result<Replay, SonnunError> {
+    let mut buffer: Vec<u16> = Vec::new();
+    let mut rope = SpanRope::new();
+    let mut events_applied = 0;
+
+    for (index, event) in events.iter().enumerate() {
+        if let Some(until) = until {
+            if parse_timestamp(&event.timestamp)? > *until {
+                break;
+            }
+        }
+
+        let fail = |reason: String| {
+            SonnunError::InvalidInput(format!("Event {} ({}) cannot be replayed: {}", index + 1, event.timestamp, reason))
+        };
+        let text = event
+            .text
+            .as_deref()
+            .ok_or_else(|| fail("no stored payload".to_string()))?;
+        apply_payload(&mut buffer, event, text).map_err(fail)?;
+        rope.apply(event)?;
+        events_applied += 1;
+    }
+
+    let text = String::from_utf16(&buffer)
+        .map_err(|_| SonnunError::InvalidInput("Replayed text is not valid UTF-16".to_string()))?;
+    Ok(Replay {
+        text,
+        spans: rope.spans().copied().collect(),
+        events_applied,
+    })
+}
+
+// AIDEV-NOTE: One event's effect on the text, shared with Database::insert_event so an event that
+// could not be replayed is refused before it reaches the log. Checks everything before editing
+// `buffer`; the error is the reason the event does not fit.
+pub(crate) fn apply_payload(buffer: &mut Vec<u16>, event: &ProvenanceEvent, text: &str) -> Result<(), String> {
+    if hash_text(text) != event.text_hash {
+        return Err("payload does not match its text_hash".to_string());
+    }
+    let units: Vec<u16> = text.encode_utf16().collect();
+
+    let from = event.from.unwrap_or(buffer.len());
+    let to = match event.operation {
+        Operation::Insert => from,
+        Operation::Delete | Operation::Replace => event.to.unwrap_or(from),
+    };
+    if from > to || to > buffer.len() {
+        return Err(format!("range {}..{} is outside the text ({} units)", from, to, buffer.len()));
+    }
+
+    match event.operation {
+        Operation::Delete => {
+            if buffer[from..to] != units[..] {
+                return Err("deleted text differs from the document".to_string());
+            }
+            buffer.drain(from..to);
+        }
+        Operation::Insert | Operation::Replace => {
+            if units.len() != event.span_length {
+                return Err(format!("payload is {} units but span_length is {}", units.len(), event.span_length));
+            }
+            buffer.splice(from..to, units);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::EventType;
+
+    fn event(timestamp: &str, event_type: EventType, operation: Operation, from: usize, to: Option<usize>, text: &str) -> ProvenanceEvent {
+        ProvenanceEvent {
+            document_id: 1,
+            timestamp: timestamp.to_string(),
+            event_type,
+            text_hash: hash_text(text),
+            source: "test".to_string(),
+            span_length: text.encode_utf16().count(),
+            operation,
+            from: Some(from),
+            to,
+            text: Some(text.to_string()),
+        }
+    }
+
+    fn history() -> Vec<ProvenanceEvent> {
+        vec![
+            event("2024-01-01T10:00:00Z", EventType::Human, Operation::Insert, 0, None, "Hello world"),
+            event("2024-01-01T10:01:00Z", EventType::Ai, Operation::Insert, 5, None, ", dear"),
+            event("2024-01-01T10:02:00Z", EventType::Human, Operation::Delete, 0, Some(5), "Hello"),
+            event("2024-01-01T10:03:00Z", EventType::Human, Operation::Replace, 7, Some(12), "reader"),
+        ]
+    }
+
+    #[test]
+    fn test_replay_rebuilds_text_and_spans() {
+        let result = replay(&history(), None).unwrap();
+        assert_eq!(result.text, ", dear reader");
+        assert_eq!(result.events_applied, 4);
+        assert_eq!(
+            result.spans,
+            vec![
+                Span { event_type: EventType::Ai, len: 6 },
+                Span { event_type: EventType::Human, len: 7 },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_replay_until_a_point_in_time() {
+        let until = parse_timestamp("2024-01-01T10:01:30Z").unwrap();
+        let result = replay(&history(), Some(&until)).unwrap();
+        assert_eq!(result.text, "Hello, dear world");
+        assert_eq!(result.events_applied, 2);
+    }
+
+    #[test]
+    fn test_replay_counts_utf16_units() {
+        let events = vec![
+            event("2024-01-01T10:00:00Z", EventType::Human, Operation::Insert, 0, None, "a😀b"),
+            event("2024-01-01T10:01:00Z", EventType::Human, Operation::Delete, 1, Some(3), "😀"),
+        ];
+        assert_eq!(replay(&events, None).unwrap().text, "ab");
+    }
+
+    #[test]
+    fn test_replay_rejects_tampered_payload() {
+        let mut events = history();
+        events[1].text = Some(", evil".to_string());
+        let error = replay(&events, None).unwrap_err();
+        assert!(error.to_string().contains("text_hash"));
+    }
+
+    #[test]
+    fn test_replay_rejects_deletion_of_other_text() {
+        let mut events = history();
+        events[2] = event("2024-01-01T10:02:00Z", EventType::Human, Operation::Delete, 0, Some(5), "Howdy");
+        let error = replay(&events, None).unwrap_err();
+        assert!(error.to_string().contains("deleted text differs"));
+    }
+
+    #[test]
+    fn test_replay_requires_payloads() {
+        let mut events = history();
+        events[0].text = None;
+        assert!(replay(&events, None).unwrap_err().to_string().contains("no stored payload"));
+    }
+}
@@ -0,0 +1,236 @@
+// AIDEV-NOTE: Key revocation - a signed list of revoked keys, each with a reason and the time it stopped
+// being trustworthy. Lists are honored only from an issuer with authority over the revoked key (the
+// key itself, the reader's trusted key, or a key on the document's rotation chain), so anyone can
+// publish a list but nobody can revoke somebody else's key.
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine as _;
+use chrono::{DateTime, FixedOffset};
+use ed25519_dalek::{Signer, SigningKey, Verifier};
+use serde::{Deserialize, Serialize};
+
+use crate::crypto_utils::{parse_public_key, parse_signature};
+use crate::error::SonnunError;
+use crate::replay::parse_timestamp;
+
+const REVOCATION_VERSION: u32 = 1;
+// Domain separation, as for rotation statements
+const REVOCATION_DOMAIN: &str = "sonnun-revocation-v1\n";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RevocationReason {
+    KeyCompromise, // every signature is suspect: a thief can backdate generated_at
+    Superseded,    // replaced by a newer key; earlier signatures stay valid
+    Retired,       // no longer used; earlier signatures stay valid
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RevokedKey {
+    pub public_key: String, // base64 ed25519 public key
+    pub reason: RevocationReason,
+    pub revoked_at: String, // RFC 3339
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RevocationList {
+    pub version: u32,
+    pub issuer: String, // base64 public key that signs this list
+    pub issued_at: String,
+    pub entries: Vec<RevokedKey>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SignedRevocationList {
+    pub list: RevocationList,
+    pub signature: String, // base64 ed25519 signature by issuer over signing_bytes()
+}
+
+impl RevocationList {
+    pub fn new(issuer: &str, entries: Vec<RevokedKey>) -> Result<Self, SonnunError> {
+        if entries.is_empty() {
+            return Err(SonnunError::InvalidInput("A revocation list needs at least one key".to_string()));
+        }
+        parse_public_key(issuer)?;
+        for entry in &entries {
+            parse_public_key(&entry.public_key)?;
+            parse_timestamp(&entry.revoked_at)?;
+        }
+        Ok(Self {
+            version: REVOCATION_VERSION,
+            issuer: issuer.to_string(),
+            issued_at: chrono::Utc::now().to_rfc3339(),
+            entries,
+        })
+    }
+
+    pub fn signing_bytes(&self) -> Vec<u8> {
+        let json = serde_json::to_string(self).expect("revocation list always serializes");
+        format!("{}{}", REVOCATION_DOMAIN, json).into_bytes()
+    }
+
+    pub fn sign(self, signing_key: &SigningKey) -> Result<SignedRevocationList, SonnunError> {
+        if BASE64.encode(signing_key.verifying_key().to_bytes()) != self.issuer {
+            return Err(SonnunError::InvalidKey("Revocation list must be signed by its issuer".to_string()));
+        }
+        let signature = BASE64.encode(signing_key.sign(&self.signing_bytes()).to_bytes());
+        Ok(SignedRevocationList {
+            list: self,
+            signature,
+        })
+    }
+}
+
+impl SignedRevocationList {
+    pub fn verify(&self) -> Result<(), SonnunError> {
+        if self.list.version != REVOCATION_VERSION {
+            return Err(SonnunError::ManifestSchema(format!(
+                "Unsupported revocation list version {}",
+                self.list.version
+            )));
+        }
+        for entry in &self.list.entries {
+            parse_timestamp(&entry.revoked_at)?;
+        }
+        parse_public_key(&self.list.issuer)?
+            .verify(&self.list.signing_bytes(), &parse_signature(&self.signature)?)
+            .map_err(|_| SonnunError::InvalidSignature("Revocation list signature does not match".to_string()))
+    }
+}
+
+// AIDEV-NOTE: Fails with KeyRevoked when an authorized list revokes `public_key`. Compromised keys are
+// rejected outright; superseded or retired keys only for signatures made at or after revoked_at, or
+// when the signing time is unknown. Lists from issuers without authority over the key are skipped
+// unread, so one foreign list in a shared directory cannot fail every document; a list from an issuer
+// that does have authority must verify, because it could be the one revoking this key.
+pub fn check_revocation(
+    public_key: &str,
+    signed_at: Option<&DateTime<FixedOffset>>,
+    lists: &[SignedRevocationList],
+    authorities: &[&str],
+) -> Result<(), SonnunError> {
+    for signed in lists {
+        let issuer = signed.list.issuer.as_str();
+        if issuer != public_key && !authorities.contains(&issuer) {
+            continue;
+        }
+        signed.verify()?;
+        for entry in signed.list.entries.iter().filter(|entry| entry.public_key == public_key) {
+            let revoked_at = parse_timestamp(&entry.revoked_at)?;
+            let revoked = match (entry.reason, signed_at) {
+                (RevocationReason::KeyCompromise, _) | (_, None) => true,
+                (_, Some(signed_at)) => *signed_at >= revoked_at,
+            };
+            if revoked {
+                return Err(SonnunError::KeyRevoked(format!(
+                    "Key {} was revoked at {} ({:?})",
+                    public_key, entry.revoked_at, entry.reason
+                )));
+            }
+        }
+    }
+    Ok(())
+}
+
+// Accepts one revocation file, or a directory whose *.json files are each a revocation list
+pub fn load_revocations(path: &Path) -> Result<Vec<SignedRevocationList>, SonnunError> {
+    let files: Vec<PathBuf> = if path.is_dir() {
+        let mut files = Vec::new();
+        for entry in fs::read_dir(path)? {
+            let file = entry?.path();
+            if file.extension().is_some_and(|extension| extension == "json") {
+                files.push(file);
+            }
+        }
+        files.sort();
+        files
+    } else {
+        vec![path.to_path_buf()]
+    };
+
+    let mut lists = Vec::new();
+    for file in files {
+        let contents = fs::read_to_string(&file)
+            .map_err(|e| SonnunError::Io(format!("Failed to read revocations {}: {}", file.display(), e)))?;
+        let list = serde_json::from_str(&contents).map_err(|e| {
+            SonnunError::ManifestSchema(format!("Invalid revocation list {}: {}", file.display(), e))
+        })?;
+        lists.push(list);
+    }
+    Ok(lists)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keystore::test_keys::public;
+    use rand::rngs::OsRng;
+
+    fn revoke(issuer: &SigningKey, revoked: &SigningKey, reason: RevocationReason) -> SignedRevocationList {
+        let entry = RevokedKey {
+            public_key: public(revoked),
+            reason,
+            revoked_at: "2025-06-01T00:00:00Z".to_string(),
+        };
+        RevocationList::new(&public(issuer), vec![entry]).unwrap().sign(issuer).unwrap()
+    }
+
+    #[test]
+    fn test_compromised_key_fails_regardless_of_signing_time() {
+        let key = SigningKey::generate(&mut OsRng);
+        let lists = vec![revoke(&key, &key, RevocationReason::KeyCompromise)];
+        let before = parse_timestamp("2024-01-01T00:00:00Z").unwrap();
+
+        let error = check_revocation(&public(&key), Some(&before), &lists, &[]).unwrap_err();
+        assert_eq!(error.code(), "KEY_REVOKED");
+    }
+
+    #[test]
+    fn test_superseded_key_keeps_earlier_signatures() {
+        let key = SigningKey::generate(&mut OsRng);
+        let lists = vec![revoke(&key, &key, RevocationReason::Superseded)];
+        let before = parse_timestamp("2025-05-31T00:00:00Z").unwrap();
+        let after = parse_timestamp("2025-06-02T00:00:00Z").unwrap();
+
+        assert!(check_revocation(&public(&key), Some(&before), &lists, &[]).is_ok());
+        assert!(check_revocation(&public(&key), Some(&after), &lists, &[]).is_err());
+        assert!(check_revocation(&public(&key), None, &lists, &[]).is_err());
+    }
+
+    #[test]
+    fn test_only_authorized_issuers_can_revoke() {
+        let key = SigningKey::generate(&mut OsRng);
+        let stranger = SigningKey::generate(&mut OsRng);
+        let lists = vec![revoke(&stranger, &key, RevocationReason::KeyCompromise)];
+
+        assert!(check_revocation(&public(&key), None, &lists, &[]).is_ok());
+        assert!(check_revocation(&public(&key), None, &lists, &[&public(&stranger)]).is_err());
+
+        let mut tampered = lists;
+        tampered[0].list.entries[0].reason = RevocationReason::Retired;
+        let error = check_revocation(&public(&key), None, &tampered, &[&public(&stranger)]).unwrap_err();
+        assert_eq!(error.code(), "INVALID_SIGNATURE");
+
+        // A broken list from someone without authority is not this document's problem
+        tampered[0].list.version = 99;
+        assert!(check_revocation(&public(&key), None, &tampered, &[]).is_ok());
+    }
+
+    #[test]
+    fn test_load_revocations_from_directory() {
+        let key = SigningKey::generate(&mut OsRng);
+        let dir = std::env::temp_dir().join(format!("sonnun-revocations-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let list = revoke(&key, &key, RevocationReason::Retired);
+        fs::write(dir.join("a.json"), serde_json::to_string(&list).unwrap()).unwrap();
+        fs::write(dir.join("b.json"), serde_json::to_string(&list).unwrap()).unwrap();
+        fs::write(dir.join("notes.txt"), "ignored").unwrap();
+
+        assert_eq!(load_revocations(&dir).unwrap().len(), 2);
+        assert_eq!(load_revocations(&dir.join("a.json")).unwrap(), vec![list]);
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
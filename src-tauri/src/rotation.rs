@@ -0,0 +1,251 @@
+// AIDEV-NOTE: Key rotation - the outgoing key signs a statement naming its successor and the date the
+// successor takes over. Verifiers walk these statements from a key they already trust, so readers
+// only ever have to pin one key per author.
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine as _;
+use chrono::{DateTime, FixedOffset};
+use ed25519_dalek::{Signer, SigningKey, Verifier};
+use serde::{Deserialize, Serialize};
+
+use crate::crypto_utils::{parse_public_key, parse_signature};
+use crate::error::SonnunError;
+use crate::replay::parse_timestamp;
+
+const ROTATION_VERSION: u32 = 1;
+// Domain separation: a rotation signature can never be replayed as a document signature
+const ROTATION_DOMAIN: &str = "sonnun-rotation-v1\n";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RotationStatement {
+    pub version: u32,
+    pub previous_key: String,   // base64 ed25519 public key that signs this statement
+    pub new_key: String,        // base64 ed25519 public key taking over
+    pub effective_date: String, // RFC 3339; documents signed by new_key before this are not covered
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SignedRotation {
+    pub statement: RotationStatement,
+    pub signature: String, // base64 ed25519 signature by previous_key over signing_bytes()
+}
+
+impl RotationStatement {
+    pub fn new(previous_key: &str, new_key: &str, effective_date: &str) -> Result<Self, SonnunError> {
+        parse_timestamp(effective_date)?;
+        if previous_key == new_key {
+            return Err(SonnunError::InvalidInput("A key cannot rotate to itself".to_string()));
+        }
+        parse_public_key(previous_key)?;
+        parse_public_key(new_key)?;
+        Ok(Self {
+            version: ROTATION_VERSION,
+            previous_key: previous_key.to_string(),
+            new_key: new_key.to_string(),
+            effective_date: effective_date.to_string(),
+        })
+    }
+
+    pub fn signing_bytes(&self) -> Vec<u8> {
+        let json = serde_json::to_string(self).expect("rotation statement always serializes");
+        format!("{}{}", ROTATION_DOMAIN, json).into_bytes()
+    }
+
+    pub fn sign(self, signing_key: &SigningKey) -> Result<SignedRotation, SonnunError> {
+        if BASE64.encode(signing_key.verifying_key().to_bytes()) != self.previous_key {
+            return Err(SonnunError::InvalidKey(
+                "Rotation must be signed by the key being rotated out".to_string(),
+            ));
+        }
+        let signature = BASE64.encode(signing_key.sign(&self.signing_bytes()).to_bytes());
+        Ok(SignedRotation {
+            statement: self,
+            signature,
+        })
+    }
+}
+
+impl SignedRotation {
+    pub fn verify(&self) -> Result<(), SonnunError> {
+        if self.statement.version != ROTATION_VERSION {
+            return Err(SonnunError::ManifestSchema(format!(
+                "Unsupported rotation statement version {}",
+                self.statement.version
+            )));
+        }
+        parse_timestamp(&self.statement.effective_date)?;
+        let verifying_key = parse_public_key(&self.statement.previous_key)?;
+        let signature = parse_signature(&self.signature)?;
+        verifying_key
+            .verify(&self.statement.signing_bytes(), &signature)
+            .map_err(|_| SonnunError::SignatureMismatch("Rotation statement signature does not match".to_string()))
+    }
+
+    fn effective_date(&self) -> DateTime<FixedOffset> {
+        // Only called on verified statements, whose dates have already parsed
+        parse_timestamp(&self.statement.effective_date).expect("verified rotation has a valid date")
+    }
+}
+
+// AIDEV-NOTE: Breadth-first search from the trusted key to the signing key over statements with
+// valid signatures; forged or malformed statements are ignored rather than failing the whole set.
+// Effective dates must not go backwards along the chain, and when the document says when it was
+// signed, that must be on or after the last hand-over. Returns the statements walked, in order.
+// Because of the date rule a key is worth revisiting when it is reached through an earlier
+// hand-over: every continuation open to the later arrival is open to the earlier one, not the reverse.
+pub fn find_rotation_chain(
+    trusted_key: &str,
+    signing_key: &str,
+    rotations: &[SignedRotation],
+    signed_at: Option<&DateTime<FixedOffset>>,
+) -> Result<Vec<SignedRotation>, SonnunError> {
+    if trusted_key == signing_key {
+        return Ok(Vec::new());
+    }
+    let valid: Vec<&SignedRotation> = rotations.iter().filter(|rotation| rotation.verify().is_ok()).collect();
+
+    // Earliest hand-over each key has been reached through; the trusted key needs none (None sorts first)
+    let mut reached: HashMap<String, Option<DateTime<FixedOffset>>> = HashMap::from([(trusted_key.to_string(), None)]);
+    let mut frontier: Vec<(String, Vec<&SignedRotation>)> = vec![(trusted_key.to_string(), Vec::new())];
+    while !frontier.is_empty() {
+        let mut next = Vec::new();
+        for (key, path) in frontier {
+            let last_date = path.last().map(|last| last.effective_date());
+            for rotation in valid.iter().filter(|rotation| rotation.statement.previous_key == key) {
+                let effective_date = rotation.effective_date();
+                if last_date.is_some_and(|last_date| effective_date < last_date) {
+                    continue;
+                }
+                let mut path = path.clone();
+                path.push(rotation);
+                let new_key = &rotation.statement.new_key;
+                if new_key == signing_key {
+                    if let Some(signed_at) = signed_at {
+                        if *signed_at < effective_date {
+                            continue;
+                        }
+                    }
+                    return Ok(path.into_iter().cloned().collect());
+                }
+                let improves = match reached.get(new_key) {
+                    Some(earliest) => Some(effective_date) < *earliest,
+                    None => true,
+                };
+                if improves {
+                    reached.insert(new_key.clone(), Some(effective_date));
+                    next.push((new_key.clone(), path));
+                }
+            }
+        }
+        frontier = next;
+    }
+
+    Err(SonnunError::InvalidKey(
+        "Document key is not the trusted key and no valid rotation chain connects them".to_string(),
+    ))
+}
+
+// Accepts a JSON file holding either one signed rotation or an array of them
+pub fn load_rotations(path: &Path) -> Result<Vec<SignedRotation>, SonnunError> {
+    let contents = fs::read_to_string(path)
+        .map_err(|e| SonnunError::Io(format!("Failed to read rotations {}: {}", path.display(), e)))?;
+    let value: serde_json::Value = serde_json::from_str(&contents)
+        .map_err(|e| SonnunError::ManifestSchema(format!("Invalid rotations JSON: {}", e)))?;
+    let parsed = if value.is_array() {
+        serde_json::from_value(value)
+    } else {
+        serde_json::from_value(value).map(|rotation| vec![rotation])
+    };
+    parsed.map_err(|e| SonnunError::ManifestSchema(format!("Invalid rotation statement: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keystore::test_keys::public;
+    use rand::rngs::OsRng;
+
+    fn rotate(from: &SigningKey, to: &SigningKey, date: &str) -> SignedRotation {
+        RotationStatement::new(&public(from), &public(to), date).unwrap().sign(from).unwrap()
+    }
+
+    #[test]
+    fn test_chain_connects_trusted_key_to_successor() {
+        let keys: Vec<SigningKey> = (0..3).map(|_| SigningKey::generate(&mut OsRng)).collect();
+        let rotations = vec![
+            rotate(&keys[1], &keys[2], "2025-01-01T00:00:00Z"),
+            rotate(&keys[0], &keys[1], "2024-01-01T00:00:00Z"),
+        ];
+
+        let chain = find_rotation_chain(&public(&keys[0]), &public(&keys[2]), &rotations, None).unwrap();
+        assert_eq!(chain.len(), 2);
+        assert_eq!(chain[0].statement.new_key, public(&keys[1]));
+        assert!(find_rotation_chain(&public(&keys[0]), &public(&keys[0]), &[], None).unwrap().is_empty());
+
+        // Rotations only run forwards: the new key cannot vouch for the old one
+        let backwards = find_rotation_chain(&public(&keys[2]), &public(&keys[0]), &rotations, None);
+        assert_eq!(backwards.unwrap_err().code(), "INVALID_KEY");
+    }
+
+    #[test]
+    fn test_forged_statement_is_ignored() {
+        let trusted = SigningKey::generate(&mut OsRng);
+        let attacker = SigningKey::generate(&mut OsRng);
+        // A statement in the trusted key's name, signed by someone else
+        let statement = RotationStatement::new(&public(&trusted), &public(&attacker), "2024-01-01T00:00:00Z").unwrap();
+        let forged = SignedRotation {
+            signature: BASE64.encode(attacker.sign(&statement.signing_bytes()).to_bytes()),
+            statement: statement.clone(),
+        };
+
+        assert_eq!(forged.verify().unwrap_err().code(), "SIGNATURE_MISMATCH");
+        assert!(find_rotation_chain(&public(&trusted), &public(&attacker), &[forged], None).is_err());
+        assert!(statement.sign(&attacker).is_err());
+    }
+
+    #[test]
+    fn test_signing_before_effective_date_is_not_covered() {
+        let old = SigningKey::generate(&mut OsRng);
+        let new = SigningKey::generate(&mut OsRng);
+        let rotations = vec![rotate(&old, &new, "2025-06-01T00:00:00Z")];
+
+        let before = parse_timestamp("2025-05-31T23:59:59Z").unwrap();
+        let after = parse_timestamp("2025-06-01T00:00:00Z").unwrap();
+        assert!(find_rotation_chain(&public(&old), &public(&new), &rotations, Some(&before)).is_err());
+        assert!(find_rotation_chain(&public(&old), &public(&new), &rotations, Some(&after)).is_ok());
+    }
+
+    #[test]
+    fn test_key_reached_late_is_revisited_through_an_earlier_hand_over() {
+        let [trusted, direct, via, signer]: [SigningKey; 4] = std::array::from_fn(|_| SigningKey::generate(&mut OsRng));
+        // Breadth-first, `direct` is reached first with a 2025-03 hand-over, after which its 2024-06
+        // rotation to the signer looks backdated. The longer path reaches it in 2024-02 and is valid.
+        let rotations = vec![
+            rotate(&trusted, &direct, "2025-03-01T00:00:00Z"),
+            rotate(&trusted, &via, "2024-01-01T00:00:00Z"),
+            rotate(&via, &direct, "2024-02-01T00:00:00Z"),
+            rotate(&direct, &signer, "2024-06-01T00:00:00Z"),
+        ];
+
+        let chain = find_rotation_chain(&public(&trusted), &public(&signer), &rotations, None).unwrap();
+        let keys: Vec<&str> = chain.iter().map(|rotation| rotation.statement.new_key.as_str()).collect();
+        assert_eq!(keys, [public(&via), public(&direct), public(&signer)]);
+    }
+
+    #[test]
+    fn test_load_rotations_accepts_object_or_array() {
+        let old = SigningKey::generate(&mut OsRng);
+        let new = SigningKey::generate(&mut OsRng);
+        let rotation = rotate(&old, &new, "2025-01-01T00:00:00Z");
+        let path = std::env::temp_dir().join(format!("sonnun-rotations-{}.json", std::process::id()));
+
+        fs::write(&path, serde_json::to_string(&rotation).unwrap()).unwrap();
+        assert_eq!(load_rotations(&path).unwrap(), vec![rotation.clone()]);
+        fs::write(&path, serde_json::to_string(&vec![rotation.clone(), rotation]).unwrap()).unwrap();
+        assert_eq!(load_rotations(&path).unwrap().len(), 2);
+        fs::remove_file(path).unwrap();
+    }
+}
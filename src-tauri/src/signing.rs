@@ -0,0 +1,124 @@
+// AIDEV-NOTE: Manifest signing. Both the signer here and verifier.rs derive the signed bytes from
+// manifest_signing_bytes, so the two sides cannot drift apart.
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine as _;
+use serde_json::Value;
+
+use crate::algorithms::{KeystoreSigner, ManifestSigner, SIGNATURE_ALGORITHM_FIELD};
+use crate::error::SonnunError;
+use crate::jcs::canonicalize_bytes;
+use crate::keystore::Keystore;
+use crate::{ManifestSignature, SignatureRole, SignedManifest};
+
+pub fn manifest_signing_bytes(manifest: &Value) -> Result<Vec<u8>, SonnunError> {
+    if !manifest.is_object() {
+        return Err(SonnunError::ManifestSchema("Manifest must be a JSON object".to_string()));
+    }
+    canonicalize_bytes(manifest)
+}
+
+// The signer of a new manifest is its author; others join through cosign_manifest
+pub fn sign_manifest(keystore: &Keystore, key_id: &str, manifest: Value) -> Result<SignedManifest, SonnunError> {
+    sign_manifest_with(&KeystoreSigner::new(keystore, key_id)?, manifest)
+}
+
+// AIDEV-NOTE: The algorithm goes into the manifest before signing, so the signature covers it and a
+// verifier cannot be talked into checking the signature under another algorithm
+pub fn sign_manifest_with(signer: &dyn ManifestSigner, mut manifest: Value) -> Result<SignedManifest, SonnunError> {
+    let algorithm = signer.algorithm().as_str();
+    let fields = manifest
+        .as_object_mut()
+        .ok_or_else(|| SonnunError::ManifestSchema("Manifest must be a JSON object".to_string()))?;
+    match fields.get(SIGNATURE_ALGORITHM_FIELD) {
+        Some(Value::String(existing)) if existing == algorithm => {}
+        Some(existing) => {
+            return Err(SonnunError::InvalidInput(format!(
+                "Manifest names signature algorithm {} but the signing key is {}",
+                existing, algorithm
+            )))
+        }
+        None => {
+            fields.insert(SIGNATURE_ALGORITHM_FIELD.to_string(), Value::from(algorithm));
+        }
+    }
+    let signature = signer.sign(&manifest_signing_bytes(&manifest)?)?;
+    let public_key = signer.public_key()?;
+    Ok(SignedManifest {
+        manifest,
+        signatures: vec![ManifestSignature {
+            role: SignatureRole::Author,
+            public_key: public_key.clone(),
+            signature: signature.clone(),
+        }],
+        signature,
+        public_key,
+        timestamp: None,
+        transparency: None,
+        jws: None,
+    })
+}
+
+// AIDEV-NOTE: Manifests from before the signatures array get their top-level signer recorded as the
+// author first, so the array always lists everyone who signed
+pub fn cosign_manifest(
+    keystore: &Keystore,
+    key_id: &str,
+    role: SignatureRole,
+    signed: &mut SignedManifest,
+) -> Result<(), SonnunError> {
+    // The verifier checks co-signatures as ed25519 only
+    let public_key = BASE64.encode(keystore.ed25519_public_key(key_id)?.as_bytes());
+    if signed.signatures.iter().any(|existing| existing.public_key == public_key && existing.role == role) {
+        return Err(SonnunError::InvalidInput(format!("Key {} has already signed as {}", key_id, role.as_str())));
+    }
+    let signature = keystore.sign(key_id, &manifest_signing_bytes(&signed.manifest)?)?;
+    if signed.signatures.is_empty() {
+        signed.signatures.push(ManifestSignature {
+            role: SignatureRole::Author,
+            public_key: signed.public_key.clone(),
+            signature: signed.signature.clone(),
+        });
+    }
+    signed.signatures.push(ManifestSignature {
+        role,
+        public_key,
+        signature,
+    });
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keystore::test_keys::{test_keystore, unlocked_key};
+    use crate::verifier::{verify_html, VerifyOptions};
+    use serde_json::json;
+
+    #[test]
+    fn test_signed_manifest_verifies_whatever_the_key_order() {
+        let (keystore, dir) = test_keystore("signing");
+        let key = unlocked_key(&keystore, "Author");
+
+        let manifest = json!({
+            "total_characters": 100,
+            "human_characters": 70,
+            "ai_characters": 20,
+            "cited_characters": 10,
+            "human_percentage": 70.0,
+        });
+        let signed = sign_manifest(&keystore, &key.key_id, manifest).unwrap();
+
+        // A JS producer would write keys in insertion order and 70.0 as 70
+        let embedded = format!(
+            r#"{{"public_key":"{}","signature":"{}","manifest":{{"human_percentage":70,"signature_algorithm":"ed25519","cited_characters":10,"ai_characters":20,"human_characters":70,"total_characters":100}}}}"#,
+            signed.public_key, signed.signature
+        );
+        let html = format!(r#"<script type="application/json" id="sonnun-manifest">{}</script>"#, embedded);
+        let options = VerifyOptions {
+            allow_unbound_content: true,
+            ..Default::default()
+        };
+        verify_html(&html, &options).unwrap();
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
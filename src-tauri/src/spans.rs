@@ -0,0 +1,337 @@
+// AIDEV-NOTE: Authoritative provenance model - every character of a document tagged with the event
+// type that wrote it. Manifest percentages come from here, never from frontend-reported counts.
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::SonnunError;
+use crate::{EventType, Operation, ProvenanceEvent};
+
+// Leaves are split once they hold more spans than this, keeping edits local to one small Vec
+const MAX_LEAF_SPANS: usize = 64;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Span {
+    pub event_type: EventType,
+    pub len: usize,
+}
+
+#[derive(Debug, Clone, Default)]
+struct Leaf {
+    spans: Vec<Span>,
+    len: usize,
+}
+
+impl Leaf {
+    fn recount(&mut self) {
+        self.len = self.spans.iter().map(|span| span.len).sum();
+    }
+}
+
+// AIDEV-NOTE: Two-level rope: an ordered list of bounded leaves, each a run-length list of spans.
+// Adjacent spans of the same type are merged on insert. There is always at least one leaf.
+#[derive(Debug, Clone)]
+pub struct SpanRope {
+    leaves: Vec<Leaf>,
+    len: usize,
+    counts: HashMap<EventType, usize>,
+}
+
+impl Default for SpanRope {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SpanRope {
+    pub fn new() -> Self {
+        Self {
+            leaves: vec![Leaf::default()],
+            len: 0,
+            counts: HashMap::new(),
+        }
+    }
+
+    // AIDEV-NOTE: Rebuilds a document by replaying its events in log order
+    pub fn from_events<'a>(
+        events: impl IntoIterator<Item = &'a ProvenanceEvent>,
+    ) -> Result<Self, SonnunError> {
+        let mut rope = Self::new();
+        for event in events {
+            rope.apply(event)?;
+        }
+        Ok(rope)
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    // Surviving characters attributed to one event type
+    pub fn count(&self, event_type: EventType) -> usize {
+        self.counts.get(&event_type).copied().unwrap_or(0)
+    }
+
+    pub fn counts(&self) -> &HashMap<EventType, usize> {
+        &self.counts
+    }
+
+    pub fn spans(&self) -> impl Iterator<Item = &Span> {
+        self.leaves.iter().flat_map(|leaf| leaf.spans.iter())
+    }
+
+    // AIDEV-NOTE: Bounds are checked before anything is touched, so a rejected event leaves the rope
+    // unchanged. Positionless (legacy) inserts append to the end.
+    pub fn apply(&mut self, event: &ProvenanceEvent) -> Result<(), SonnunError> {
+        let from = event.from.unwrap_or(self.len);
+        let to = match event.operation {
+            Operation::Insert => from,
+            Operation::Delete | Operation::Replace => event.to.unwrap_or(from),
+        };
+        if from > to || to > self.len {
+            return Err(SonnunError::InvalidInput(format!(
+                "{} at {}..{} is outside the document ({} characters)",
+                event.operation.as_str(),
+                from,
+                to,
+                self.len
+            )));
+        }
+
+        if event.operation != Operation::Insert {
+            self.delete(from, to)?;
+        }
+        if event.operation != Operation::Delete {
+            self.insert(from, event.event_type, event.span_length)?;
+        }
+        Ok(())
+    }
+
+    pub fn insert(&mut self, at: usize, event_type: EventType, len: usize) -> Result<(), SonnunError> {
+        self.check_range(at, at)?;
+        if len == 0 {
+            return Ok(());
+        }
+
+        let (li, si) = self.boundary(at);
+        let leaf = &mut self.leaves[li];
+        if si > 0 && leaf.spans[si - 1].event_type == event_type {
+            leaf.spans[si - 1].len += len;
+            // Inserting inside a span of the same type: rejoin the halves boundary() split apart
+            if si < leaf.spans.len() && leaf.spans[si].event_type == event_type {
+                leaf.spans[si - 1].len += leaf.spans.remove(si).len;
+            }
+        } else if si < leaf.spans.len() && leaf.spans[si].event_type == event_type {
+            leaf.spans[si].len += len;
+        } else {
+            leaf.spans.insert(si, Span { event_type, len });
+        }
+        leaf.len += len;
+
+        if leaf.spans.len() > MAX_LEAF_SPANS {
+            let mut tail = Leaf {
+                spans: leaf.spans.split_off(leaf.spans.len() / 2),
+                len: 0,
+            };
+            tail.recount();
+            leaf.recount();
+            self.leaves.insert(li + 1, tail);
+        }
+
+        self.len += len;
+        *self.counts.entry(event_type).or_default() += len;
+        Ok(())
+    }
+
+    pub fn delete(&mut self, from: usize, to: usize) -> Result<(), SonnunError> {
+        self.check_range(from, to)?;
+        if from == to {
+            return Ok(());
+        }
+
+        // Split the start first: splitting at the later offset never shifts the earlier boundary
+        let (start_leaf, start_span) = self.boundary(from);
+        let (end_leaf, end_span) = self.boundary(to);
+
+        let mut removed = Vec::new();
+        if start_leaf == end_leaf {
+            removed.extend(self.leaves[start_leaf].spans.drain(start_span..end_span));
+        } else {
+            removed.extend(self.leaves[end_leaf].spans.drain(..end_span));
+            for leaf in self.leaves.drain(start_leaf + 1..end_leaf) {
+                removed.extend(leaf.spans);
+            }
+            removed.extend(self.leaves[start_leaf].spans.drain(start_span..));
+        }
+
+        for span in removed {
+            self.len -= span.len;
+            if let Some(count) = self.counts.get_mut(&span.event_type) {
+                *count -= span.len;
+                if *count == 0 {
+                    self.counts.remove(&span.event_type);
+                }
+            }
+        }
+
+        self.leaves.iter_mut().for_each(Leaf::recount);
+        self.leaves.retain(|leaf| !leaf.spans.is_empty());
+        if self.leaves.is_empty() {
+            self.leaves.push(Leaf::default());
+        }
+        Ok(())
+    }
+
+    fn check_range(&self, from: usize, to: usize) -> Result<(), SonnunError> {
+        if from > to || to > self.len {
+            return Err(SonnunError::InvalidInput(format!(
+                "Range {}..{} is outside the document ({} characters)",
+                from, to, self.len
+            )));
+        }
+        Ok(())
+    }
+
+    // Splits the span containing `pos` so a span starts exactly there; returns (leaf, span) indices.
+    // Callers have already checked pos <= len.
+    fn boundary(&mut self, pos: usize) -> (usize, usize) {
+        let mut offset = 0;
+        let last = self.leaves.len() - 1;
+        for (li, leaf) in self.leaves.iter_mut().enumerate() {
+            if pos >= offset + leaf.len && li < last {
+                offset += leaf.len;
+                continue;
+            }
+            for si in 0..leaf.spans.len() {
+                if offset == pos {
+                    return (li, si);
+                }
+                let span = leaf.spans[si];
+                if pos < offset + span.len {
+                    leaf.spans[si].len = pos - offset;
+                    leaf.spans.insert(
+                        si + 1,
+                        Span {
+                            event_type: span.event_type,
+                            len: offset + span.len - pos,
+                        },
+                    );
+                    return (li, si + 1);
+                }
+                offset += span.len;
+            }
+            return (li, leaf.spans.len());
+        }
+        unreachable!("SpanRope always has at least one leaf")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(event_type: EventType, operation: Operation, from: usize, to: usize, span_length: usize) -> ProvenanceEvent {
+        ProvenanceEvent {
+            document_id: 1,
+            timestamp: "2023-01-01T00:00:00Z".to_string(),
+            event_type,
+            text_hash: "hash".to_string(),
+            source: "test".to_string(),
+            span_length,
+            operation,
+            from: Some(from),
+            to: Some(to),
+            text: None,
+        }
+    }
+
+    fn layout(rope: &SpanRope) -> Vec<(EventType, usize)> {
+        rope.spans().map(|span| (span.event_type, span.len)).collect()
+    }
+
+    #[test]
+    fn test_insert_splits_and_merges_spans() {
+        let mut rope = SpanRope::new();
+        rope.insert(0, EventType::Human, 10).unwrap();
+        rope.insert(5, EventType::Ai, 4).unwrap();
+        rope.insert(9, EventType::Human, 1).unwrap();
+
+        assert_eq!(
+            layout(&rope),
+            vec![(EventType::Human, 5), (EventType::Ai, 4), (EventType::Human, 6)]
+        );
+        assert_eq!(rope.len(), 15);
+        assert_eq!(rope.count(EventType::Human), 11);
+        assert_eq!(rope.count(EventType::Ai), 4);
+    }
+
+    #[test]
+    fn test_delete_removes_text_across_spans() {
+        let mut rope = SpanRope::new();
+        rope.insert(0, EventType::Human, 10).unwrap();
+        rope.insert(10, EventType::Ai, 10).unwrap();
+        rope.insert(20, EventType::Cited, 10).unwrap();
+
+        rope.delete(5, 25).unwrap();
+        assert_eq!(layout(&rope), vec![(EventType::Human, 5), (EventType::Cited, 5)]);
+        assert_eq!(rope.count(EventType::Ai), 0);
+        assert!(!rope.counts().contains_key(&EventType::Ai));
+
+        rope.delete(0, 10).unwrap();
+        assert!(rope.is_empty());
+        rope.insert(0, EventType::Human, 3).unwrap();
+        assert_eq!(rope.len(), 3);
+    }
+
+    #[test]
+    fn test_apply_replace_and_legacy_append() {
+        let mut rope = SpanRope::new();
+        let mut legacy = event(EventType::Ai, Operation::Insert, 0, 0, 40);
+        legacy.from = None;
+        legacy.to = None;
+        rope.apply(&legacy).unwrap();
+        rope.apply(&legacy).unwrap();
+        assert_eq!(rope.len(), 80);
+
+        rope.apply(&event(EventType::Human, Operation::Replace, 10, 70, 5)).unwrap();
+        assert_eq!(
+            layout(&rope),
+            vec![(EventType::Ai, 10), (EventType::Human, 5), (EventType::Ai, 10)]
+        );
+    }
+
+    #[test]
+    fn test_apply_rejects_out_of_range_without_changing_rope() {
+        let mut rope = SpanRope::new();
+        rope.insert(0, EventType::Human, 10).unwrap();
+
+        let result = rope.apply(&event(EventType::Human, Operation::Replace, 5, 15, 3));
+        assert_eq!(result.unwrap_err().code(), "INVALID_INPUT");
+        assert!(rope.apply(&event(EventType::Ai, Operation::Insert, 11, 11, 3)).is_err());
+        assert_eq!(layout(&rope), vec![(EventType::Human, 10)]);
+    }
+
+    #[test]
+    fn test_many_edits_split_leaves_and_keep_totals() {
+        let mut rope = SpanRope::new();
+        let types = [EventType::Human, EventType::Ai, EventType::Cited];
+        // Alternate types so nothing merges and leaves must split
+        for i in 0..500 {
+            rope.insert(rope.len() / 2, types[i % 3], 2).unwrap();
+        }
+        assert!(rope.leaves.len() > 1);
+        assert_eq!(rope.len(), 1000);
+
+        rope.delete(100, 900).unwrap();
+        assert_eq!(rope.len(), 200);
+        let span_total: usize = rope.spans().map(|span| span.len).sum();
+        let count_total: usize = rope.counts().values().sum();
+        assert_eq!(span_total, 200);
+        assert_eq!(count_total, 200);
+        assert!(rope.leaves.iter().all(|leaf| leaf.len == leaf.spans.iter().map(|s| s.len).sum::<usize>()));
+    }
+}
@@ -0,0 +1,698 @@
+// AIDEV-NOTE: RFC 3161 trusted timestamps. A TSA countersigns the SHA-256 of the primary manifest
+// signature, which proves the signature existed no later than the token's genTime - so a stolen key
+// cannot produce documents that look older than its revocation. The token is DER (a CMS SignedData
+// wrapping TSTInfo) kept base64 in SignedManifest.timestamp, outside the signed manifest itself.
+// Trust comes from TSA certificates the verifier supplies; a token is always checked for integrity,
+// but only counts as trusted when its signer chains to one of them.
+use std::path::Path;
+use std::time::Duration;
+
+use chrono::{DateTime, NaiveDateTime, Utc};
+use cms::cert::CertificateChoices;
+use cms::content_info::ContentInfo;
+use cms::signed_data::{SignedData, SignerIdentifier, SignerInfo};
+use der::asn1::{Any, BitString, Int, ObjectIdentifier, OctetString, Uint};
+use der::{Decode, Encode, Sequence, Tagged};
+use rand::RngCore;
+use serde::Serialize;
+use sha2::{Digest, Sha256, Sha384, Sha512};
+use spki::{AlgorithmIdentifierOwned, SubjectPublicKeyInfoOwned};
+use x509_cert::ext::pkix::name::GeneralName;
+use x509_cert::ext::pkix::{BasicConstraints, ExtendedKeyUsage, SubjectKeyIdentifier};
+use x509_cert::ext::Extensions;
+use x509_cert::Certificate;
+
+use crate::error::SonnunError;
+
+pub const TSA_URL_ENV: &str = "SONNUN_TSA_URL";
+
+const ID_SIGNED_DATA: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.7.2");
+const ID_CT_TST_INFO: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.9.16.1.4");
+const ID_CONTENT_TYPE: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.9.3");
+const ID_MESSAGE_DIGEST: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.9.4");
+const ID_KP_TIME_STAMPING: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.3.6.1.5.5.7.3.8");
+const ID_SHA256: ObjectIdentifier = ObjectIdentifier::new_unwrap("2.16.840.1.101.3.4.2.1");
+const ID_SHA384: ObjectIdentifier = ObjectIdentifier::new_unwrap("2.16.840.1.101.3.4.2.2");
+const ID_SHA512: ObjectIdentifier = ObjectIdentifier::new_unwrap("2.16.840.1.101.3.4.2.3");
+const ID_RSA_ENCRYPTION: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.1.1");
+const ID_SHA256_WITH_RSA: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.1.11");
+const ID_SHA384_WITH_RSA: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.1.12");
+const ID_SHA512_WITH_RSA: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.1.13");
+const ID_ECDSA_WITH_SHA256: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.10045.4.3.2");
+const ID_ED25519: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.3.101.112");
+
+// Intermediate certificates followed from the TSA certificate towards a trusted one
+const MAX_CHAIN_LENGTH: usize = 4;
+
+#[derive(Clone, Debug, Eq, PartialEq, Sequence)]
+struct MessageImprint {
+    hash_algorithm: AlgorithmIdentifierOwned,
+    hashed_message: OctetString,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Sequence)]
+struct TimeStampReq {
+    version: u8,
+    message_imprint: MessageImprint,
+    #[asn1(optional = "true")]
+    req_policy: Option<ObjectIdentifier>,
+    #[asn1(optional = "true")]
+    nonce: Option<Int>,
+    #[asn1(default = "Default::default")]
+    cert_req: bool,
+    #[asn1(context_specific = "0", tag_mode = "IMPLICIT", optional = "true")]
+    extensions: Option<Extensions>,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Sequence)]
+struct PkiStatusInfo {
+    status: u32, // 0 granted, 1 granted with modifications, anything else is a rejection
+    #[asn1(optional = "true")]
+    status_string: Option<Vec<String>>,
+    #[asn1(optional = "true")]
+    fail_info: Option<BitString>,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Sequence)]
+struct TimeStampResp {
+    status: PkiStatusInfo,
+    #[asn1(optional = "true")]
+    time_stamp_token: Option<ContentInfo>,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Sequence)]
+struct Accuracy {
+    #[asn1(optional = "true")]
+    seconds: Option<u32>,
+    #[asn1(context_specific = "0", tag_mode = "IMPLICIT", optional = "true")]
+    millis: Option<u16>,
+    #[asn1(context_specific = "1", tag_mode = "IMPLICIT", optional = "true")]
+    micros: Option<u16>,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Sequence)]
+struct TstInfo {
+    version: u8,
+    policy: ObjectIdentifier,
+    message_imprint: MessageImprint,
+    serial_number: Int,
+    // GeneralizedTime, kept raw: TSAs commonly add fractional seconds, which der's type rejects
+    gen_time: Any,
+    #[asn1(optional = "true")]
+    accuracy: Option<Accuracy>,
+    #[asn1(default = "Default::default")]
+    ordering: bool,
+    #[asn1(optional = "true")]
+    nonce: Option<Int>,
+    #[asn1(context_specific = "0", tag_mode = "EXPLICIT", optional = "true")]
+    tsa: Option<GeneralName>,
+    #[asn1(context_specific = "1", tag_mode = "IMPLICIT", optional = "true")]
+    extensions: Option<Extensions>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct VerifiedTimestamp {
+    pub gen_time: DateTime<Utc>,
+    pub tsa: String,   // subject of the certificate that signed the token
+    pub trusted: bool, // signer is, or chains to, one of the supplied TSA certificates
+}
+
+// AIDEV-NOTE: Asks the TSA for a token over `signature` (the raw 64 signature bytes, not base64).
+// The response is checked - nonce, imprint and token signature - before it is handed back.
+pub async fn request_timestamp(tsa_url: &str, signature: &[u8]) -> Result<Vec<u8>, SonnunError> {
+    let mut nonce_bytes = [0u8; 8];
+    rand::thread_rng().fill_bytes(&mut nonce_bytes);
+    let nonce = Int::from(Uint::new(&nonce_bytes).map_err(asn1_error)?);
+    let request = TimeStampReq {
+        version: 1,
+        message_imprint: MessageImprint {
+            hash_algorithm: AlgorithmIdentifierOwned { oid: ID_SHA256, parameters: None },
+            hashed_message: OctetString::new(Sha256::digest(signature).to_vec()).map_err(asn1_error)?,
+        },
+        req_policy: None,
+        nonce: Some(nonce.clone()),
+        cert_req: true,
+        extensions: None,
+    };
+
+    let response = reqwest::Client::new()
+        .post(tsa_url)
+        .header("Content-Type", "application/timestamp-query")
+        .body(request.to_der().map_err(asn1_error)?)
+        .send()
+        .await
+        .map_err(|e| SonnunError::Timestamp(format!("TSA request failed: {}", e)))?;
+    if !response.status().is_success() {
+        return Err(SonnunError::Timestamp(format!("TSA returned HTTP {}", response.status())));
+    }
+    let body = response
+        .bytes()
+        .await
+        .map_err(|e| SonnunError::Timestamp(format!("Failed to read TSA response: {}", e)))?;
+
+    let response = TimeStampResp::from_der(&body).map_err(asn1_error)?;
+    if response.status.status > 1 {
+        let reason = response.status.status_string.unwrap_or_default().join("; ");
+        return Err(SonnunError::Timestamp(format!(
+            "TSA rejected the request (status {}): {}",
+            response.status.status, reason
+        )));
+    }
+    let token = response
+        .time_stamp_token
+        .ok_or_else(|| SonnunError::Timestamp("TSA granted the request but sent no token".to_string()))?
+        .to_der()
+        .map_err(asn1_error)?;
+
+    let (tst_info, _) = read_token(&token)?;
+    if tst_info.nonce.as_ref() != Some(&nonce) {
+        return Err(SonnunError::Timestamp("TSA response nonce does not match the request".to_string()));
+    }
+    validate_timestamp(&token, signature, &[])?;
+    Ok(token)
+}
+
+// AIDEV-NOTE: Checks, in order: the token imprint is the hash of `signature`, the CMS signed
+// attributes bind the TSTInfo, the signer certificate's signature over them, the certificate is a
+// timestamping certificate valid at genTime, and finally whether it chains to `trusted`.
+pub fn validate_timestamp(
+    token: &[u8],
+    signature: &[u8],
+    trusted: &[Certificate],
+) -> Result<VerifiedTimestamp, SonnunError> {
+    let (tst_info, signed_data) = read_token(token)?;
+    if tst_info.version != 1 {
+        return Err(SonnunError::Timestamp(format!("Unsupported TSTInfo version {}", tst_info.version)));
+    }
+    let imprint = &tst_info.message_imprint;
+    if digest(&imprint.hash_algorithm.oid, signature)? != imprint.hashed_message.as_bytes() {
+        return Err(SonnunError::Timestamp("Timestamp token was issued for a different signature".to_string()));
+    }
+    let gen_time = parse_generalized_time(&tst_info.gen_time)?;
+
+    let signer_info = match signed_data.signer_infos.0.as_slice() {
+        [signer_info] => signer_info,
+        _ => return Err(SonnunError::Timestamp("Timestamp token must have exactly one signer".to_string())),
+    };
+    let tst_der = signed_data
+        .encap_content_info
+        .econtent
+        .as_ref()
+        .ok_or_else(|| SonnunError::Timestamp("Timestamp token has no TSTInfo".to_string()))?
+        .decode_as::<OctetString>()
+        .map_err(asn1_error)?;
+    let signed_attributes = check_signed_attributes(signer_info, tst_der.as_bytes())?;
+
+    let embedded: Vec<Certificate> = signed_data
+        .certificates
+        .iter()
+        .flat_map(|set| set.0.iter())
+        .filter_map(|choice| match choice {
+            CertificateChoices::Certificate(certificate) => Some(certificate.clone()),
+            CertificateChoices::Other(_) => None,
+        })
+        .collect();
+    let certificate = embedded
+        .iter()
+        .chain(trusted)
+        .find(|certificate| identifies(&signer_info.sid, certificate))
+        .ok_or_else(|| SonnunError::Timestamp("Timestamp signer certificate not found".to_string()))?;
+
+    verify_signed(
+        &certificate.tbs_certificate.subject_public_key_info,
+        &signer_info.signature_algorithm.oid,
+        Some(&signer_info.digest_alg.oid),
+        &signed_attributes,
+        signer_info.signature.as_bytes(),
+    )
+    .map_err(|_| SonnunError::Timestamp("Timestamp token signature is invalid".to_string()))?;
+
+    if !is_timestamping_certificate(certificate)? {
+        return Err(SonnunError::Timestamp(
+            "Timestamp signer certificate is not authorised for timestamping".to_string(),
+        ));
+    }
+    if !valid_at(certificate, &gen_time) {
+        return Err(SonnunError::Timestamp(
+            "Timestamp genTime is outside the signer certificate's validity".to_string(),
+        ));
+    }
+
+    Ok(VerifiedTimestamp {
+        gen_time,
+        tsa: certificate.tbs_certificate.subject.to_string(),
+        trusted: chains_to_trusted(certificate, &embedded, trusted, &gen_time),
+    })
+}
+
+// A PEM bundle (one or more CERTIFICATE blocks) or a single DER certificate
+pub fn load_tsa_certificates(path: &Path) -> Result<Vec<Certificate>, SonnunError> {
+    let bytes = std::fs::read(path)
+        .map_err(|e| SonnunError::Io(format!("Failed to read TSA certificate {}: {}", path.display(), e)))?;
+    let certificates = if bytes.starts_with(b"-----BEGIN") {
+        Certificate::load_pem_chain(&bytes)
+    } else {
+        Certificate::from_der(&bytes).map(|certificate| vec![certificate])
+    };
+    certificates.map_err(|e| SonnunError::InvalidInput(format!("Invalid TSA certificate {}: {}", path.display(), e)))
+}
+
+fn read_token(token: &[u8]) -> Result<(TstInfo, SignedData), SonnunError> {
+    let content_info = ContentInfo::from_der(token).map_err(asn1_error)?;
+    if content_info.content_type != ID_SIGNED_DATA {
+        return Err(SonnunError::Timestamp("Timestamp token is not CMS SignedData".to_string()));
+    }
+    let signed_data: SignedData = content_info.content.decode_as().map_err(asn1_error)?;
+    if signed_data.encap_content_info.econtent_type != ID_CT_TST_INFO {
+        return Err(SonnunError::Timestamp("Timestamp token does not carry a TSTInfo".to_string()));
+    }
+    let tst_der = signed_data
+        .encap_content_info
+        .econtent
+        .as_ref()
+        .ok_or_else(|| SonnunError::Timestamp("Timestamp token has no TSTInfo".to_string()))?
+        .decode_as::<OctetString>()
+        .map_err(asn1_error)?;
+    let tst_info = TstInfo::from_der(tst_der.as_bytes()).map_err(asn1_error)?;
+    Ok((tst_info, signed_data))
+}
+
+// RFC 5652 5.4: with signed attributes present the signature covers their DER SET encoding, and
+// the messageDigest attribute is what ties that signature to the TSTInfo
+fn check_signed_attributes(signer_info: &SignerInfo, tst_der: &[u8]) -> Result<Vec<u8>, SonnunError> {
+    let attributes = signer_info
+        .signed_attrs
+        .as_ref()
+        .ok_or_else(|| SonnunError::Timestamp("Timestamp token has no signed attributes".to_string()))?;
+    let single_value = |oid: ObjectIdentifier| {
+        attributes
+            .iter()
+            .find(|attribute| attribute.oid == oid)
+            .and_then(|attribute| match attribute.values.as_slice() {
+                [value] => Some(value),
+                _ => None,
+            })
+            .ok_or_else(|| SonnunError::Timestamp(format!("Timestamp token lacks signed attribute {}", oid)))
+    };
+
+    let content_type: ObjectIdentifier = single_value(ID_CONTENT_TYPE)?.decode_as().map_err(asn1_error)?;
+    if content_type != ID_CT_TST_INFO {
+        return Err(SonnunError::Timestamp("Signed content type is not TSTInfo".to_string()));
+    }
+    let message_digest: OctetString = single_value(ID_MESSAGE_DIGEST)?.decode_as().map_err(asn1_error)?;
+    if digest(&signer_info.digest_alg.oid, tst_der)? != message_digest.as_bytes() {
+        return Err(SonnunError::Timestamp("Timestamp token TSTInfo has been altered".to_string()));
+    }
+    attributes.to_der().map_err(asn1_error)
+}
+
+fn identifies(sid: &SignerIdentifier, certificate: &Certificate) -> bool {
+    match sid {
+        SignerIdentifier::IssuerAndSerialNumber(id) => {
+            id.issuer == certificate.tbs_certificate.issuer && id.serial_number == certificate.tbs_certificate.serial_number
+        }
+        SignerIdentifier::SubjectKeyIdentifier(key_id) => matches!(
+            certificate.tbs_certificate.get::<SubjectKeyIdentifier>(),
+            Ok(Some((_, ref own))) if own == key_id
+        ),
+    }
+}
+
+// RFC 3161 2.3: the TSA certificate's extended key usage is exactly id-kp-timeStamping, marked critical
+fn is_timestamping_certificate(certificate: &Certificate) -> Result<bool, SonnunError> {
+    let usage = certificate.tbs_certificate.get::<ExtendedKeyUsage>().map_err(asn1_error)?;
+    Ok(matches!(usage, Some((true, ExtendedKeyUsage(ref purposes))) if purposes.as_slice() == [ID_KP_TIME_STAMPING]))
+}
+
+fn valid_at(certificate: &Certificate, time: &DateTime<Utc>) -> bool {
+    let validity = &certificate.tbs_certificate.validity;
+    let seconds = |duration: Duration| duration.as_secs() as i64;
+    let at = time.timestamp();
+    seconds(validity.not_before.to_unix_duration()) <= at && at <= seconds(validity.not_after.to_unix_duration())
+}
+
+// AIDEV-NOTE: Path building is deliberately small - issuer name plus signature, CA flag on
+// intermediates, validity at genTime. The trusted certificates are pinned anchors, not a system store.
+fn chains_to_trusted(
+    certificate: &Certificate,
+    intermediates: &[Certificate],
+    trusted: &[Certificate],
+    at: &DateTime<Utc>,
+) -> bool {
+    let mut current = certificate;
+    for _ in 0..=MAX_CHAIN_LENGTH {
+        if trusted.iter().any(|anchor| anchor == current || issued_by(current, anchor)) {
+            return true;
+        }
+        let issuer = intermediates.iter().find(|candidate| {
+            *candidate != current && is_ca(candidate) && valid_at(candidate, at) && issued_by(current, candidate)
+        });
+        match issuer {
+            Some(issuer) => current = issuer,
+            None => return false,
+        }
+    }
+    false
+}
+
+fn issued_by(certificate: &Certificate, issuer: &Certificate) -> bool {
+    if certificate.tbs_certificate.issuer != issuer.tbs_certificate.subject {
+        return false;
+    }
+    let Ok(tbs) = certificate.tbs_certificate.to_der() else {
+        return false;
+    };
+    verify_signed(
+        &issuer.tbs_certificate.subject_public_key_info,
+        &certificate.signature_algorithm.oid,
+        None,
+        &tbs,
+        certificate.signature.raw_bytes(),
+    )
+    .is_ok()
+}
+
+fn is_ca(certificate: &Certificate) -> bool {
+    matches!(certificate.tbs_certificate.get::<BasicConstraints>(), Ok(Some((_, constraints))) if constraints.ca)
+}
+
+// AIDEV-NOTE: The algorithms TSAs actually sign with: RSA PKCS#1 v1.5, ECDSA P-256 and Ed25519.
+// `digest_algorithm` is the CMS digestAlgorithm, needed when the signature algorithm is bare rsaEncryption.
+fn verify_signed(
+    key: &SubjectPublicKeyInfoOwned,
+    algorithm: &ObjectIdentifier,
+    digest_algorithm: Option<&ObjectIdentifier>,
+    message: &[u8],
+    signature: &[u8],
+) -> Result<(), SonnunError> {
+    let invalid = |e: String| SonnunError::Timestamp(format!("Signature check failed: {}", e));
+    let key_bytes = key.subject_public_key.raw_bytes();
+    match *algorithm {
+        ID_ED25519 => {
+            use ed25519_dalek::Verifier;
+            let key_bytes: [u8; 32] = key_bytes.try_into().map_err(|_| invalid("bad ed25519 key".to_string()))?;
+            let key = ed25519_dalek::VerifyingKey::from_bytes(&key_bytes).map_err(|e| invalid(e.to_string()))?;
+            let signature = ed25519_dalek::Signature::from_slice(signature).map_err(|e| invalid(e.to_string()))?;
+            key.verify(message, &signature).map_err(|e| invalid(e.to_string()))
+        }
+        ID_ECDSA_WITH_SHA256 => {
+            use p256::ecdsa::signature::Verifier;
+            let key = p256::ecdsa::VerifyingKey::from_sec1_bytes(key_bytes).map_err(|e| invalid(e.to_string()))?;
+            let signature = p256::ecdsa::Signature::from_der(signature).map_err(|e| invalid(e.to_string()))?;
+            key.verify(message, &signature).map_err(|e| invalid(e.to_string()))
+        }
+        ID_RSA_ENCRYPTION | ID_SHA256_WITH_RSA | ID_SHA384_WITH_RSA | ID_SHA512_WITH_RSA => {
+            use rsa::pkcs8::DecodePublicKey;
+            let hash_algorithm = match *algorithm {
+                ID_SHA256_WITH_RSA => ID_SHA256,
+                ID_SHA384_WITH_RSA => ID_SHA384,
+                ID_SHA512_WITH_RSA => ID_SHA512,
+                _ => *digest_algorithm.ok_or_else(|| invalid("rsaEncryption without a digest".to_string()))?,
+            };
+            let scheme = match hash_algorithm {
+                ID_SHA256 => rsa::Pkcs1v15Sign::new::<Sha256>(),
+                ID_SHA384 => rsa::Pkcs1v15Sign::new::<Sha384>(),
+                ID_SHA512 => rsa::Pkcs1v15Sign::new::<Sha512>(),
+                other => return Err(invalid(format!("unsupported RSA digest {}", other))),
+            };
+            let key = rsa::RsaPublicKey::from_public_key_der(&key.to_der().map_err(asn1_error)?)
+                .map_err(|e| invalid(e.to_string()))?;
+            key.verify(scheme, &digest(&hash_algorithm, message)?, signature)
+                .map_err(|e| invalid(e.to_string()))
+        }
+        other => Err(invalid(format!("unsupported signature algorithm {}", other))),
+    }
+}
+
+fn digest(algorithm: &ObjectIdentifier, data: &[u8]) -> Result<Vec<u8>, SonnunError> {
+    match *algorithm {
+        ID_SHA256 => Ok(Sha256::digest(data).to_vec()),
+        ID_SHA384 => Ok(Sha384::digest(data).to_vec()),
+        ID_SHA512 => Ok(Sha512::digest(data).to_vec()),
+        other => Err(SonnunError::Timestamp(format!("Unsupported digest algorithm {}", other))),
+    }
+}
+
+fn parse_generalized_time(value: &Any) -> Result<DateTime<Utc>, SonnunError> {
+    let invalid = || SonnunError::Timestamp("Timestamp genTime is not a valid GeneralizedTime".to_string());
+    if value.tag() != der::Tag::GeneralizedTime {
+        return Err(invalid());
+    }
+    let text = std::str::from_utf8(value.value()).map_err(|_| invalid())?;
+    let text = text.strip_suffix('Z').ok_or_else(invalid)?;
+    NaiveDateTime::parse_from_str(text, "%Y%m%d%H%M%S%.f")
+        .map(|naive| naive.and_utc())
+        .map_err(|_| invalid())
+}
+
+fn asn1_error(e: der::Error) -> SonnunError {
+    SonnunError::Timestamp(format!("Malformed timestamp data: {}", e))
+}
+
+// AIDEV-NOTE: A local TSA for tests: an Ed25519 timestamping certificate and an HTTP endpoint that
+// answers timestamp queries the way a real TSA does.
+#[cfg(test)]
+pub(crate) mod test_tsa {
+    use std::str::FromStr;
+    use std::sync::Arc;
+
+    use cms::cert::IssuerAndSerialNumber;
+    use cms::content_info::CmsVersion;
+    use cms::signed_data::{CertificateSet, EncapsulatedContentInfo, SignerInfos};
+    use der::asn1::{SetOfVec, UtcTime};
+    use der::oid::AssociatedOid;
+    use ed25519_dalek::pkcs8::EncodePublicKey;
+    use ed25519_dalek::{Signer, SigningKey};
+    use rand::rngs::OsRng;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use x509_cert::attr::Attribute;
+    use x509_cert::certificate::{TbsCertificate, Version};
+    use x509_cert::ext::Extension;
+    use x509_cert::name::Name;
+    use x509_cert::serial_number::SerialNumber;
+    use x509_cert::time::{Time, Validity};
+
+    use super::*;
+
+    pub(crate) struct TestTsa {
+        key: SigningKey,
+        pub certificate: Certificate,
+    }
+
+    fn ed25519() -> AlgorithmIdentifierOwned {
+        AlgorithmIdentifierOwned { oid: ID_ED25519, parameters: None }
+    }
+
+    fn sha256() -> AlgorithmIdentifierOwned {
+        AlgorithmIdentifierOwned { oid: ID_SHA256, parameters: None }
+    }
+
+    impl TestTsa {
+        pub fn new(name: &str) -> Self {
+            let key = SigningKey::generate(&mut OsRng);
+            let name = Name::from_str(&format!("CN={}", name)).unwrap();
+            let usage = ExtendedKeyUsage(vec![ID_KP_TIME_STAMPING]);
+            let tbs_certificate = TbsCertificate {
+                version: Version::V3,
+                serial_number: SerialNumber::new(&[1]).unwrap(),
+                signature: ed25519(),
+                issuer: name.clone(),
+                validity: Validity {
+                    // 2020-01-01 to 2049-12-31, so fixed genTimes in tests fall inside it
+                    not_before: Time::UtcTime(UtcTime::from_unix_duration(Duration::from_secs(1_577_836_800)).unwrap()),
+                    not_after: Time::UtcTime(UtcTime::from_unix_duration(Duration::from_secs(2_524_607_999)).unwrap()),
+                },
+                subject: name,
+                subject_public_key_info: SubjectPublicKeyInfoOwned::from_der(
+                    key.verifying_key().to_public_key_der().unwrap().as_bytes(),
+                )
+                .unwrap(),
+                issuer_unique_id: None,
+                subject_unique_id: None,
+                extensions: Some(vec![Extension {
+                    extn_id: ExtendedKeyUsage::OID,
+                    critical: true,
+                    extn_value: OctetString::new(usage.to_der().unwrap()).unwrap(),
+                }]),
+            };
+            let signature = key.sign(&tbs_certificate.to_der().unwrap());
+            let certificate = Certificate {
+                tbs_certificate,
+                signature_algorithm: ed25519(),
+                signature: BitString::from_bytes(&signature.to_bytes()).unwrap(),
+            };
+            TestTsa { key, certificate }
+        }
+
+        pub fn token(&self, signature: &[u8], nonce: Option<Int>, gen_time: DateTime<Utc>) -> Vec<u8> {
+            self.token_for_imprint(Sha256::digest(signature).to_vec(), nonce, gen_time)
+        }
+
+        fn token_for_imprint(&self, imprint: Vec<u8>, nonce: Option<Int>, gen_time: DateTime<Utc>) -> Vec<u8> {
+            let gen_time = gen_time.format("%Y%m%d%H%M%SZ").to_string();
+            let tst_info = TstInfo {
+                version: 1,
+                policy: ObjectIdentifier::new_unwrap("1.3.6.1.4.1.99999.1"),
+                message_imprint: MessageImprint {
+                    hash_algorithm: sha256(),
+                    hashed_message: OctetString::new(imprint).unwrap(),
+                },
+                serial_number: Int::new(&[7]).unwrap(),
+                gen_time: Any::new(der::Tag::GeneralizedTime, gen_time.as_bytes()).unwrap(),
+                accuracy: None,
+                ordering: false,
+                nonce,
+                tsa: None,
+                extensions: None,
+            };
+            let tst_der = tst_info.to_der().unwrap();
+
+            let attribute = |oid, value: Any| Attribute { oid, values: SetOfVec::try_from(vec![value]).unwrap() };
+            let signed_attrs = SetOfVec::try_from(vec![
+                attribute(ID_CONTENT_TYPE, Any::encode_from(&ID_CT_TST_INFO).unwrap()),
+                attribute(
+                    ID_MESSAGE_DIGEST,
+                    Any::encode_from(&OctetString::new(Sha256::digest(&tst_der).to_vec()).unwrap()).unwrap(),
+                ),
+            ])
+            .unwrap();
+            let signature = self.key.sign(&signed_attrs.to_der().unwrap());
+            let signer_info = SignerInfo {
+                version: CmsVersion::V1,
+                sid: SignerIdentifier::IssuerAndSerialNumber(IssuerAndSerialNumber {
+                    issuer: self.certificate.tbs_certificate.issuer.clone(),
+                    serial_number: self.certificate.tbs_certificate.serial_number.clone(),
+                }),
+                digest_alg: sha256(),
+                signed_attrs: Some(signed_attrs),
+                signature_algorithm: ed25519(),
+                signature: OctetString::new(signature.to_bytes().to_vec()).unwrap(),
+                unsigned_attrs: None,
+            };
+            let signed_data = SignedData {
+                version: CmsVersion::V3,
+                digest_algorithms: SetOfVec::try_from(vec![sha256()]).unwrap(),
+                encap_content_info: EncapsulatedContentInfo {
+                    econtent_type: ID_CT_TST_INFO,
+                    econtent: Some(Any::new(der::Tag::OctetString, tst_der).unwrap()),
+                },
+                certificates: Some(CertificateSet(
+                    SetOfVec::try_from(vec![CertificateChoices::Certificate(self.certificate.clone())]).unwrap(),
+                )),
+                crls: None,
+                signer_infos: SignerInfos(SetOfVec::try_from(vec![signer_info]).unwrap()),
+            };
+            ContentInfo { content_type: ID_SIGNED_DATA, content: Any::encode_from(&signed_data).unwrap() }
+                .to_der()
+                .unwrap()
+        }
+
+        fn respond(&self, request: &[u8]) -> Vec<u8> {
+            let request = TimeStampReq::from_der(request).unwrap();
+            let token = self.token_for_imprint(
+                request.message_imprint.hashed_message.as_bytes().to_vec(),
+                request.nonce,
+                Utc::now(),
+            );
+            TimeStampResp {
+                status: PkiStatusInfo { status: 0, status_string: None, fail_info: None },
+                time_stamp_token: Some(ContentInfo::from_der(&token).unwrap()),
+            }
+            .to_der()
+            .unwrap()
+        }
+
+        // Serves POST requests on a random local port until the test runtime shuts down
+        pub async fn serve(self) -> String {
+            let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let url = format!("http://{}/tsa", listener.local_addr().unwrap());
+            let tsa = Arc::new(self);
+            tokio::spawn(async move {
+                while let Ok((mut stream, _)) = listener.accept().await {
+                    let tsa = tsa.clone();
+                    tokio::spawn(async move {
+                        let mut received = Vec::new();
+                        let mut buffer = [0u8; 4096];
+                        let body_start = loop {
+                            let read = stream.read(&mut buffer).await.unwrap();
+                            received.extend_from_slice(&buffer[..read]);
+                            if let Some(end) = received.windows(4).position(|window| window == b"\r\n\r\n") {
+                                break end + 4;
+                            }
+                        };
+                        let headers = String::from_utf8_lossy(&received[..body_start]).to_ascii_lowercase();
+                        let length: usize = headers
+                            .lines()
+                            .find_map(|line| line.strip_prefix("content-length:"))
+                            .map(|value| value.trim().parse().unwrap())
+                            .unwrap_or(0);
+                        while received.len() < body_start + length {
+                            let read = stream.read(&mut buffer).await.unwrap();
+                            received.extend_from_slice(&buffer[..read]);
+                        }
+                        let reply = tsa.respond(&received[body_start..body_start + length]);
+                        let head = format!(
+                            "HTTP/1.1 200 OK\r\nContent-Type: application/timestamp-reply\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                            reply.len()
+                        );
+                        stream.write_all(head.as_bytes()).await.unwrap();
+                        stream.write_all(&reply).await.unwrap();
+                    });
+                }
+            });
+            url
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::test_tsa::TestTsa;
+    use super::*;
+
+    #[tokio::test]
+    async fn test_token_from_local_tsa_validates() {
+        let tsa = TestTsa::new("Sonnun Test TSA");
+        let certificate = tsa.certificate.clone();
+        let url = tsa.serve().await;
+        let signature = [42u8; 64];
+
+        let token = request_timestamp(&url, &signature).await.unwrap();
+        let verified = validate_timestamp(&token, &signature, std::slice::from_ref(&certificate)).unwrap();
+        assert!(verified.trusted);
+        assert_eq!(verified.tsa, "CN=Sonnun Test TSA");
+        assert!((Utc::now() - verified.gen_time).num_seconds().abs() < 60);
+
+        // Integrity holds without anchors, but trust needs the TSA certificate
+        assert!(!validate_timestamp(&token, &signature, &[]).unwrap().trusted);
+        let stranger = TestTsa::new("Other TSA").certificate;
+        assert!(!validate_timestamp(&token, &signature, &[stranger]).unwrap().trusted);
+
+        let error = validate_timestamp(&token, &[0u8; 64], &[certificate]).unwrap_err();
+        assert_eq!(error.code(), "TIMESTAMP");
+    }
+
+    #[test]
+    fn test_altered_gen_time_is_rejected() {
+        let tsa = TestTsa::new("Sonnun Test TSA");
+        let signature = [7u8; 64];
+        let gen_time = "2025-03-01T12:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        let token = tsa.token(&signature, None, gen_time);
+        let verified = validate_timestamp(&token, &signature, std::slice::from_ref(&tsa.certificate)).unwrap();
+        assert_eq!(verified.gen_time, gen_time);
+
+        // Moving genTime a year back breaks the messageDigest signed attribute
+        let at = token.windows(15).position(|window| window == b"20250301120000Z").unwrap();
+        let mut backdated = token.clone();
+        backdated[at + 3] = b'4';
+        let error = validate_timestamp(&backdated, &signature, &[tsa.certificate]).unwrap_err();
+        assert_eq!(error.code(), "TIMESTAMP");
+    }
+
+    #[test]
+    fn test_fractional_gen_time_parses() {
+        let value = Any::new(der::Tag::GeneralizedTime, b"20250301120000.125Z".to_vec()).unwrap();
+        let parsed = parse_generalized_time(&value).unwrap();
+        assert_eq!(parsed.timestamp_subsec_millis(), 125);
+    }
+}
@@ -0,0 +1,667 @@
+// AIDEV-NOTE: Append-only transparency log in the style of RFC 6962 (Certificate Transparency).
+// Every published manifest becomes a leaf - its RFC 8785 bytes, the same bytes the author signed - and
+// the log signs tree heads over the Merkle root with a key of its own, never an author's key. Documents carry an inclusion proof against a signed
+// tree head; any two tree heads can be checked for consistency, so a reader holding an older head can
+// tell whether history was rewritten (say, an altered manifest swapped in for one already published).
+// The log file keeps leaf hashes only, one JSON line per entry, and is only ever appended to.
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
+
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine as _;
+use ed25519_dalek::{Signer, SigningKey, Verifier};
+use rand::rngs::OsRng;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::crypto_utils::{parse_public_key, parse_signature};
+use crate::error::SonnunError;
+use crate::jcs::canonicalize_bytes;
+use crate::keystore::write_private_file;
+use crate::replay::parse_timestamp;
+use crate::verifier::extract_signed_manifest;
+
+const TREE_HEAD_VERSION: u32 = 1;
+// Domain separation: a tree head signature can never be replayed as a document signature
+const TREE_HEAD_DOMAIN: &str = "sonnun-tree-head-v1\n";
+// RFC 6962 2.1: leaves and interior nodes hash under different prefixes (second-preimage resistance)
+const LEAF_PREFIX: u8 = 0x00;
+const NODE_PREFIX: u8 = 0x01;
+
+pub type Hash = [u8; 32];
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TreeHead {
+    pub version: u32,
+    pub log_key: String,   // base64 ed25519 public key the log signs tree heads with
+    pub tree_size: u64,
+    pub timestamp: String, // RFC 3339
+    pub root_hash: String, // base64 Merkle tree hash of the first tree_size leaves
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SignedTreeHead {
+    pub tree_head: TreeHead,
+    pub signature: String, // base64 ed25519 signature by log_key over signing_bytes()
+}
+
+// Embedded next to the signature in a SignedManifest, like a timestamp token
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InclusionProof {
+    pub leaf_index: u64,
+    pub audit_path: Vec<String>, // base64 sibling hashes, leaf level first
+    pub tree_head: SignedTreeHead,
+}
+
+// What the app hands out so readers can link the tree heads in two documents
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConsistencyProof {
+    pub first_size: u64,
+    pub second_size: u64,
+    pub proof: Vec<String>, // base64 hashes
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct LogEntry {
+    index: u64,
+    leaf_hash: String,
+    logged_at: String,
+}
+
+impl TreeHead {
+    // JCS (RFC 8785), so a reader re-serializing the head in another language gets the same bytes
+    pub fn signing_bytes(&self) -> Vec<u8> {
+        let value = serde_json::to_value(self).expect("tree head always serializes");
+        let mut bytes = TREE_HEAD_DOMAIN.as_bytes().to_vec();
+        bytes.extend(canonicalize_bytes(&value).expect("tree head has no non-finite numbers"));
+        bytes
+    }
+
+    pub fn root(&self) -> Result<Hash, SonnunError> {
+        decode_hash(&self.root_hash)
+    }
+}
+
+impl SignedTreeHead {
+    pub fn verify(&self) -> Result<(), SonnunError> {
+        if self.tree_head.version != TREE_HEAD_VERSION {
+            return Err(SonnunError::ManifestSchema(format!(
+                "Unsupported tree head version {}",
+                self.tree_head.version
+            )));
+        }
+        parse_timestamp(&self.tree_head.timestamp)?;
+        self.tree_head.root()?;
+        let verifying_key = parse_public_key(&self.tree_head.log_key)?;
+        let signature = parse_signature(&self.signature)?;
+        verifying_key
+            .verify(&self.tree_head.signing_bytes(), &signature)
+            .map_err(|_| SonnunError::SignatureMismatch("Tree head signature does not match".to_string()))
+    }
+}
+
+impl InclusionProof {
+    // `entry` is the logged leaf input: the manifest's signing bytes
+    pub fn verify(&self, entry: &[u8]) -> Result<(), SonnunError> {
+        self.tree_head.verify()?;
+        let path = decode_hashes(&self.audit_path)?;
+        verify_inclusion(
+            &leaf_hash(entry),
+            self.leaf_index,
+            self.tree_head.tree_head.tree_size,
+            &path,
+            &self.tree_head.tree_head.root()?,
+        )
+    }
+}
+
+// AIDEV-NOTE: Both heads must come from the same log key and verify on their own before the proof
+// linking them means anything
+pub fn verify_tree_heads(
+    first: &SignedTreeHead,
+    second: &SignedTreeHead,
+    proof: &ConsistencyProof,
+) -> Result<(), SonnunError> {
+    first.verify()?;
+    second.verify()?;
+    if first.tree_head.log_key != second.tree_head.log_key {
+        return Err(SonnunError::Transparency("Tree heads were signed by different logs".to_string()));
+    }
+    if (proof.first_size, proof.second_size) != (first.tree_head.tree_size, second.tree_head.tree_size) {
+        return Err(SonnunError::Transparency(format!(
+            "Proof is for sizes {} -> {} but the tree heads have {} -> {}",
+            proof.first_size, proof.second_size, first.tree_head.tree_size, second.tree_head.tree_size
+        )));
+    }
+    verify_consistency(
+        proof.first_size,
+        proof.second_size,
+        &first.tree_head.root()?,
+        &second.tree_head.root()?,
+        &decode_hashes(&proof.proof)?,
+    )
+}
+
+// A signed tree head on its own, or a Sonnun document whose manifest carries one
+pub fn read_tree_head(text: &str) -> Result<SignedTreeHead, SonnunError> {
+    if let Ok(tree_head) = serde_json::from_str::<SignedTreeHead>(text) {
+        return Ok(tree_head);
+    }
+    let signed_manifest = extract_signed_manifest(text)?;
+    let proof = signed_manifest
+        .get("transparency")
+        .ok_or_else(|| SonnunError::Transparency("Document carries no transparency log proof".to_string()))?;
+    let proof: InclusionProof = serde_json::from_value(proof.clone())
+        .map_err(|e| SonnunError::ManifestSchema(format!("Invalid transparency proof: {}", e)))?;
+    Ok(proof.tree_head)
+}
+
+// AIDEV-NOTE: The log key is generated on first use and kept owner-only next to the log file (the
+// ".key" sibling of its path). It signs nothing but tree heads, so readers pin it with --log-key
+// independently of whichever author keys the logged documents were signed with.
+pub struct TransparencyLog {
+    path: PathBuf,
+    key_path: PathBuf,
+    // Read from the file once; appends and the proofs built right after them must see the same tree
+    tree: Mutex<Option<MerkleTree>>,
+}
+
+impl TransparencyLog {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        Self {
+            key_path: path.with_extension("key"),
+            path,
+            tree: Mutex::new(None),
+        }
+    }
+
+    // Appends the entry and returns its proof against a tree head signed at the new size
+    pub fn append(&self, entry: &[u8]) -> Result<InclusionProof, SonnunError> {
+        let mut guard = self.lock();
+        let log_key = self.log_key()?;
+        let tree = self.tree(&mut guard)?;
+        let leaf = leaf_hash(entry);
+        let record = LogEntry {
+            index: tree.len() as u64,
+            leaf_hash: BASE64.encode(leaf),
+            logged_at: chrono::Utc::now().to_rfc3339(),
+        };
+
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let line = serde_json::to_string(&record)
+            .map_err(|e| SonnunError::Io(format!("Failed to serialize log entry: {}", e)))?;
+        let written = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .and_then(|mut file| {
+                writeln!(file, "{}", line)?;
+                file.sync_data()
+            });
+        if let Err(e) = written {
+            // A partial line may have reached the file; read it again rather than trust the cache
+            *guard = None;
+            return Err(e.into());
+        }
+        tree.push(leaf);
+
+        Ok(InclusionProof {
+            leaf_index: record.index,
+            audit_path: encode_hashes(&tree.inclusion_path(record.index as usize, tree.len())),
+            tree_head: sign_tree_head(&log_key, tree),
+        })
+    }
+
+    pub fn tree_head(&self) -> Result<SignedTreeHead, SonnunError> {
+        let mut guard = self.lock();
+        let log_key = self.log_key()?;
+        Ok(sign_tree_head(&log_key, self.tree(&mut guard)?))
+    }
+
+    // The key readers pass to sonnun-verify --log-key
+    pub fn public_key(&self) -> Result<String, SonnunError> {
+        let _guard = self.lock();
+        Ok(BASE64.encode(self.log_key()?.verifying_key().to_bytes()))
+    }
+
+    pub fn consistency_proof(&self, first_size: u64, second_size: u64) -> Result<ConsistencyProof, SonnunError> {
+        let mut guard = self.lock();
+        let tree = self.tree(&mut guard)?;
+        if first_size == 0 || first_size > second_size || second_size > tree.len() as u64 {
+            return Err(SonnunError::InvalidInput(format!(
+                "Cannot prove {} -> {} on a log of {} entries",
+                first_size,
+                second_size,
+                tree.len()
+            )));
+        }
+        Ok(ConsistencyProof {
+            first_size,
+            second_size,
+            proof: encode_hashes(&tree.consistency_path(first_size as usize, second_size as usize)),
+        })
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Option<MerkleTree>> {
+        // A panic mid-append leaves the file as the source of truth, so drop the cache and reload
+        self.tree.lock().unwrap_or_else(|poisoned| {
+            let mut guard = poisoned.into_inner();
+            *guard = None;
+            guard
+        })
+    }
+
+    fn tree<'a>(&self, guard: &'a mut Option<MerkleTree>) -> Result<&'a mut MerkleTree, SonnunError> {
+        if guard.is_none() {
+            *guard = Some(MerkleTree::from_leaves(self.read_leaf_hashes()?));
+        }
+        Ok(guard.as_mut().expect("tree was just loaded"))
+    }
+
+    // Called with the lock held, so two first appends cannot both generate a key
+    fn log_key(&self) -> Result<SigningKey, SonnunError> {
+        if !self.key_path.exists() {
+            if let Some(parent) = self.key_path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            let key = SigningKey::generate(&mut OsRng);
+            write_private_file(&self.key_path, BASE64.encode(key.to_bytes()).as_bytes())?;
+            return Ok(key);
+        }
+        let encoded = fs::read_to_string(&self.key_path)
+            .map_err(|e| SonnunError::Io(format!("Failed to read log key {}: {}", self.key_path.display(), e)))?;
+        let bytes: [u8; 32] = BASE64
+            .decode(encoded.trim())
+            .ok()
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or_else(|| SonnunError::InvalidKey(format!("Corrupt log key {}", self.key_path.display())))?;
+        Ok(SigningKey::from_bytes(&bytes))
+    }
+
+    // Entries must be numbered 0, 1, 2, ... - a gap or reordering means the file was edited
+    fn read_leaf_hashes(&self) -> Result<Vec<Hash>, SonnunError> {
+        if !self.path.exists() {
+            return Ok(Vec::new());
+        }
+        let contents = fs::read_to_string(&self.path)
+            .map_err(|e| SonnunError::Io(format!("Failed to read transparency log {}: {}", self.path.display(), e)))?;
+        let mut leaves = Vec::new();
+        for line in contents.lines().filter(|line| !line.trim().is_empty()) {
+            let entry: LogEntry = serde_json::from_str(line)
+                .map_err(|e| SonnunError::Transparency(format!("Corrupt log entry: {}", e)))?;
+            if entry.index != leaves.len() as u64 {
+                return Err(SonnunError::Transparency(format!(
+                    "Log entry {} found where {} was expected",
+                    entry.index,
+                    leaves.len()
+                )));
+            }
+            leaves.push(decode_hash(&entry.leaf_hash)?);
+        }
+        Ok(leaves)
+    }
+}
+
+fn sign_tree_head(log_key: &SigningKey, tree: &MerkleTree) -> SignedTreeHead {
+    let tree_head = TreeHead {
+        version: TREE_HEAD_VERSION,
+        log_key: BASE64.encode(log_key.verifying_key().to_bytes()),
+        tree_size: tree.len() as u64,
+        timestamp: chrono::Utc::now().to_rfc3339(),
+        root_hash: BASE64.encode(tree.root(tree.len())),
+    };
+    let signature = BASE64.encode(log_key.sign(&tree_head.signing_bytes()).to_bytes());
+    SignedTreeHead { tree_head, signature }
+}
+
+pub fn leaf_hash(entry: &[u8]) -> Hash {
+    let mut hasher = Sha256::new();
+    hasher.update([LEAF_PREFIX]);
+    hasher.update(entry);
+    hasher.finalize().into()
+}
+
+fn node_hash(left: &Hash, right: &Hash) -> Hash {
+    let mut hasher = Sha256::new();
+    hasher.update([NODE_PREFIX]);
+    hasher.update(left);
+    hasher.update(right);
+    hasher.finalize().into()
+}
+
+// Largest power of two strictly below n (n > 1)
+fn split_point(n: usize) -> usize {
+    let mut k = 1;
+    while k << 1 < n {
+        k <<= 1;
+    }
+    k
+}
+
+// AIDEV-NOTE: levels[h][i] is the hash of the complete subtree of 2^h leaves starting at leaf i * 2^h.
+// Every subtree RFC 6962 splits a tree into is either one of those or runs along the right edge, so
+// appends and proofs touch O(log n) stored nodes instead of rehashing every leaf.
+#[derive(Debug, Default)]
+struct MerkleTree {
+    levels: Vec<Vec<Hash>>,
+}
+
+impl MerkleTree {
+    fn from_leaves(leaves: Vec<Hash>) -> Self {
+        let mut tree = Self::default();
+        for leaf in leaves {
+            tree.push(leaf);
+        }
+        tree
+    }
+
+    fn len(&self) -> usize {
+        self.levels.first().map_or(0, Vec::len)
+    }
+
+    fn push(&mut self, leaf: Hash) {
+        let mut node = leaf;
+        let mut height = 0;
+        loop {
+            if self.levels.len() == height {
+                self.levels.push(Vec::new());
+            }
+            let level = &mut self.levels[height];
+            level.push(node);
+            if level.len() % 2 == 1 {
+                break;
+            }
+            node = node_hash(&level[level.len() - 2], &level[level.len() - 1]);
+            height += 1;
+        }
+    }
+
+    // RFC 6962 2.1: MTH over leaves [start, start + size); the empty tree hashes to SHA-256 of nothing
+    fn subtree_root(&self, start: usize, size: usize) -> Hash {
+        match size {
+            0 => Sha256::digest([]).into(),
+            _ if size.is_power_of_two() && start % size == 0 => self.levels[size.trailing_zeros() as usize][start / size],
+            _ => {
+                let k = split_point(size);
+                node_hash(&self.subtree_root(start, k), &self.subtree_root(start + k, size - k))
+            }
+        }
+    }
+
+    // Root of the tree made of the first `size` leaves
+    fn root(&self, size: usize) -> Hash {
+        self.subtree_root(0, size)
+    }
+
+    // RFC 6962 2.1.1 PATH(m, D[n]) for the first `size` leaves
+    fn inclusion_path(&self, index: usize, size: usize) -> Vec<Hash> {
+        fn path(tree: &MerkleTree, start: usize, size: usize, index: usize) -> Vec<Hash> {
+            if size <= 1 {
+                return Vec::new();
+            }
+            let k = split_point(size);
+            if index < k {
+                let mut path = path(tree, start, k, index);
+                path.push(tree.subtree_root(start + k, size - k));
+                path
+            } else {
+                let mut path = path(tree, start + k, size - k, index - k);
+                path.push(tree.subtree_root(start, k));
+                path
+            }
+        }
+        path(self, 0, size, index)
+    }
+
+    // RFC 6962 2.1.2 PROOF(m, D[n]) = SUBPROOF(m, D[n], true)
+    fn consistency_path(&self, first_size: usize, second_size: usize) -> Vec<Hash> {
+        fn subproof(tree: &MerkleTree, start: usize, n: usize, m: usize, complete_subtree: bool) -> Vec<Hash> {
+            if m == n {
+                return if complete_subtree { Vec::new() } else { vec![tree.subtree_root(start, n)] };
+            }
+            let k = split_point(n);
+            if m <= k {
+                let mut proof = subproof(tree, start, k, m, complete_subtree);
+                proof.push(tree.subtree_root(start + k, n - k));
+                proof
+            } else {
+                let mut proof = subproof(tree, start + k, n - k, m - k, false);
+                proof.push(tree.subtree_root(start, k));
+                proof
+            }
+        }
+        subproof(self, 0, second_size, first_size, true)
+    }
+}
+
+// RFC 9162 2.1.3.2
+pub fn verify_inclusion(leaf: &Hash, index: u64, tree_size: u64, path: &[Hash], root: &Hash) -> Result<(), SonnunError> {
+    let fail = || SonnunError::Transparency(format!("Entry {} is not included in the tree of size {}", index, tree_size));
+    if index >= tree_size {
+        return Err(fail());
+    }
+    let (mut f_n, mut s_n) = (index, tree_size - 1);
+    let mut r = *leaf;
+    for p in path {
+        if s_n == 0 {
+            return Err(fail());
+        }
+        if f_n & 1 == 1 || f_n == s_n {
+            r = node_hash(p, &r);
+            while f_n & 1 == 0 && f_n != 0 {
+                f_n >>= 1;
+                s_n >>= 1;
+            }
+        } else {
+            r = node_hash(&r, p);
+        }
+        f_n >>= 1;
+        s_n >>= 1;
+    }
+    if s_n == 0 && r == *root {
+        Ok(())
+    } else {
+        Err(fail())
+    }
+}
+
+// RFC 9162 2.1.4.2
+pub fn verify_consistency(
+    first_size: u64,
+    second_size: u64,
+    first_root: &Hash,
+    second_root: &Hash,
+    proof: &[Hash],
+) -> Result<(), SonnunError> {
+    let fail = || {
+        SonnunError::Transparency(format!(
+            "Tree of size {} is not a prefix of the tree of size {}",
+            first_size, second_size
+        ))
+    };
+    if first_size == 0 || first_size > second_size {
+        return Err(fail());
+    }
+    if first_size == second_size {
+        return if proof.is_empty() && first_root == second_root { Ok(()) } else { Err(fail()) };
+    }
+
+    let mut path = proof.to_vec();
+    if first_size.is_power_of_two() {
+        path.insert(0, *first_root);
+    }
+    let (head, rest) = path.split_first().ok_or_else(fail)?;
+    let (mut f_n, mut s_n) = (first_size - 1, second_size - 1);
+    while f_n & 1 == 1 {
+        f_n >>= 1;
+        s_n >>= 1;
+    }
+    let (mut f_r, mut s_r) = (*head, *head);
+    for c in rest {
+        if s_n == 0 {
+            return Err(fail());
+        }
+        if f_n & 1 == 1 || f_n == s_n {
+            f_r = node_hash(c, &f_r);
+            s_r = node_hash(c, &s_r);
+            while f_n & 1 == 0 && f_n != 0 {
+                f_n >>= 1;
+                s_n >>= 1;
+            }
+        } else {
+            s_r = node_hash(&s_r, c);
+        }
+        f_n >>= 1;
+        s_n >>= 1;
+    }
+    if f_r == *first_root && s_r == *second_root && s_n == 0 {
+        Ok(())
+    } else {
+        Err(fail())
+    }
+}
+
+pub fn load_consistency_proof(path: &Path) -> Result<ConsistencyProof, SonnunError> {
+    let contents = fs::read_to_string(path)
+        .map_err(|e| SonnunError::Io(format!("Failed to read consistency proof {}: {}", path.display(), e)))?;
+    serde_json::from_str(&contents).map_err(|e| SonnunError::ManifestSchema(format!("Invalid consistency proof: {}", e)))
+}
+
+fn decode_hash(encoded: &str) -> Result<Hash, SonnunError> {
+    BASE64
+        .decode(encoded)
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| SonnunError::ManifestSchema(format!("Invalid log hash '{}'", encoded)))
+}
+
+fn decode_hashes(encoded: &[String]) -> Result<Vec<Hash>, SonnunError> {
+    encoded.iter().map(|hash| decode_hash(hash)).collect()
+}
+
+fn encode_hashes(hashes: &[Hash]) -> Vec<String> {
+    hashes.iter().map(|hash| BASE64.encode(hash)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex(hash: &Hash) -> String {
+        hash.iter().map(|byte| format!("{:02x}", byte)).collect()
+    }
+
+    // Leaf inputs from the Certificate Transparency reference test data
+    fn reference_leaves() -> Vec<Hash> {
+        let inputs: [&[u8]; 8] = [
+            b"",
+            &[0x00],
+            &[0x10],
+            &[0x20, 0x21],
+            &[0x30, 0x31],
+            &[0x40, 0x41, 0x42, 0x43],
+            &[0x50, 0x51, 0x52, 0x53, 0x54, 0x55, 0x56, 0x57],
+            &[0x60, 0x61, 0x62, 0x63, 0x64, 0x65, 0x66, 0x67, 0x68, 0x69, 0x6a, 0x6b, 0x6c, 0x6d, 0x6e, 0x6f],
+        ];
+        inputs.iter().map(|input| leaf_hash(input)).collect()
+    }
+
+    #[test]
+    fn test_reference_roots() {
+        let expected = [
+            "6e340b9cffb37a989ca544e6bb780a2c78901d3fb33738768511a30617afa01d",
+            "fac54203e7cc696cf0dfcb42c92a1d9dbaf70ad9e621f4bd8d98662f00e3c125",
+            "aeb6bcfe274b70a14fb067a5e5578264db0fa9b51af5e0ba159158f329e06e77",
+            "d37ee418976dd95753c1c73862b9398fa2a2cf9b4ff0fdfe8b30cd95209614b7",
+            "4e3bbb1f7b478dcfe71fb631631519a3bca12c9aefca1612bfce4c13a86264d4",
+            "76e67dadbcdf1e10e1b74ddc608abd2f98dfb16fbce75277b5232a127f2087ef",
+            "ddb89be403809e325750d3d263cd78929c2942b7942a34b77e122c9594a74c8c",
+            "5dc9da79a70659a9ad559cb701ded9a2ab9d823aad2f4960cfe370eff4604328",
+        ];
+        let tree = MerkleTree::from_leaves(reference_leaves());
+        for (size, root) in expected.iter().enumerate() {
+            assert_eq!(hex(&tree.root(size + 1)), *root, "size {}", size + 1);
+        }
+        assert_eq!(
+            hex(&tree.root(0)),
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+    }
+
+    #[test]
+    fn test_every_inclusion_and_consistency_proof_verifies() {
+        let leaves: Vec<Hash> = (0u8..13).map(|i| leaf_hash(&[i])).collect();
+        let tree = MerkleTree::from_leaves(leaves.clone());
+        for size in 1..=leaves.len() {
+            let root = tree.root(size);
+            for index in 0..size {
+                let path = tree.inclusion_path(index, size);
+                verify_inclusion(&leaves[index], index as u64, size as u64, &path, &root).unwrap();
+                // The same path does not prove a different leaf or position
+                assert!(verify_inclusion(&leaf_hash(b"other"), index as u64, size as u64, &path, &root).is_err());
+                if size > 1 {
+                    let moved = (index + 1) % size;
+                    assert!(verify_inclusion(&leaves[index], moved as u64, size as u64, &path, &root).is_err());
+                }
+            }
+            for first in 1..=size {
+                let proof = tree.consistency_path(first, size);
+                verify_consistency(first as u64, size as u64, &tree.root(first), &root, &proof).unwrap();
+            }
+        }
+    }
+
+    #[test]
+    fn test_rewritten_history_fails_consistency() {
+        let mut leaves: Vec<Hash> = (0u8..6).map(|i| leaf_hash(&[i])).collect();
+        let old_root = MerkleTree::from_leaves(leaves.clone()).root(4);
+        // Entry 2 quietly replaced with an altered manifest, then the log grows
+        leaves[2] = leaf_hash(b"altered");
+        let rewritten = MerkleTree::from_leaves(leaves);
+        let proof = rewritten.consistency_path(4, 6);
+        let error = verify_consistency(4, 6, &old_root, &rewritten.root(6), &proof).unwrap_err();
+        assert_eq!(error.code(), "TRANSPARENCY");
+    }
+
+    #[test]
+    fn test_log_appends_and_proves() {
+        let dir = std::env::temp_dir().join(format!("sonnun-log-{}", std::process::id()));
+        let log = TransparencyLog::new(dir.join("transparency.jsonl"));
+
+        let first = log.append(b"manifest one").unwrap();
+        first.verify(b"manifest one").unwrap();
+        for i in 0..4 {
+            log.append(format!("manifest {}", i).as_bytes()).unwrap();
+        }
+        let latest = log.tree_head().unwrap();
+        assert_eq!(latest.tree_head.tree_size, 5);
+        assert_eq!(latest.tree_head.log_key, log.public_key().unwrap());
+        assert!(first.verify(b"manifest two").is_err());
+        // Signed in JCS form: keys sorted, whatever order the struct declares them in
+        let signed = String::from_utf8(latest.tree_head.signing_bytes()).unwrap();
+        assert!(signed.starts_with(&format!("{}{{\"log_key\":", TREE_HEAD_DOMAIN)));
+
+        let proof = log.consistency_proof(1, 5).unwrap();
+        verify_tree_heads(&first.tree_head, &latest, &proof).unwrap();
+        assert!(verify_tree_heads(&latest, &first.tree_head, &proof).is_err());
+        assert!(log.consistency_proof(2, 9).is_err());
+
+        // A fresh handle reads the same entries and signs with the same key
+        let reopened = TransparencyLog::new(dir.join("transparency.jsonl"));
+        let reread = reopened.tree_head().unwrap();
+        assert_eq!(reread.tree_head.root_hash, latest.tree_head.root_hash);
+        assert_eq!(reread.tree_head.log_key, latest.tree_head.log_key);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(dir.join("transparency.key")).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,916 @@
+// AIDEV-NOTE: Signed-document verification shared by sonnun-verify and the library tests. The binary
+// only parses arguments and maps errors to exit codes; every acceptance rule lives here.
+use std::ops::Range;
+use std::str::FromStr;
+
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine as _;
+use chrono::{DateTime, Duration, FixedOffset};
+use ed25519_dalek::{Signature, Verifier, VerifyingKey};
+use regex::Regex;
+use serde::Serialize;
+use serde_json::Value;
+use x509_cert::Certificate;
+
+use crate::algorithms::{decode_signature, manifest_algorithm, signature_verifier};
+use crate::c2pa::{read_c2pa_manifest, C2paManifest};
+use crate::crypto_utils::{parse_public_key, parse_signature};
+use crate::error::SonnunError;
+use crate::export::{content_hash, document_content_hash, CONTENT_HASH_FIELD};
+use crate::jws::verify_detached_jws;
+use crate::key_formats::{did_key, normalize_public_key, parse_did_key};
+use crate::keystore::key_fingerprint;
+use crate::replay::parse_timestamp;
+use crate::revocation::{check_revocation, SignedRevocationList};
+use crate::rotation::{find_rotation_chain, SignedRotation};
+use crate::signing::manifest_signing_bytes;
+use crate::timestamp::{validate_timestamp, VerifiedTimestamp};
+use crate::transparency::InclusionProof;
+use crate::{SignatureAlgorithm, SignatureRole, SignedManifest};
+
+#[derive(Debug, Clone, Default)]
+pub struct VerifyOptions {
+    pub trusted_key: Option<String>,    // key the reader trusts, in any form key_formats parses; None accepts the embedded key
+    pub rotations: Vec<SignedRotation>, // statements that may connect trusted_key to the signing key
+    pub revocations: Vec<SignedRevocationList>,
+    pub allow_unbound_content: bool, // accept manifests from before content_hash existed
+    pub required_signers: Vec<RequiredSigner>,
+    pub trusted_signers: Vec<String>, // keys the reader accepts in any role; a bare --require role needs one of them
+    pub tsa_certificates: Vec<Certificate>, // TSAs whose RFC 3161 tokens are trusted
+    pub require_timestamp: bool,
+    pub log_key: Option<String>, // when set, the manifest must be in the transparency log signed by this key
+    pub c2pa_manifest: Option<Vec<u8>>, // .c2pa manifest store to check against the page
+}
+
+// How far generated_at may run ahead of a trusted genTime before the two are treated as inconsistent
+const MAX_CLOCK_SKEW_MINUTES: i64 = 5;
+
+// AIDEV-NOTE: One clause of a signing policy: a key the reader trusts signed in `role`. With
+// `public_key` set (any key_formats form) it must be that key; otherwise any trusted signer will do.
+// Written on the command line as "editor" or "editor=<key>".
+#[derive(Debug, Clone, PartialEq)]
+pub struct RequiredSigner {
+    pub role: SignatureRole,
+    pub public_key: Option<String>,
+}
+
+impl FromStr for RequiredSigner {
+    type Err = SonnunError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // Split at the first '=' only - base64 keys end in '=' padding
+        let (role, public_key) = match s.split_once('=') {
+            Some((role, key)) => (role, Some(key.trim().to_string())),
+            None => (s, None),
+        };
+        Ok(RequiredSigner {
+            role: role.trim().parse()?,
+            public_key,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct VerifiedSigner {
+    pub role: SignatureRole,
+    pub public_key: String,
+    pub trusted: bool, // false when the role is only the signer's own claim
+}
+
+// AIDEV-NOTE: What the signed content_hash was checked against. Only Matched says anything about the
+// page; the other two are reported so callers never mistake an unchecked envelope for a matching one.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "status", content = "content_hash", rename_all = "snake_case")]
+pub enum ContentBinding {
+    Matched(String),   // recomputed from the page and equal to the signed content_hash
+    Unchecked(String), // the signed content_hash, with no page given to compare it with
+    Unbound,           // the manifest has no content_hash; accepted only with allow_unbound_content
+}
+
+impl ContentBinding {
+    pub fn matched(&self) -> Option<&str> {
+        match self {
+            ContentBinding::Matched(hash) => Some(hash),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct VerificationResult {
+    pub public_key: String,
+    pub author_did: String, // did:key of public_key
+    pub algorithm: SignatureAlgorithm,
+    pub manifest: Value,
+    pub rotation_path: Vec<SignedRotation>, // empty when the document key is the trusted key itself
+    pub content: ContentBinding,
+    pub signers: Vec<VerifiedSigner>,       // primary signer first, then co-signers in manifest order
+    pub timestamp: Option<VerifiedTimestamp>,
+    pub transparency: Option<InclusionProof>, // verified against the manifest when present
+    pub c2pa: Option<C2paManifest>,           // the store's active manifest, once verified
+}
+
+// AIDEV-NOTE: Regex-based HTML parsing for manifest extraction - more robust than string matching.
+// Returns the byte range of the whole <script> element (what export.rs leaves out of content_hash)
+// and the JSON inside it.
+pub(crate) fn find_manifest_script(html: &str) -> Option<(Range<usize>, &str)> {
+    let manifest_regex = Regex::new(
+        r#"(?s)<script\s+type\s*=\s*["']application/json["']\s+id\s*=\s*["']sonnun-manifest["']\s*>(.*?)</script>"#,
+    )
+    .expect("manifest regex is valid");
+    let captures = manifest_regex.captures(html)?;
+    let element = captures.get(0)?;
+    Some((element.range(), captures.get(1)?.as_str()))
+}
+
+pub fn extract_signed_manifest(html: &str) -> Result<Value, SonnunError> {
+    let (_, manifest_json) =
+        find_manifest_script(html).ok_or_else(|| schema_error("No Sonnun manifest found in document"))?;
+    serde_json::from_str(manifest_json.trim())
+        .map_err(|e| SonnunError::ManifestSchema(format!("Invalid manifest JSON: {}", e)))
+}
+
+pub fn verify_html(html: &str, options: &VerifyOptions) -> Result<VerificationResult, SonnunError> {
+    let signed_manifest = extract_signed_manifest(html)?;
+
+    // AIDEV-NOTE: Manifest validation - ensure required fields exist before accessing
+    validate_manifest_structure(&signed_manifest)?;
+
+    let SignedManifest {
+        manifest,
+        signature: signature_b64,
+        public_key: public_key_b64,
+        signatures,
+        timestamp,
+        transparency,
+        jws,
+    } = serde_json::from_value(signed_manifest)
+        .map_err(|e| SonnunError::ManifestSchema(format!("Invalid signed manifest: {}", e)))?;
+    let public_key_b64 = public_key_b64.as_str();
+
+    // AIDEV-NOTE: The top-level pair is the primary signer older verifiers read. When a signatures
+    // array is present it must list that pair too, which gives the primary signer its role.
+    let primary_role = if signatures.is_empty() {
+        SignatureRole::Author
+    } else {
+        signatures
+            .iter()
+            .find(|entry| entry.public_key == public_key_b64 && entry.signature == signature_b64)
+            .map(|entry| entry.role)
+            .ok_or_else(|| schema_error("signatures does not include the top-level signature"))?
+    };
+
+    let signed_at = manifest_signed_at(&manifest)?;
+
+    // AIDEV-NOTE: A document signed by a newer key is accepted only through a rotation chain that
+    // starts at the trusted key and took effect before the manifest was generated
+    let trusted_key = options.trusted_key.as_deref().map(normalize_public_key).transpose()?;
+    let rotation_path = match &trusted_key {
+        Some(trusted_key) => {
+            find_rotation_chain(trusted_key, public_key_b64, &options.rotations, signed_at.as_ref())?
+        }
+        None => Vec::new(),
+    };
+
+    // AIDEV-NOTE: The manifest names the primary signature's algorithm; since that name is part of the
+    // signed bytes, relabelling an ed25519 manifest as P-256 (or back) breaks the signature
+    let algorithm = manifest_algorithm(&manifest)?;
+    let verifier = signature_verifier(algorithm, public_key_b64)?;
+
+    // AIDEV-NOTE: author_did is optional, but when present it must name the key that signed
+    if let Some(author_did) = manifest.get("author_did") {
+        let author_did = author_did
+            .as_str()
+            .ok_or_else(|| schema_error("author_did must be a string"))?;
+        if author_did.trim() != verifier.did_key() {
+            return Err(SonnunError::InvalidKey(format!(
+                "author_did {} does not match the document public key",
+                author_did
+            )));
+        }
+    }
+
+    // AIDEV-NOTE: Signatures cover the RFC 8785 form of the manifest. Ed25519 documents signed before that
+    // used serde_json's compact output, which is still accepted so existing exports keep verifying.
+    let canonical_manifest = manifest_signing_bytes(&manifest)?;
+    if !verifier.verify(&canonical_manifest, &signature_b64)? {
+        let legacy_manifest = serde_json::to_string(&manifest)
+            .map_err(|e| SonnunError::ManifestSchema(format!("Failed to serialize manifest: {}", e)))?;
+        if algorithm != SignatureAlgorithm::Ed25519 || !verifier.verify(legacy_manifest.as_bytes(), &signature_b64)? {
+            return Err(SonnunError::SignatureMismatch("Signature does not match manifest".to_string()));
+        }
+    }
+
+    // The detached JWS is EdDSA (see jws.rs), so it only accompanies ed25519 manifests
+    if let Some(jws) = &jws {
+        if algorithm != SignatureAlgorithm::Ed25519 {
+            return Err(schema_error("A detached JWS requires an ed25519 primary signature"));
+        }
+        verify_detached_jws(jws, &manifest, &parse_public_key(public_key_b64)?)?;
+    }
+
+    // Co-signatures postdate JCS signing, so they are only checked against the canonical bytes
+    let trusted_signers = trusted_signer_keys(options)?;
+    let mut signers = vec![VerifiedSigner {
+        role: primary_role,
+        public_key: public_key_b64.to_string(),
+        trusted: trusted_key.is_some() || trusted_signers.iter().any(|key| key == public_key_b64),
+    }];
+    for entry in &signatures {
+        if entry.public_key == public_key_b64 && entry.signature == signature_b64 {
+            continue;
+        }
+        let cosigner_key = parse_public_key(&entry.public_key)?;
+        verify_cosignature(&cosigner_key, &parse_signature(&entry.signature)?, &canonical_manifest, entry.role)?;
+        signers.push(VerifiedSigner {
+            role: entry.role,
+            public_key: entry.public_key.clone(),
+            trusted: trusted_signers.contains(&entry.public_key),
+        });
+    }
+
+    // AIDEV-NOTE: The signature covers content_hash, so recomputing it from the page proves the signed
+    // manifest belongs to this HTML and not to some other article it was copied from
+    let content = match manifest_content_hash(&manifest)? {
+        Some(expected) => {
+            let actual = document_content_hash(html)?;
+            if actual != expected {
+                return Err(SonnunError::ContentMismatch(
+                    "Document content does not match the signed content_hash".to_string(),
+                ));
+            }
+            ContentBinding::Matched(actual)
+        }
+        None if options.allow_unbound_content => ContentBinding::Unbound,
+        None => {
+            return Err(SonnunError::ContentMismatch(
+                "Manifest does not bind the document content (no content_hash)".to_string(),
+            ))
+        }
+    };
+
+    let timestamp = match timestamp {
+        Some(token) => {
+            let token = BASE64
+                .decode(token)
+                .map_err(|e| SonnunError::Timestamp(format!("Invalid timestamp encoding: {}", e)))?;
+            Some(validate_timestamp(&token, &decode_signature(&signature_b64)?, &options.tsa_certificates)?)
+        }
+        None => None,
+    };
+    let trusted_time = timestamp
+        .as_ref()
+        .filter(|timestamp| timestamp.trusted)
+        .map(|timestamp| DateTime::<FixedOffset>::from(timestamp.gen_time));
+    if options.require_timestamp && trusted_time.is_none() {
+        return Err(SonnunError::Timestamp(match timestamp {
+            Some(timestamp) => format!("Timestamp from {} is not from a trusted TSA", timestamp.tsa),
+            None => "Document has no timestamp".to_string(),
+        }));
+    }
+    if let (Some(stamped), Some(claimed)) = (&trusted_time, &signed_at) {
+        if *claimed > *stamped + Duration::minutes(MAX_CLOCK_SKEW_MINUTES) {
+            return Err(SonnunError::Timestamp(format!(
+                "Manifest claims generation at {} but was timestamped at {}",
+                claimed, stamped
+            )));
+        }
+    }
+
+    // AIDEV-NOTE: The inclusion proof ties these exact manifest bytes to a tree head the log signed;
+    // whether that log is the one the reader follows is up to log_key
+    if let Some(proof) = &transparency {
+        proof.verify(&canonical_manifest)?;
+    }
+    if let Some(log_key) = &options.log_key {
+        let log_key = normalize_public_key(log_key)?;
+        match &transparency {
+            Some(proof) if proof.tree_head.tree_head.log_key == log_key => {}
+            Some(_) => return Err(SonnunError::Transparency("Inclusion proof is from a different log".to_string())),
+            None => return Err(SonnunError::Transparency("Document is not in the transparency log".to_string())),
+        }
+    }
+
+    let c2pa = match &options.c2pa_manifest {
+        Some(store) => Some(verify_c2pa(store, public_key_b64, html)?),
+        None => None,
+    };
+
+    // AIDEV-NOTE: Revocation is checked last so a forged document still reports a signature failure.
+    let authorities = check_rotation_chain(trusted_key.as_deref(), &rotation_path, &options.revocations)?;
+    // A trusted timestamp is an upper bound on when the primary signature existed, so it replaces the
+    // signer's own generated_at there. It says nothing about co-signatures, which may come later.
+    let primary_signed_at = trusted_time.or(signed_at);
+    check_revocation(public_key_b64, primary_signed_at.as_ref(), &options.revocations, &authorities)?;
+    for signer in &signers[1..] {
+        check_revocation(&signer.public_key, signed_at.as_ref(), &options.revocations, &authorities)?;
+    }
+
+    // Policy comes after revocation: a revoked co-signer should be reported as revoked, not missing
+    check_required_signers(&signers, &options.required_signers)?;
+
+    Ok(VerificationResult {
+        public_key: public_key_b64.to_string(),
+        author_did: verifier.did_key(),
+        algorithm,
+        manifest,
+        rotation_path,
+        content,
+        signers,
+        timestamp,
+        transparency,
+        c2pa,
+    })
+}
+
+// AIDEV-NOTE: A C2PA manifest is accepted only when its signing certificate holds the page's primary
+// key and its hard binding covers these exact page bytes
+fn verify_c2pa(store: &[u8], public_key: &str, html: &str) -> Result<C2paManifest, SonnunError> {
+    let manifest = read_c2pa_manifest(store)?;
+    if manifest.public_key != public_key {
+        return Err(SonnunError::InvalidKey("C2PA manifest is not signed by the document key".to_string()));
+    }
+    if !manifest.binds(html.as_bytes()) {
+        return Err(SonnunError::ContentMismatch("C2PA manifest describes a different page".to_string()));
+    }
+    Ok(manifest)
+}
+
+// Every key on the rotation chain is checked at the time it handed over. Returns the keys whose
+// revocation lists count for the signing key.
+fn check_rotation_chain<'a>(
+    trusted_key: Option<&'a str>,
+    rotation_path: &'a [SignedRotation],
+    revocations: &[SignedRevocationList],
+) -> Result<Vec<&'a str>, SonnunError> {
+    let mut authorities: Vec<&str> = trusted_key.into_iter().collect();
+    for rotation in rotation_path {
+        let previous_key = rotation.statement.previous_key.as_str();
+        let handed_over = parse_timestamp(&rotation.statement.effective_date)?;
+        check_revocation(previous_key, Some(&handed_over), revocations, &authorities)?;
+        authorities.push(previous_key);
+    }
+    Ok(authorities)
+}
+
+// AIDEV-NOTE: Envelopes that travel without their page (credentials, COSE, JWS) share the checks below.
+// Timestamps, log proofs and C2PA manifests are tied to the HTML envelope, so asking for them is an error.
+pub(crate) fn reject_html_only_options(options: &VerifyOptions, format: &str) -> Result<(), SonnunError> {
+    if options.require_timestamp || options.log_key.is_some() || options.c2pa_manifest.is_some() {
+        return Err(SonnunError::InvalidInput(format!(
+            "Timestamps, transparency logs and C2PA manifests apply to HTML documents, not {}",
+            format
+        )));
+    }
+    Ok(())
+}
+
+// `page` is the published HTML. Without it the signed content_hash cannot be compared, which is only
+// accepted under allow_unbound_content and reported as Unchecked. A page exported by Sonnun still
+// carries its manifest script, which is never part of the hash.
+fn check_page_content(
+    expected: Option<&str>,
+    page: Option<&str>,
+    options: &VerifyOptions,
+) -> Result<ContentBinding, SonnunError> {
+    let expected = match expected {
+        Some(expected) => expected,
+        None if options.allow_unbound_content => return Ok(ContentBinding::Unbound),
+        None => {
+            return Err(SonnunError::ContentMismatch(
+                "Manifest does not bind the document content (no content_hash)".to_string(),
+            ))
+        }
+    };
+    let page = match page {
+        Some(page) => page,
+        None if options.allow_unbound_content => return Ok(ContentBinding::Unchecked(expected.to_string())),
+        None => {
+            return Err(SonnunError::ContentMismatch(
+                "No page to check the signed content_hash against".to_string(),
+            ))
+        }
+    };
+    let actual = match find_manifest_script(page) {
+        Some(_) => document_content_hash(page)?,
+        None => content_hash(page),
+    };
+    if actual != expected {
+        return Err(SonnunError::ContentMismatch("Page content does not match the signed content_hash".to_string()));
+    }
+    Ok(ContentBinding::Matched(actual))
+}
+
+fn manifest_content_hash(manifest: &Value) -> Result<Option<&str>, SonnunError> {
+    manifest
+        .get(CONTENT_HASH_FIELD)
+        .map(|hash| hash.as_str().ok_or_else(|| schema_error("content_hash must be a string")))
+        .transpose()
+}
+
+fn manifest_signed_at(manifest: &Value) -> Result<Option<DateTime<FixedOffset>>, SonnunError> {
+    match manifest.get("generated_at").and_then(Value::as_str) {
+        Some(generated_at) => Ok(Some(parse_timestamp(generated_at).map_err(|e| {
+            SonnunError::ManifestSchema(format!("Invalid generated_at: {}", e.message()))
+        })?)),
+        None => Ok(None),
+    }
+}
+
+// AIDEV-NOTE: Compact envelopes (COSE, JWS) name their key only by keystore fingerprint. author_did in
+// the manifest supplies the key when present; otherwise it must be the trusted key or one it rotated to.
+pub(crate) fn resolve_fingerprinted_key(
+    manifest: &Value,
+    key_id: &str,
+    options: &VerifyOptions,
+) -> Result<VerifyingKey, SonnunError> {
+    let mut candidates = Vec::new();
+    match manifest.get("author_did") {
+        Some(author_did) => candidates.push(parse_did_key(
+            author_did.as_str().ok_or_else(|| schema_error("author_did must be a string"))?,
+        )?),
+        None => {
+            if let Some(trusted_key) = &options.trusted_key {
+                candidates.push(parse_public_key(&normalize_public_key(trusted_key)?)?);
+            }
+            for rotation in &options.rotations {
+                candidates.push(parse_public_key(&rotation.statement.new_key)?);
+            }
+        }
+    }
+    candidates
+        .into_iter()
+        .find(|key| key_fingerprint(key.as_bytes()) == key_id)
+        .ok_or_else(|| {
+            SonnunError::InvalidKey(format!(
+                "Signed by key {}, which is neither author_did nor the trusted key or its rotations",
+                key_id
+            ))
+        })
+}
+
+// A bare manifest (COSE, JWS) carries its own content_hash and generated_at
+pub(crate) fn verify_sole_signer_manifest(
+    manifest: Value,
+    verifying_key: &VerifyingKey,
+    page: Option<&str>,
+    options: &VerifyOptions,
+) -> Result<VerificationResult, SonnunError> {
+    let content_hash = manifest_content_hash(&manifest)?.map(str::to_string);
+    let signed_at = manifest_signed_at(&manifest)?;
+    verify_sole_signer(manifest, content_hash.as_deref(), verifying_key, signed_at.as_ref(), page, options)
+}
+
+// AIDEV-NOTE: Everything after the signature check for an envelope whose only signer is its author
+// (credential, COSE, JWS): page content, then trust, revocation and policy as for the HTML envelope
+pub(crate) fn verify_sole_signer(
+    manifest: Value,
+    content_hash: Option<&str>,
+    verifying_key: &VerifyingKey,
+    signed_at: Option<&DateTime<FixedOffset>>,
+    page: Option<&str>,
+    options: &VerifyOptions,
+) -> Result<VerificationResult, SonnunError> {
+    // The envelope signature is always EdDSA, so the manifest must not claim the page was signed otherwise
+    let algorithm = manifest_algorithm(&manifest)?;
+    if algorithm != SignatureAlgorithm::Ed25519 {
+        return Err(schema_error(&format!(
+            "Manifest names {} but the envelope is signed with ed25519",
+            algorithm.as_str()
+        )));
+    }
+    let content = check_page_content(content_hash, page, options)?;
+
+    let public_key = BASE64.encode(verifying_key.to_bytes());
+    let trusted_key = options.trusted_key.as_deref().map(normalize_public_key).transpose()?;
+    let rotation_path = match &trusted_key {
+        Some(trusted_key) => find_rotation_chain(trusted_key, &public_key, &options.rotations, signed_at)?,
+        None => Vec::new(),
+    };
+    let authorities = check_rotation_chain(trusted_key.as_deref(), &rotation_path, &options.revocations)?;
+    check_revocation(&public_key, signed_at, &options.revocations, &authorities)?;
+    let signers = vec![VerifiedSigner {
+        role: SignatureRole::Author,
+        public_key: public_key.clone(),
+        trusted: trusted_key.is_some() || trusted_signer_keys(options)?.contains(&public_key),
+    }];
+    check_required_signers(&signers, &options.required_signers)?;
+
+    Ok(VerificationResult {
+        public_key,
+        author_did: did_key(verifying_key),
+        algorithm,
+        manifest,
+        rotation_path,
+        content,
+        signers,
+        timestamp: None,
+        transparency: None,
+        c2pa: None,
+    })
+}
+
+fn verify_cosignature(
+    key: &VerifyingKey,
+    signature: &Signature,
+    canonical_manifest: &[u8],
+    role: SignatureRole,
+) -> Result<(), SonnunError> {
+    key.verify(canonical_manifest, signature).map_err(|_| {
+        SonnunError::SignatureMismatch(format!("{} signature does not match manifest", role.as_str()))
+    })
+}
+
+fn trusted_signer_keys(options: &VerifyOptions) -> Result<Vec<String>, SonnunError> {
+    options.trusted_signers.iter().map(|key| normalize_public_key(key)).collect()
+}
+
+// AIDEV-NOTE: Anyone can append a signature entry claiming a role, so a role only counts when the
+// reader vouched for the key behind it: pinned in the clause, or trusted as a signer
+fn check_required_signers(signers: &[VerifiedSigner], required: &[RequiredSigner]) -> Result<(), SonnunError> {
+    for requirement in required {
+        let pinned_key = requirement.public_key.as_deref().map(normalize_public_key).transpose()?;
+        let satisfied = signers.iter().any(|signer| {
+            signer.role == requirement.role
+                && pinned_key.as_ref().map_or(signer.trusted, |key| *key == signer.public_key)
+        });
+        if !satisfied {
+            return Err(SonnunError::PolicyUnsatisfied(match pinned_key {
+                Some(key) => format!("No {} signature by {}", requirement.role.as_str(), key),
+                None => format!("No {} signature by a trusted key", requirement.role.as_str()),
+            }));
+        }
+    }
+    Ok(())
+}
+
+fn schema_error(message: &str) -> SonnunError {
+    SonnunError::ManifestSchema(message.to_string())
+}
+
+// AIDEV-NOTE: Validates manifest has required fields: manifest, signature, public_key
+fn validate_manifest_structure(signed_manifest: &Value) -> Result<(), SonnunError> {
+    if !signed_manifest.is_object() {
+        return Err(schema_error("Manifest must be a JSON object"));
+    }
+    for field in ["manifest", "signature", "public_key"] {
+        if signed_manifest.get(field).is_none() {
+            return Err(SonnunError::ManifestSchema(format!("Missing '{}' field in signed document", field)));
+        }
+    }
+
+    // AIDEV-NOTE: Validate inner manifest structure for provenance tracking. The app signs ManifestData
+    // (generate_manifest), which reports percentages; older exports carried character counts instead.
+    let manifest = &signed_manifest["manifest"];
+    if !manifest.is_object() {
+        return Err(schema_error("Inner manifest must be a JSON object"));
+    }
+    let missing = |fields: [&'static str; 4]| fields.into_iter().find(|field| manifest.get(*field).is_none());
+    let percentages = missing(["total_characters", "human_percentage", "ai_percentage", "cited_percentage"]);
+    let counts = missing(["total_characters", "human_characters", "ai_characters", "cited_characters"]);
+    match (percentages, counts) {
+        (Some(field), Some(_)) if manifest.get("human_percentage").is_some() => {
+            Err(SonnunError::ManifestSchema(format!("Missing '{}' field in manifest", field)))
+        }
+        (Some(_), Some(field)) => Err(SonnunError::ManifestSchema(format!("Missing '{}' field in manifest", field))),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keystore::test_keys::public;
+    use crate::export::{content_hash, export_signed_document_with, render_document};
+    use crate::key_formats::{did_key_p256, encode_public_key, PublicKeyFormat};
+    use crate::{ManifestSignature, SignedManifest};
+    use crate::revocation::{RevocationList, RevocationReason, RevokedKey};
+    use crate::rotation::RotationStatement;
+    use crate::test_manifest::manifest_data;
+    use crate::timestamp::test_tsa::TestTsa;
+    use ed25519_dalek::{Signer, SigningKey};
+    use rand::rngs::OsRng;
+    use serde_json::json;
+
+    fn manifest() -> Value {
+        json!({
+            "total_characters": 100,
+            "human_characters": 70,
+            "ai_characters": 20,
+            "cited_characters": 10,
+            "generated_at": "2025-03-01T12:00:00Z"
+        })
+    }
+
+    fn signed_html(key: &SigningKey, manifest: &Value) -> String {
+        let mut manifest = manifest.clone();
+        manifest[CONTENT_HASH_FIELD] = json!(content_hash(&render_document("Test", "<p>Hello</p>", None).unwrap()));
+        let signature = key.sign(&manifest_signing_bytes(&manifest).unwrap());
+        let signed = SignedManifest {
+            manifest,
+            signature: BASE64.encode(signature.to_bytes()),
+            public_key: public(key),
+            signatures: Vec::new(),
+            timestamp: None,
+            transparency: None,
+            jws: None,
+        };
+        render_document("Test", "<p>Hello</p>", Some(&signed)).unwrap()
+    }
+
+    fn cosigned_html(author: &SigningKey, editor: &SigningKey) -> String {
+        let mut manifest = manifest();
+        manifest[CONTENT_HASH_FIELD] = json!(content_hash(&render_document("Test", "<p>Hello</p>", None).unwrap()));
+        let bytes = manifest_signing_bytes(&manifest).unwrap();
+        let signatures: Vec<ManifestSignature> = [(SignatureRole::Author, author), (SignatureRole::Editor, editor)]
+            .into_iter()
+            .map(|(role, key)| ManifestSignature {
+                role,
+                public_key: public(key),
+                signature: BASE64.encode(key.sign(&bytes).to_bytes()),
+            })
+            .collect();
+        let signed = SignedManifest {
+            manifest,
+            signature: signatures[0].signature.clone(),
+            public_key: public(author),
+            signatures,
+            timestamp: None,
+            transparency: None,
+            jws: None,
+        };
+        render_document("Test", "<p>Hello</p>", Some(&signed)).unwrap()
+    }
+
+    fn requiring(policy: &[&str]) -> VerifyOptions {
+        VerifyOptions {
+            required_signers: policy.iter().map(|clause| clause.parse().unwrap()).collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_verifies_embedded_manifest() {
+        let key = SigningKey::generate(&mut OsRng);
+        let html = signed_html(&key, &manifest());
+
+        let result = verify_html(&html, &VerifyOptions::default()).unwrap();
+        assert_eq!(result.public_key, public(&key));
+        assert_eq!(result.manifest["human_characters"], 70);
+
+        let tampered = html.replace("\"human_characters\": 70", "\"human_characters\": 90");
+        assert_eq!(verify_html(&tampered, &VerifyOptions::default()).unwrap_err().code(), "SIGNATURE_MISMATCH");
+        assert_eq!(verify_html("<p>no manifest</p>", &VerifyOptions::default()).unwrap_err().code(), "MANIFEST_SCHEMA");
+    }
+
+    #[test]
+    fn test_unbound_manifest_needs_explicit_opt_in() {
+        let key = SigningKey::generate(&mut OsRng);
+        let manifest = manifest();
+        let signature = key.sign(serde_json::to_string(&manifest).unwrap().as_bytes());
+        let signed = SignedManifest {
+            manifest,
+            signature: BASE64.encode(signature.to_bytes()),
+            public_key: public(&key),
+            signatures: Vec::new(),
+            timestamp: None,
+            transparency: None,
+            jws: None,
+        };
+        let html = render_document("Legacy", "<p>Old export</p>", Some(&signed)).unwrap();
+
+        let error = verify_html(&html, &VerifyOptions::default()).unwrap_err();
+        assert_eq!(error.code(), "CONTENT_MISMATCH");
+        let options = VerifyOptions {
+            allow_unbound_content: true,
+            ..Default::default()
+        };
+        assert_eq!(verify_html(&html, &options).unwrap().content, ContentBinding::Unbound);
+    }
+
+    #[test]
+    fn test_author_did_must_match_public_key() {
+        let key = SigningKey::generate(&mut OsRng);
+        let other = SigningKey::generate(&mut OsRng);
+        let mut manifest = manifest();
+
+        manifest["author_did"] = json!(did_key(&key.verifying_key()));
+        let result = verify_html(&signed_html(&key, &manifest), &VerifyOptions::default()).unwrap();
+        assert_eq!(result.author_did, did_key(&key.verifying_key()));
+
+        manifest["author_did"] = json!(did_key(&other.verifying_key()));
+        let error = verify_html(&signed_html(&key, &manifest), &VerifyOptions::default()).unwrap_err();
+        assert_eq!(error.code(), "INVALID_KEY");
+    }
+
+    #[test]
+    fn test_p256_manifest_verifies_under_its_named_algorithm() {
+        let key = p256::ecdsa::SigningKey::random(&mut OsRng);
+        let did = did_key_p256(key.verifying_key());
+        let html = export_signed_document_with(&key, "Test", "<p>Hello</p>", manifest_data()).unwrap();
+        let options = VerifyOptions {
+            trusted_key: Some(did.clone()),
+            ..Default::default()
+        };
+        let result = verify_html(&html, &options).unwrap();
+        assert_eq!((result.algorithm, result.author_did), (SignatureAlgorithm::EcdsaP256, did));
+
+        // The algorithm is signed along with the rest, so relabelling it cannot pass
+        let relabelled = html.replace(r#""signature_algorithm": "ecdsa-p256""#, r#""signature_algorithm": "ed25519""#);
+        assert_ne!(relabelled, html);
+        assert_eq!(verify_html(&relabelled, &options).unwrap_err().code(), "INVALID_KEY");
+    }
+
+    #[test]
+    fn test_trusted_key_accepts_any_public_form() {
+        let key = SigningKey::generate(&mut OsRng);
+        let html = signed_html(&key, &manifest());
+        for format in [PublicKeyFormat::Raw, PublicKeyFormat::ProvPk, PublicKeyFormat::SpkiPem, PublicKeyFormat::DidKey] {
+            let options = VerifyOptions {
+                trusted_key: Some(encode_public_key(&key.verifying_key(), format).unwrap()),
+                ..Default::default()
+            };
+            assert!(verify_html(&html, &options).is_ok());
+        }
+    }
+
+    #[test]
+    fn test_rotated_key_is_trusted_through_chain() {
+        let old = SigningKey::generate(&mut OsRng);
+        let new = SigningKey::generate(&mut OsRng);
+        let html = signed_html(&new, &manifest());
+        let rotation = RotationStatement::new(&public(&old), &public(&new), "2025-01-01T00:00:00Z")
+            .unwrap()
+            .sign(&old)
+            .unwrap();
+
+        let without_chain = VerifyOptions {
+            trusted_key: Some(public(&old)),
+            ..Default::default()
+        };
+        assert_eq!(verify_html(&html, &without_chain).unwrap_err().code(), "INVALID_KEY");
+
+        let with_chain = VerifyOptions {
+            trusted_key: Some(public(&old)),
+            rotations: vec![rotation],
+            ..Default::default()
+        };
+        let result = verify_html(&html, &with_chain).unwrap();
+        assert_eq!(result.rotation_path.len(), 1);
+    }
+
+    #[test]
+    fn test_rotation_after_signing_time_does_not_cover_document() {
+        let old = SigningKey::generate(&mut OsRng);
+        let new = SigningKey::generate(&mut OsRng);
+        let html = signed_html(&new, &manifest());
+        let rotation = RotationStatement::new(&public(&old), &public(&new), "2025-06-01T00:00:00Z")
+            .unwrap()
+            .sign(&old)
+            .unwrap();
+
+        let options = VerifyOptions {
+            trusted_key: Some(public(&old)),
+            rotations: vec![rotation],
+            ..Default::default()
+        };
+        assert_eq!(verify_html(&html, &options).unwrap_err().code(), "INVALID_KEY");
+    }
+
+    #[test]
+    fn test_revoked_key_is_rejected() {
+        let old = SigningKey::generate(&mut OsRng);
+        let new = SigningKey::generate(&mut OsRng);
+        let html = signed_html(&new, &manifest());
+        let rotation = RotationStatement::new(&public(&old), &public(&new), "2025-01-01T00:00:00Z")
+            .unwrap()
+            .sign(&old)
+            .unwrap();
+        // The lost key is revoked by the trusted key it was rotated from
+        let entry = RevokedKey {
+            public_key: public(&new),
+            reason: RevocationReason::KeyCompromise,
+            revoked_at: "2025-04-01T00:00:00Z".to_string(),
+        };
+        let revocation = RevocationList::new(&public(&old), vec![entry]).unwrap().sign(&old).unwrap();
+
+        let mut options = VerifyOptions {
+            trusted_key: Some(public(&old)),
+            rotations: vec![rotation],
+            ..Default::default()
+        };
+        assert!(verify_html(&html, &options).is_ok());
+        options.revocations.push(revocation);
+        let error = verify_html(&html, &options).unwrap_err();
+        assert_eq!(error.code(), "KEY_REVOKED");
+        assert_eq!(error.exit_code(), 11);
+    }
+
+    #[test]
+    fn test_role_policy_over_cosignatures() {
+        let author = SigningKey::generate(&mut OsRng);
+        let editor = SigningKey::generate(&mut OsRng);
+        let html = cosigned_html(&author, &editor);
+
+        // A bare role is only the signer's own claim until the reader trusts its key
+        let error = verify_html(&html, &requiring(&["editor"])).unwrap_err();
+        assert_eq!(error.code(), "POLICY_UNSATISFIED");
+        assert_eq!(error.exit_code(), 13);
+        let untrusted = verify_html(&html, &VerifyOptions::default()).unwrap();
+        assert!(untrusted.signers.iter().all(|signer| !signer.trusted));
+
+        let trusting = |policy: &[&str]| VerifyOptions {
+            trusted_key: Some(public(&author)),
+            trusted_signers: vec![did_key(&editor.verifying_key())],
+            ..requiring(policy)
+        };
+        let result = verify_html(&html, &trusting(&["author", "editor"])).unwrap();
+        let roles: Vec<SignatureRole> = result.signers.iter().map(|signer| signer.role).collect();
+        assert_eq!(roles, [SignatureRole::Author, SignatureRole::Editor]);
+        assert!(result.signers.iter().all(|signer| signer.trusted));
+        assert_eq!(verify_html(&html, &trusting(&["author", "publisher"])).unwrap_err().code(), "POLICY_UNSATISFIED");
+
+        // A key trusted as a signer does not vouch for roles other keys claim
+        let forger = SigningKey::generate(&mut OsRng);
+        let forged = cosigned_html(&author, &forger);
+        assert_eq!(verify_html(&forged, &trusting(&["editor"])).unwrap_err().code(), "POLICY_UNSATISFIED");
+
+        // A pinned key has to be the one that signed in that role
+        let pinned = format!("editor={}", did_key(&editor.verifying_key()));
+        assert!(verify_html(&html, &requiring(&[&pinned])).is_ok());
+        let wrong = format!("editor={}", public(&author));
+        assert_eq!(verify_html(&html, &requiring(&[&wrong])).unwrap_err().code(), "POLICY_UNSATISFIED");
+        assert!("reviewer".parse::<RequiredSigner>().is_err());
+    }
+
+    #[test]
+    fn test_forged_cosignature_is_rejected() {
+        let author = SigningKey::generate(&mut OsRng);
+        let editor = SigningKey::generate(&mut OsRng);
+        let html = cosigned_html(&author, &editor);
+        let mut signed: SignedManifest = serde_json::from_value(extract_signed_manifest(&html).unwrap()).unwrap();
+
+        // Claiming the editor's key with a signature someone else made
+        let forged = SigningKey::generate(&mut OsRng).sign(&manifest_signing_bytes(&signed.manifest).unwrap());
+        signed.signatures[1].signature = BASE64.encode(forged.to_bytes());
+        let html = render_document("Test", "<p>Hello</p>", Some(&signed)).unwrap();
+        assert_eq!(verify_html(&html, &VerifyOptions::default()).unwrap_err().code(), "SIGNATURE_MISMATCH");
+
+        // The top-level signer must appear in the signatures array
+        signed.signatures.remove(0);
+        let html = render_document("Test", "<p>Hello</p>", Some(&signed)).unwrap();
+        assert_eq!(verify_html(&html, &VerifyOptions::default()).unwrap_err().code(), "MANIFEST_SCHEMA");
+    }
+
+    fn timestamped_html(key: &SigningKey, tsa: &TestTsa, gen_time: &str) -> String {
+        let html = signed_html(key, &manifest());
+        let mut signed: SignedManifest = serde_json::from_value(extract_signed_manifest(&html).unwrap()).unwrap();
+        let signature = BASE64.decode(&signed.signature).unwrap();
+        signed.timestamp = Some(BASE64.encode(tsa.token(&signature, None, gen_time.parse().unwrap())));
+        render_document("Test", "<p>Hello</p>", Some(&signed)).unwrap()
+    }
+
+    #[test]
+    fn test_trusted_timestamp_defeats_backdating() {
+        let key = SigningKey::generate(&mut OsRng);
+        let tsa = TestTsa::new("Test TSA");
+        // The manifest claims 2025-03-01, but the TSA saw the signature only after the key was retired
+        let html = timestamped_html(&key, &tsa, "2025-07-01T00:00:00Z");
+        let entry = RevokedKey {
+            public_key: public(&key),
+            reason: RevocationReason::Superseded,
+            revoked_at: "2025-06-01T00:00:00Z".to_string(),
+        };
+        let retired = RevocationList::new(&public(&key), vec![entry]).unwrap().sign(&key).unwrap();
+
+        let mut options = VerifyOptions {
+            revocations: vec![retired],
+            ..Default::default()
+        };
+        let result = verify_html(&html, &options).unwrap();
+        assert!(!result.timestamp.unwrap().trusted);
+
+        options.require_timestamp = true;
+        let error = verify_html(&html, &options).unwrap_err();
+        assert_eq!((error.code(), error.exit_code()), ("TIMESTAMP", 14));
+
+        options.tsa_certificates = vec![tsa.certificate.clone()];
+        assert_eq!(verify_html(&html, &options).unwrap_err().code(), "KEY_REVOKED");
+    }
+
+    #[test]
+    fn test_generated_at_after_trusted_timestamp_is_rejected() {
+        let key = SigningKey::generate(&mut OsRng);
+        let tsa = TestTsa::new("Test TSA");
+        let options = VerifyOptions {
+            tsa_certificates: vec![tsa.certificate.clone()],
+            require_timestamp: true,
+            ..Default::default()
+        };
+        let stamped = verify_html(&timestamped_html(&key, &tsa, "2025-03-01T12:01:00Z"), &options).unwrap();
+        assert!(stamped.timestamp.unwrap().trusted);
+
+        let error = verify_html(&timestamped_html(&key, &tsa, "2025-01-01T00:00:00Z"), &options).unwrap_err();
+        assert_eq!(error.code(), "TIMESTAMP");
+    }
+}
@@ -1,5 +1,6 @@
-import React, { useState, useCallback, useRef } from 'react'
-import EditorPane from './components/EditorPane'
+import React, { useState, useCallback, useRef, useEffect } from 'react'
+import { invoke } from '@tauri-apps/api/core'
+import EditorPane, { type EditorPaneHandle } from './components/EditorPane'
 import AssistantPanel from './components/AssistantPanel'
 import ProvenanceLegend from './components/ProvenanceLegend'
 import './App.css'
@@ -19,15 +20,21 @@ const App: React.FC = () => {
     totalCharacters: 0,
   })
   const [isAssistantOpen, setIsAssistantOpen] = useState(false)
+  const [documentId, setDocumentId] = useState<number | null>(null)
   // AIDEV-TODO: Consider using context or state management lib for complex component communication
   const [skipEditorUpdateFunction, setSkipEditorUpdateFunction] = useState<(() => void) | null>(
     null
   )
 
   // AIDEV-NOTE: Component communication - allows AssistantPanel to insert AI content into editor
-  const editorRef = useRef<{ insertAIContent: (content: string, model: string) => void } | null>(
-    null
-  )
+  const editorRef = useRef<EditorPaneHandle | null>(null)
+
+  // AIDEV-NOTE: Provenance events are scoped per document - open one before the editor logs anything
+  useEffect(() => {
+    invoke<{ id: number }>('create_document', { title: 'Untitled' })
+      .then((document) => setDocumentId(document.id))
+      .catch((error) => console.error('Failed to create document:', error))
+  }, [])
 
   // AIDEV-NOTE: State orchestration - manages document content and provenance flow between components
   const handleInsertAIText = useCallback(
@@ -59,6 +66,7 @@ const App: React.FC = () => {
       <main className="app-main">
         <EditorPane
           ref={editorRef}
+          documentId={documentId}
           onProvenanceChange={setProvenanceStats}
           className="main-editor"
           onReady={(_editor, setSkipFlag) => {
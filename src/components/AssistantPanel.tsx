@@ -1,5 +1,6 @@
 import React, { useState, useCallback, useRef, useEffect } from 'react'
 import { invoke } from '@tauri-apps/api/core'
+import { errorMessage } from '../utils/errors'
 
 interface AssistantPanelProps {
   onInsertText: (text: string, source: string) => void
@@ -95,13 +96,13 @@ const AssistantPanel: React.FC<AssistantPanelProps> = ({
 
       setMessages((prev) => [...prev, assistantMessage])
     } catch (err) {
-      const errorMessage = err instanceof Error ? err.message : String(err)
-      setError(errorMessage)
+      const message = errorMessage(err)
+      setError(message)
 
       // Add error message to chat
       const errorChatMessage: Message = {
         role: 'assistant',
-        content: `Error: ${errorMessage}`,
+        content: `Error: ${message}`,
         timestamp: new Date(),
       }
       setMessages((prev) => [...prev, errorChatMessage])
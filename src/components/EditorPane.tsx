@@ -9,30 +9,86 @@ import React, {
 import { useEditor, EditorContent, Editor } from '@tiptap/react'
 import StarterKit from '@tiptap/starter-kit'
 import Link from '@tiptap/extension-link'
-import { Extension } from '@tiptap/core'
+import { Extension, getTextBetween, getTextSerializersFromSchema } from '@tiptap/core'
 import { ProvenanceMark } from '../extensions/ProvenanceMark'
 import { Plugin, PluginKey } from 'prosemirror-state'
 import { invoke } from '@tauri-apps/api/core'
 import CitationModal from './CitationModal'
 import { diffChars, Change } from 'diff'
-import type { ProvenanceStats } from '../utils/manifestGenerator'
+import type { EventType, Operation, ProvenanceStats } from '../utils/manifestGenerator'
 
 interface EditorPaneProps {
+  documentId: number | null
   onContentChange?: (content: string) => void
   onProvenanceChange?: (stats: ProvenanceStats) => void
   className?: string
   onReady?: (editor: Editor, setSkipFlag: () => void) => void
 }
 
+interface TextEdit {
+  operation: Operation
+  from: number
+  to: number
+  text: string // Inserted text, or the removed text for a delete
+}
+
 // Helper function for Step 3
-// AIDEV-NOTE: perf-sensitive - diff new vs old to detect human input
-const findInsertedText = (oldText: string, newText: string): string => {
-  const diff = diffChars(oldText, newText)
-  let inserted = ''
-  diff.forEach((part: Change) => {
-    if (part.added) inserted += part.value
+// AIDEV-NOTE: perf-sensitive - diff old vs new plain text into positioned edits. Each edit's offsets
+// are relative to the text as the previous edits left it, matching how the backend replays them.
+const diffToEdits = (oldText: string, newText: string): TextEdit[] => {
+  const edits: TextEdit[] = []
+  let pos = 0
+  let previous: Change | null = null
+  diffChars(oldText, newText).forEach((part: Change) => {
+    if (part.removed) {
+      edits.push({ operation: 'delete', from: pos, to: pos + part.value.length, text: part.value })
+    } else if (part.added) {
+      const last = edits[edits.length - 1]
+      if (previous?.removed && last) {
+        edits[edits.length - 1] = { ...last, operation: 'replace', text: part.value }
+      } else {
+        edits.push({ operation: 'insert', from: pos, to: pos, text: part.value })
+      }
+      pos += part.value.length
+    } else {
+      pos += part.value.length
+    }
+    previous = part
+  })
+  return edits
+}
+
+// AIDEV-NOTE: Converts a ProseMirror position to an offset into editor.getText()
+const textOffset = (editor: Editor, pos: number): number =>
+  getTextBetween(
+    editor.state.doc,
+    { from: 0, to: pos },
+    { textSerializers: getTextSerializersFromSchema(editor.schema) }
+  ).length
+
+// AIDEV-NOTE: Characters per event type, in editor.getText() terms as the backend span model counts them.
+// Marked text counts under its mark; everything else - unmarked text and block separators - was typed
+// and logged as human.
+const provenanceCounts = (editor: Editor): Partial<Record<EventType, number>> => {
+  const counts: Partial<Record<EventType, number>> = {}
+  let marked = 0
+  editor.state.doc.descendants((node) => {
+    if (!node.isText || !node.text) return
+    const type = node.marks.find((mark) => mark.type.name === ProvenanceMark.name)?.attrs.type as
+      | EventType
+      | undefined
+    if (type && type !== 'human') {
+      counts[type] = (counts[type] ?? 0) + node.text.length
+      marked += node.text.length
+    }
   })
-  return inserted
+  counts.human = editor.getText().length - marked
+  return counts
+}
+
+export interface EditorPaneHandle {
+  insertAIContent: (content: string, model: string) => void
+  exportSignedHtml: (keyId: string, title: string) => Promise<string>
 }
 
 interface CitationModalState {
@@ -41,12 +97,10 @@ interface CitationModalState {
   insertPosition?: number
 }
 
-const EditorPane = forwardRef<
-  { insertAIContent: (content: string, model: string) => void },
-  EditorPaneProps
->(
+const EditorPane = forwardRef<EditorPaneHandle, EditorPaneProps>(
   (
     {
+      documentId,
       onContentChange,
       onProvenanceChange,
       className = '',
@@ -57,6 +111,9 @@ const EditorPane = forwardRef<
     // AIDEV-NOTE: Counter handles overlapping async insertions
     const lastContentRef = useRef<string>('')
     const skipUpdateCountRef = useRef<number>(0)
+    // AIDEV-NOTE: Offsets only make sense applied in order, so events are sent one at a time
+    const logQueueRef = useRef<Promise<void>>(Promise.resolve())
+    const baselineDocumentRef = useRef<number | null>(null)
 
     const [citationModal, setCitationModal] = useState<CitationModalState>({
       isOpen: false,
@@ -108,22 +165,28 @@ const EditorPane = forwardRef<
 
     // AIDEV-NOTE: Critical path - logs all content changes to SQLite via Tauri for audit trail
     const logProvenanceEvent = useCallback(
-      async (eventType: string, text: string, source: string, spanLength: number) => {
-        try {
-          await invoke('log_provenance_event', {
-            event: {
-              timestamp: new Date().toISOString(),
-              event_type: eventType,
-              text,
-              source,
-              span_length: spanLength,
-            },
-          })
-        } catch (error) {
-          console.error('Failed to log provenance event:', error)
+      (eventType: EventType, edit: TextEdit, source: string) => {
+        if (documentId === null) return
+        const event = {
+          document_id: documentId,
+          timestamp: new Date().toISOString(),
+          event_type: eventType,
+          text: edit.text,
+          source,
+          span_length: edit.operation === 'delete' ? edit.to - edit.from : edit.text.length,
+          operation: edit.operation,
+          from: edit.from,
+          to: edit.operation === 'insert' ? null : edit.to,
         }
+        logQueueRef.current = logQueueRef.current.then(async () => {
+          try {
+            await invoke('log_provenance_event', { event })
+          } catch (error) {
+            console.error('Failed to log provenance event:', error)
+          }
+        })
       },
-      []
+      [documentId]
     )
 
     const editor = useEditor({
@@ -157,11 +220,7 @@ const EditorPane = forwardRef<
                       return true
                     }
 
-                    // Small pastes default to human content
-                    if (text && text.length <= 10) {
-                      void logProvenanceEvent('human', text, 'user', text.length)
-                    }
-
+                    // Small pastes default to human content and are logged by the update diff
                     return false
                   },
                 },
@@ -193,6 +252,25 @@ const EditorPane = forwardRef<
         // Initialize lastContentRef with current editor text
         lastContentRef.current = editor.getText()
 
+        // Whatever the editor holds when a document opens is the baseline later offsets build on
+        if (documentId !== null && baselineDocumentRef.current !== documentId) {
+          baselineDocumentRef.current = documentId
+          if (lastContentRef.current.length > 0) {
+            logProvenanceEvent(
+              'imported',
+              { operation: 'insert', from: 0, to: 0, text: lastContentRef.current },
+              'initial-content'
+            )
+            // Marked so the counts reported at signing time attribute it the way the log does
+            const { state } = editor
+            const mark = state.schema.marks[ProvenanceMark.name].create({
+              source: 'initial-content',
+              type: 'imported',
+            })
+            editor.view.dispatch(state.tr.addMark(0, state.doc.content.size, mark))
+          }
+        }
+
         // AIDEV-TODO: Add cleanup to prevent memory leaks on component unmount
 
         const handleEditorUpdate = () => {
@@ -209,13 +287,10 @@ const EditorPane = forwardRef<
           const currentText = editor.getText()
           const lastText = lastContentRef.current
 
-          if (currentText.length > lastText.length) {
-            const insertedText = findInsertedText(lastText, currentText)
-            if (insertedText.trim().length > 0) {
-              // console.log('Human typed:', insertedText);
-              void logProvenanceEvent('human', insertedText, 'user', insertedText.length)
-            }
-          }
+          // Deletions matter as much as insertions - the manifest only counts surviving text
+          diffToEdits(lastText, currentText).forEach((edit) => {
+            logProvenanceEvent('human', edit, 'user')
+          })
           lastContentRef.current = currentText
         }
 
@@ -233,7 +308,7 @@ const EditorPane = forwardRef<
           editor.off('update', handleEditorUpdate)
         }
       }
-    }, [editor, onReady, logProvenanceEvent]) // Added dependencies
+    }, [editor, onReady, logProvenanceEvent, documentId]) // Added dependencies
 
     const handleCitationConfirm = useCallback(
       (citation: string) => {
@@ -245,6 +320,7 @@ const EditorPane = forwardRef<
         const { pastedText, insertPosition } = citationModal
         const currentPosition = editor.state.selection.from
         const resolvedInsertPos = insertPosition !== undefined ? insertPosition : currentPosition
+        const insertOffset = textOffset(editor, resolvedInsertPos)
 
         // Insert content with provenance mark
         // For compatibility with existing `calculateProvenanceStats`, we ensure `type: 'cited'` is set.
@@ -257,6 +333,13 @@ const EditorPane = forwardRef<
           .setMark(ProvenanceMark.name, { source: citation, type: 'cited' })
           .run()
 
+        // Queued before the suffix below, whose update is diffed and logged at an offset past this insert
+        logProvenanceEvent(
+          'cited',
+          { operation: 'insert', from: insertOffset, to: insertOffset, text: pastedText },
+          citation
+        )
+
         const isURL = /^(ftp|http|https):\/\/[^ "]+$/.test(citation)
         const insertedTextStartPos = resolvedInsertPos
         const insertedTextEndPos = resolvedInsertPos + pastedText.length
@@ -275,8 +358,6 @@ const EditorPane = forwardRef<
           editor.chain().focus().insertContentAt(insertedTextEndPos, ` (source: ${citation})`).run()
         }
 
-        void logProvenanceEvent('cited', pastedText, citation, pastedText.length)
-
         setCitationModal({ isOpen: false, pastedText: '', insertPosition: undefined })
       },
       [editor, citationModal, logProvenanceEvent]
@@ -292,6 +373,11 @@ const EditorPane = forwardRef<
       (content: string, model: string) => {
         if (!editor) return
 
+        // insertContent replaces any selected text, so the AI span may be a replace
+        const { from, to } = editor.state.selection
+        const fromOffset = textOffset(editor, from)
+        const toOffset = textOffset(editor, to)
+
         editor.commands.insertContent({
           type: 'text',
           text: content,
@@ -306,18 +392,48 @@ const EditorPane = forwardRef<
           ],
         })
 
-        void logProvenanceEvent('ai', content, model, content.length)
+        logProvenanceEvent(
+          'ai',
+          {
+            operation: fromOffset === toOffset ? 'insert' : 'replace',
+            from: fromOffset,
+            to: toOffset,
+            text: content,
+          },
+          model
+        )
       },
       [editor, logProvenanceEvent]
     )
 
-    // AIDEV-NOTE: Expose insertAIContent method to parent via ref for component communication
+    // AIDEV-NOTE: The backend generates and signs the manifest itself. The counts this editor shows go
+    // along with it, and the export is refused if they disagree with the event log.
+    const exportSignedHtml = useCallback(
+      async (keyId: string, title: string): Promise<string> => {
+        if (!editor || documentId === null) {
+          throw new Error('No document is open')
+        }
+        // Every edit must be logged before the counts are compared
+        await logQueueRef.current
+        return invoke<string>('export_signed_html', {
+          keyId,
+          documentId,
+          reportedCounts: provenanceCounts(editor),
+          title,
+          contentHtml: editor.getHTML(),
+        })
+      },
+      [editor, documentId]
+    )
+
+    // AIDEV-NOTE: Expose editor actions to parent via ref for component communication
     useImperativeHandle(
       ref,
       () => ({
         insertAIContent,
+        exportSignedHtml,
       }),
-      [insertAIContent]
+      [insertAIContent, exportSignedHtml]
     )
 
     return (
@@ -19,6 +19,9 @@ declare module '@tiptap/core' {
 export const ProvenanceMark = Mark.create<ProvenanceMarkOptions>({
   name: 'provenanceMark',
 
+  // Typing at the edge of AI or cited text is logged as human, so it must not pick up the mark
+  inclusive: false,
+
   addOptions() {
     return {
       HTMLAttributes: {},
@@ -0,0 +1,21 @@
+// AIDEV-NOTE: Mirrors the Rust SonnunError - commands reject with { code, message }
+
+export interface SonnunError {
+  code: string
+  message: string
+}
+
+export function isSonnunError(err: unknown): err is SonnunError {
+  if (err === null || typeof err !== 'object') {
+    return false
+  }
+
+  const e = err as Record<string, unknown>
+  return typeof e.code === 'string' && typeof e.message === 'string'
+}
+
+export function errorMessage(err: unknown): string {
+  if (isSonnunError(err)) return err.message
+  if (err instanceof Error) return err.message
+  return String(err)
+}
@@ -7,12 +7,33 @@ export interface ProvenanceStats {
   totalCharacters: number
 }
 
+// AIDEV-NOTE: Mirrors the Rust EventType enum - the backend rejects anything outside this list
+export const EVENT_TYPES = [
+  'human',
+  'ai',
+  'cited',
+  'ai_edited',
+  'translated',
+  'tool_assisted',
+  'imported',
+] as const
+
+export type EventType = (typeof EVENT_TYPES)[number]
+
+// AIDEV-NOTE: Mirrors the Rust Operation enum - offsets are into editor.getText() at the time of the edit
+export const OPERATIONS = ['insert', 'delete', 'replace'] as const
+
+export type Operation = (typeof OPERATIONS)[number]
+
 export interface ProvenanceEvent {
   timestamp: string
-  event_type: 'human' | 'ai' | 'cited'
+  event_type: EventType
   text_hash: string
   source: string
   span_length: number
+  operation?: Operation
+  from?: number | null
+  to?: number | null
 }
 
 export interface ManifestData {
@@ -23,6 +44,7 @@ export interface ManifestData {
   events: ProvenanceEvent[]
   generated_at: string
   document_hash: string
+  author_did?: string // did:key of the signing key, checked by sonnun-verify
 }
 
 // AIDEV-NOTE: Core calculation engine for real-time provenance statistics
@@ -112,13 +134,22 @@ export function validateProvenanceEvent(event: Partial<ProvenanceEvent>): string
   const errors: string[] = []
 
   if (!event.timestamp) errors.push('Timestamp is required')
-  if (!event.event_type || !['human', 'ai', 'cited'].includes(event.event_type)) {
-    errors.push('Event type must be human, ai, or cited')
+  if (!event.event_type || !EVENT_TYPES.includes(event.event_type)) {
+    errors.push(`Event type must be one of: ${EVENT_TYPES.join(', ')}`)
   }
   if (!event.source) errors.push('Source is required')
   if (typeof event.span_length !== 'number' || event.span_length < 0) {
     errors.push('Span length must be a non-negative number')
   }
+  if (event.operation !== undefined && !OPERATIONS.includes(event.operation)) {
+    errors.push(`Operation must be one of: ${OPERATIONS.join(', ')}`)
+  }
+  if (
+    (event.operation === 'delete' || event.operation === 'replace') &&
+    (typeof event.from !== 'number' || typeof event.to !== 'number' || event.from > event.to)
+  ) {
+    errors.push(`A ${event.operation} needs a valid from/to range`)
+  }
 
   return errors
 }